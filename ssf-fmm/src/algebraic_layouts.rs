@@ -0,0 +1,40 @@
+use crate::types;
+
+/// Binary representations of algebraic data types on 64-bit targets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AlgebraicLayout {
+    /// Records of payloads of the only constructors. Records are empty if the
+    /// constructors are enum ones.
+    Untagged,
+    /// Records of integer tags followed by unions of constructor payloads.
+    /// Unions are omitted if all constructors are enum ones.
+    Tagged { tag_size: usize },
+    /// Records of unions of pointers to payloads of boxed constructors which
+    /// are null for enum constructors.
+    NullPointerNiche { enum_tag: u64, boxed_tag: u64 },
+    /// Pointers to payloads of boxed constructors plus indices of the
+    /// constructors in the order of their tags. Enum constructors are
+    /// represented by their indices alone.
+    PointerTagged { tag_bits: usize },
+}
+
+/// Gets a binary representation of an algebraic data type so that foreign
+/// code can read and write its values.
+pub fn get_algebraic_layout(algebraic: &ssf::types::Algebraic) -> AlgebraicLayout {
+    if types::is_pointer_tagged(algebraic) {
+        AlgebraicLayout::PointerTagged {
+            tag_bits: types::POINTER_TAG_BITS,
+        }
+    } else if let Some((enum_tag, boxed_tag)) = types::get_null_pointer_niche(algebraic) {
+        AlgebraicLayout::NullPointerNiche {
+            enum_tag,
+            boxed_tag,
+        }
+    } else if types::is_tagged(algebraic) {
+        AlgebraicLayout::Tagged {
+            tag_size: types::get_primitive_size(types::compile_tag(algebraic)),
+        }
+    } else {
+        AlgebraicLayout::Untagged
+    }
+}
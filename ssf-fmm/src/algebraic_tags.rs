@@ -0,0 +1,250 @@
+use crate::{error::CompileError, types};
+use std::collections::{BTreeMap, HashSet};
+
+// Tags of algebraic data types are not represented in their fmm types. So we
+// map the fmm types to tags of their union members in advance. Tags mapped to
+// different members by different types are rejected unless the types contain
+// no pointers to describe or drop.
+pub fn collect_algebraic_tags(
+    algebraic_types: &[ssf::types::Algebraic],
+) -> Result<Vec<(fmm::types::Record, BTreeMap<u64, usize>)>, CompileError> {
+    let mut algebraic_tags = Vec::<(fmm::types::Record, BTreeMap<u64, usize>)>::new();
+
+    for algebraic_type in algebraic_types {
+        if !types::is_tagged(algebraic_type) {
+            continue;
+        }
+
+        let record = types::compile_algebraic(algebraic_type);
+
+        if types::is_pointer_free(&record.clone().into()) {
+            continue;
+        }
+
+        let index = if let Some(index) = algebraic_tags
+            .iter()
+            .position(|(known_record, _)| known_record == &record)
+        {
+            index
+        } else {
+            algebraic_tags.push((record, Default::default()));
+            algebraic_tags.len() - 1
+        };
+        let tags = &mut algebraic_tags[index].1;
+
+        for (index, tag) in algebraic_type.constructors().keys().enumerate() {
+            if *tags.entry(*tag).or_insert(index) != index {
+                return Err(CompileError::AlgebraicTagConflict(algebraic_type.clone()));
+            }
+        }
+    }
+
+    Ok(algebraic_tags)
+}
+
+pub fn collect_algebraic_types(module: &ssf::ir::Module) -> Vec<ssf::types::Algebraic> {
+    let mut algebraic_types = HashSet::new();
+
+    for declaration in module.foreign_declarations() {
+        collect_from_type(&declaration.type_().clone().into(), &mut algebraic_types);
+    }
+
+    for declaration in module.declarations() {
+        collect_from_type(&declaration.type_().clone().into(), &mut algebraic_types);
+    }
+
+    for definition in module.definitions() {
+        collect_from_definition(definition, &mut algebraic_types);
+    }
+
+    algebraic_types.into_iter().collect()
+}
+
+fn collect_from_definition(
+    definition: &ssf::ir::Definition,
+    algebraic_types: &mut HashSet<ssf::types::Algebraic>,
+) {
+    collect_from_type(&definition.type_().clone().into(), algebraic_types);
+
+    for argument in definition.environment() {
+        collect_from_type(argument.type_(), algebraic_types);
+    }
+
+    collect_from_expression(definition.body(), algebraic_types);
+}
+
+fn collect_from_expression(
+    expression: &ssf::ir::Expression,
+    algebraic_types: &mut HashSet<ssf::types::Algebraic>,
+) {
+    match expression {
+        ssf::ir::Expression::ArithmeticOperation(operation) => {
+            collect_from_expression(operation.lhs(), algebraic_types);
+            collect_from_expression(operation.rhs(), algebraic_types);
+        }
+        ssf::ir::Expression::Array(array) => {
+            collect_from_type(&array.type_().clone().into(), algebraic_types);
+
+            for element in array.elements() {
+                collect_from_expression(element, algebraic_types);
+            }
+        }
+        ssf::ir::Expression::ArrayElement(element) => {
+            collect_from_type(&element.type_().clone().into(), algebraic_types);
+            collect_from_expression(element.array(), algebraic_types);
+            collect_from_expression(element.index(), algebraic_types);
+            collect_from_expression(element.default_element(), algebraic_types);
+        }
+        ssf::ir::Expression::ArrayInitialization(initialization) => {
+            collect_from_type(&initialization.type_().clone().into(), algebraic_types);
+            collect_from_expression(initialization.length(), algebraic_types);
+            collect_from_expression(initialization.function(), algebraic_types);
+        }
+        ssf::ir::Expression::ArrayLength(length) => {
+            collect_from_expression(length.array(), algebraic_types);
+        }
+        ssf::ir::Expression::BitCast(bit_cast) => {
+            collect_from_type(bit_cast.type_(), algebraic_types);
+            collect_from_expression(bit_cast.expression(), algebraic_types);
+        }
+        ssf::ir::Expression::Case(ssf::ir::Case::Algebraic(case)) => {
+            collect_from_expression(case.argument(), algebraic_types);
+
+            for alternative in case.alternatives() {
+                collect_from_type(
+                    &alternative.constructor().algebraic_type().clone().into(),
+                    algebraic_types,
+                );
+                collect_from_expression(alternative.expression(), algebraic_types);
+            }
+
+            if let Some(expression) = case.default_alternative() {
+                collect_from_expression(expression, algebraic_types);
+            }
+        }
+        ssf::ir::Expression::Case(ssf::ir::Case::Primitive(case)) => {
+            collect_from_expression(case.argument(), algebraic_types);
+
+            for alternative in case.alternatives() {
+                collect_from_expression(alternative.expression(), algebraic_types);
+            }
+
+            if let Some(expression) = case.default_alternative() {
+                collect_from_expression(expression, algebraic_types);
+            }
+        }
+        ssf::ir::Expression::ComparisonOperation(operation) => {
+            collect_from_expression(operation.lhs(), algebraic_types);
+            collect_from_expression(operation.rhs(), algebraic_types);
+        }
+        ssf::ir::Expression::ConstructorApplication(application) => {
+            collect_from_type(
+                &application.constructor().algebraic_type().clone().into(),
+                algebraic_types,
+            );
+
+            for argument in application.arguments() {
+                collect_from_expression(argument, algebraic_types);
+            }
+        }
+        ssf::ir::Expression::ConstructorField(field) => {
+            collect_from_type(
+                &field.constructor().algebraic_type().clone().into(),
+                algebraic_types,
+            );
+            collect_from_expression(field.argument(), algebraic_types);
+        }
+        ssf::ir::Expression::Delay(delay) => {
+            collect_from_definition(delay.definition(), algebraic_types);
+        }
+        ssf::ir::Expression::Force(force) => {
+            collect_from_expression(force.expression(), algebraic_types);
+        }
+        ssf::ir::Expression::FunctionApplication(application) => {
+            collect_from_expression(application.function(), algebraic_types);
+            collect_from_expression(application.argument(), algebraic_types);
+        }
+        ssf::ir::Expression::Lambda(lambda) => {
+            collect_from_definition(lambda.definition(), algebraic_types);
+        }
+        ssf::ir::Expression::Let(let_) => {
+            collect_from_type(let_.type_(), algebraic_types);
+            collect_from_expression(let_.bound_expression(), algebraic_types);
+            collect_from_expression(let_.expression(), algebraic_types);
+        }
+        ssf::ir::Expression::LetRecursive(let_) => {
+            for definition in let_.definitions() {
+                collect_from_definition(definition, algebraic_types);
+            }
+
+            collect_from_expression(let_.expression(), algebraic_types);
+        }
+        ssf::ir::Expression::Load(load) => {
+            collect_from_expression(load.pointer(), algebraic_types);
+        }
+        ssf::ir::Expression::PointerOffset(offset) => {
+            collect_from_expression(offset.pointer(), algebraic_types);
+            collect_from_expression(offset.offset(), algebraic_types);
+        }
+        ssf::ir::Expression::Record(record) => {
+            collect_from_type(&record.type_().clone().into(), algebraic_types);
+
+            for element in record.elements() {
+                collect_from_expression(element, algebraic_types);
+            }
+        }
+        ssf::ir::Expression::RecordElement(element) => {
+            collect_from_expression(element.record(), algebraic_types);
+        }
+        ssf::ir::Expression::RecordUpdate(update) => {
+            collect_from_expression(update.record(), algebraic_types);
+
+            for (_, element) in update.elements() {
+                collect_from_expression(element, algebraic_types);
+            }
+        }
+        ssf::ir::Expression::Store(store) => {
+            collect_from_expression(store.value(), algebraic_types);
+            collect_from_expression(store.pointer(), algebraic_types);
+            collect_from_expression(store.expression(), algebraic_types);
+        }
+        ssf::ir::Expression::ByteString(_)
+        | ssf::ir::Expression::Primitive(_)
+        | ssf::ir::Expression::Variable(_) => {}
+    }
+}
+
+fn collect_from_type(
+    type_: &ssf::types::Type,
+    algebraic_types: &mut HashSet<ssf::types::Algebraic>,
+) {
+    match type_ {
+        ssf::types::Type::Algebraic(algebraic) => {
+            let algebraic = algebraic.unfold();
+
+            if algebraic_types.contains(&algebraic) {
+                return;
+            }
+
+            algebraic_types.insert(algebraic.clone());
+
+            for constructor in algebraic.constructors().values() {
+                for element in constructor.elements() {
+                    collect_from_type(element, algebraic_types);
+                }
+            }
+        }
+        ssf::types::Type::Array(array) => collect_from_type(array.element(), algebraic_types),
+        ssf::types::Type::Function(function) => {
+            collect_from_type(function.argument(), algebraic_types);
+            collect_from_type(function.result(), algebraic_types);
+        }
+        ssf::types::Type::Lazy(lazy) => collect_from_type(lazy.content(), algebraic_types),
+        ssf::types::Type::Record(record) => {
+            for element in record.elements() {
+                collect_from_type(element, algebraic_types);
+            }
+        }
+        ssf::types::Type::Index(_) | ssf::types::Type::Primitive(_) => {}
+    }
+}
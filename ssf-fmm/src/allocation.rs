@@ -0,0 +1,269 @@
+use crate::{
+    configuration::{AllocationKind, AllocationStrategy},
+    context::Context,
+    entry_functions,
+    layout_descriptors::{self, ThunkEntryFunctions},
+    reference_counts, types,
+};
+
+pub fn compile_stack_allocation(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    type_: impl Into<fmm::types::Type>,
+) -> fmm::build::TypedExpression {
+    fmm::build::bit_cast(
+        types::compile_generic_pointer(),
+        instruction_builder.allocate_stack(type_),
+    )
+    .into()
+}
+
+pub fn compile_closure_allocation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    type_: impl Into<fmm::types::Type>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let type_ = type_.into();
+
+    compile_allocation(
+        context,
+        instruction_builder,
+        type_.clone(),
+        AllocationKind::Closure,
+        None,
+        || reference_counts::compile_drop_function(context, &type_),
+    )
+}
+
+pub fn compile_thunk_allocation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    definition: &ssf::ir::Definition,
+    entry_function_name: &str,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let entry_function_type = types::compile_entry_function_from_definition(definition);
+    let entry_function: fmm::build::TypedExpression =
+        fmm::build::variable(entry_function_name, entry_function_type.clone()).into();
+
+    compile_allocation(
+        context,
+        instruction_builder,
+        types::compile_sized_closure(definition).into(),
+        AllocationKind::Closure,
+        Some(&ThunkEntryFunctions {
+            initial: entry_function.clone(),
+            normal: fmm::build::variable(
+                entry_functions::get_normal_thunk_entry_function_name(entry_function_name),
+                entry_function_type,
+            )
+            .into(),
+        }),
+        || reference_counts::compile_thunk_drop_function(context, definition, entry_function),
+    )
+}
+
+pub fn compile_payload_allocation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    type_: impl Into<fmm::types::Type>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let type_ = type_.into();
+    let kind = if types::is_pointer_free(&type_) {
+        AllocationKind::Atomic
+    } else {
+        AllocationKind::Constructor
+    };
+
+    compile_allocation(
+        context,
+        instruction_builder,
+        type_.clone(),
+        kind,
+        None,
+        || reference_counts::compile_drop_function(context, &type_),
+    )
+}
+
+// Sizes of arrays are computed from their lengths at runtime.
+pub fn compile_array_allocation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    element_type: &fmm::types::Type,
+    length: fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let size: fmm::build::TypedExpression = fmm::build::arithmetic_operation(
+        fmm::ir::ArithmeticOperator::Add,
+        fmm::build::size_of(types::compile_array_header()),
+        fmm::build::arithmetic_operation(
+            fmm::ir::ArithmeticOperator::Multiply,
+            fmm::build::size_of(element_type.clone()),
+            length,
+        )?,
+    )?
+    .into();
+
+    if !context.configuration().reference_counting {
+        return compile_raw_allocation_with_size(
+            context,
+            instruction_builder,
+            size,
+            element_type,
+            AllocationKind::Array,
+            None,
+        );
+    }
+
+    let header_pointer = fmm::build::bit_cast(
+        fmm::types::Pointer::new(reference_counts::compile_header_type()),
+        compile_raw_allocation_with_size(
+            context,
+            instruction_builder,
+            fmm::build::arithmetic_operation(
+                fmm::ir::ArithmeticOperator::Add,
+                fmm::build::size_of(reference_counts::compile_header_type()),
+                size,
+            )?
+            .into(),
+            element_type,
+            AllocationKind::Array,
+            None,
+        )?,
+    );
+
+    instruction_builder.store(
+        reference_counts::compile_header(
+            1,
+            reference_counts::compile_array_drop_function(context, element_type)?,
+        ),
+        header_pointer.clone(),
+    );
+
+    Ok(fmm::build::bit_cast(
+        types::compile_generic_pointer(),
+        fmm::build::pointer_address(header_pointer, fmm::ir::Primitive::PointerInteger(1))?,
+    )
+    .into())
+}
+
+fn compile_allocation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    type_: fmm::types::Type,
+    kind: AllocationKind,
+    thunk_entry_functions: Option<&ThunkEntryFunctions>,
+    compile_drop_function: impl FnOnce() -> Result<fmm::build::TypedExpression, fmm::build::BuildError>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    if !context.configuration().reference_counting {
+        return compile_raw_allocation(
+            context,
+            instruction_builder,
+            type_,
+            kind,
+            thunk_entry_functions,
+        );
+    }
+
+    let object_type = reference_counts::compile_object_type(type_);
+    let object_pointer = fmm::build::bit_cast(
+        fmm::types::Pointer::new(object_type.clone()),
+        compile_raw_allocation(
+            context,
+            instruction_builder,
+            object_type.into(),
+            kind,
+            thunk_entry_functions,
+        )?,
+    );
+
+    instruction_builder.store(
+        reference_counts::compile_header(1, compile_drop_function()?),
+        fmm::build::record_address(object_pointer.clone(), 0)?,
+    );
+
+    Ok(fmm::build::bit_cast(
+        types::compile_generic_pointer(),
+        fmm::build::record_address(object_pointer, 1)?,
+    )
+    .into())
+}
+
+fn compile_raw_allocation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    type_: fmm::types::Type,
+    kind: AllocationKind,
+    thunk_entry_functions: Option<&ThunkEntryFunctions>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    compile_raw_allocation_with_size(
+        context,
+        instruction_builder,
+        fmm::build::size_of(type_.clone()),
+        &type_,
+        kind,
+        thunk_entry_functions,
+    )
+}
+
+fn compile_raw_allocation_with_size(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    size: fmm::build::TypedExpression,
+    type_: &fmm::types::Type,
+    kind: AllocationKind,
+    thunk_entry_functions: Option<&ThunkEntryFunctions>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let compile_runtime_call = |name: &str, arguments: Vec<fmm::build::TypedExpression>| {
+        instruction_builder.call(
+            context.module_builder().declare_function(
+                name,
+                fmm::types::Function::new(
+                    arguments
+                        .iter()
+                        .map(|argument| argument.type_().clone())
+                        .collect(),
+                    types::compile_generic_pointer(),
+                    fmm::types::CallingConvention::Target,
+                ),
+            ),
+            arguments,
+        )
+    };
+
+    Ok(match &context.configuration().allocation_strategy {
+        AllocationStrategy::Default => instruction_builder.allocate_heap(size),
+        AllocationStrategy::Functions(functions) => compile_runtime_call(
+            match kind {
+                AllocationKind::Closure => &functions.closure,
+                AllocationKind::Constructor => &functions.constructor,
+                AllocationKind::Atomic => &functions.atomic,
+                AllocationKind::Array => {
+                    if types::is_pointer_free(type_) {
+                        &functions.atomic
+                    } else {
+                        &functions.constructor
+                    }
+                }
+            },
+            vec![size],
+        )?,
+        AllocationStrategy::Tagged(name) => compile_runtime_call(
+            name,
+            vec![size, fmm::ir::Primitive::PointerInteger(kind as i64).into()],
+        )?,
+        AllocationStrategy::Described(name) => compile_runtime_call(
+            name,
+            vec![
+                size,
+                fmm::ir::Primitive::PointerInteger(kind as i64).into(),
+                if let Some(entry_functions) = thunk_entry_functions {
+                    layout_descriptors::compile_thunk_layout_descriptor(
+                        context,
+                        type_,
+                        entry_functions,
+                    )?
+                } else {
+                    layout_descriptors::compile_layout_descriptor(context, type_)?
+                },
+            ],
+        )?,
+    })
+}
@@ -0,0 +1,116 @@
+use crate::{context::Context, types};
+
+const ENVIRONMENT_ARGUMENT_NAME: &str = "_environment";
+const INDEX_ARGUMENT_NAME: &str = "_index";
+const LENGTH_ARGUMENT_NAME: &str = "_length";
+
+pub fn compile_length_pointer(
+    array: fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::record_address(fmm::build::bit_cast(types::compile_array(), array), 0)?.into())
+}
+
+// Elements are placed right after headers because no element has an
+// alignment larger than a pointer.
+pub fn compile_elements_pointer(
+    array: fmm::build::TypedExpression,
+    element_type: &fmm::types::Type,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::bit_cast(
+        fmm::types::Pointer::new(element_type.clone()),
+        fmm::build::pointer_address(
+            fmm::build::bit_cast(types::compile_array(), array),
+            fmm::ir::Primitive::PointerInteger(1),
+        )?,
+    )
+    .into())
+}
+
+// fmm has no loop instruction. So loops are compiled into functions which
+// receive environments of loop bodies, indices, and lengths and call
+// themselves with tail calls so that they run in constant stack space.
+pub fn compile_loop(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    environment: fmm::build::TypedExpression,
+    length: fmm::build::TypedExpression,
+    compile_body: impl Fn(
+        &fmm::build::InstructionBuilder,
+        fmm::build::TypedExpression,
+        fmm::build::TypedExpression,
+    ) -> Result<(), fmm::build::BuildError>,
+) -> Result<(), fmm::build::BuildError> {
+    let name = context.module_builder().generate_name();
+    let environment_type = environment.type_().clone();
+    let function_type = fmm::types::Function::new(
+        vec![
+            environment_type.clone(),
+            fmm::types::Primitive::PointerInteger.into(),
+            fmm::types::Primitive::PointerInteger.into(),
+        ],
+        fmm::types::Record::new(vec![]),
+        fmm::types::CallingConvention::Source,
+    );
+
+    let function = context.module_builder().define_function(
+        &name,
+        vec![
+            fmm::ir::Argument::new(ENVIRONMENT_ARGUMENT_NAME, environment_type.clone()),
+            fmm::ir::Argument::new(INDEX_ARGUMENT_NAME, fmm::types::Primitive::PointerInteger),
+            fmm::ir::Argument::new(LENGTH_ARGUMENT_NAME, fmm::types::Primitive::PointerInteger),
+        ],
+        fmm::types::Record::new(vec![]),
+        |instruction_builder| {
+            let environment: fmm::build::TypedExpression =
+                fmm::build::variable(ENVIRONMENT_ARGUMENT_NAME, environment_type.clone()).into();
+            let index: fmm::build::TypedExpression =
+                fmm::build::variable(INDEX_ARGUMENT_NAME, fmm::types::Primitive::PointerInteger)
+                    .into();
+            let length: fmm::build::TypedExpression =
+                fmm::build::variable(LENGTH_ARGUMENT_NAME, fmm::types::Primitive::PointerInteger)
+                    .into();
+
+            instruction_builder.if_(
+                fmm::build::comparison_operation(
+                    fmm::ir::ComparisonOperator::LessThan(true),
+                    index.clone(),
+                    length.clone(),
+                )?,
+                |instruction_builder| {
+                    compile_body(&instruction_builder, index.clone(), environment.clone())?;
+
+                    Ok(instruction_builder.return_(instruction_builder.call(
+                        fmm::build::variable(&name, function_type.clone()),
+                        vec![
+                            environment.clone(),
+                            fmm::build::arithmetic_operation(
+                                fmm::ir::ArithmeticOperator::Add,
+                                index.clone(),
+                                fmm::ir::Primitive::PointerInteger(1),
+                            )?
+                            .into(),
+                            length.clone(),
+                        ],
+                    )?))
+                },
+                |instruction_builder| Ok(instruction_builder.return_(fmm::build::record(vec![]))),
+            )?;
+
+            Ok(instruction_builder.unreachable())
+        },
+        fmm::ir::FunctionDefinitionOptions::new()
+            .set_calling_convention(fmm::types::CallingConvention::Source)
+            .set_linkage(fmm::ir::Linkage::Internal),
+    )?;
+
+    instruction_builder.call(
+        function,
+        vec![
+            environment,
+            fmm::ir::Primitive::PointerInteger(0).into(),
+            length,
+        ],
+    )?;
+
+    Ok(())
+}
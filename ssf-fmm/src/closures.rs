@@ -1,15 +1,21 @@
-use super::{expressions, types};
+use super::{configuration::ThreadSafety, context::Context, expressions, types};
 
 pub fn compile_load_entry_pointer(
+    context: &Context,
     builder: &fmm::build::InstructionBuilder,
     closure_pointer: impl Into<fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    // Entry functions of thunks need to be loaded atomically
-    // to make thunk update thread-safe.
-    builder.atomic_load(
-        fmm::build::record_address(closure_pointer, 0)?,
-        fmm::ir::AtomicOrdering::SequentiallyConsistent,
-    )
+    let pointer = fmm::build::record_address(closure_pointer, 0)?;
+
+    match context.configuration().thread_safety {
+        // Entry functions of thunks need to be loaded atomically
+        // to make thunk update thread-safe. Any closure might be a thunk
+        // unless its callee is known statically.
+        ThreadSafety::MultiThreaded => {
+            builder.atomic_load(pointer, fmm::ir::AtomicOrdering::SequentiallyConsistent)
+        }
+        ThreadSafety::SingleThreaded => builder.load(pointer),
+    }
 }
 
 pub fn compile_load_arity(
@@ -0,0 +1,179 @@
+use crate::error::CompileError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompileConfiguration {
+    pub thread_safety: ThreadSafety,
+    pub locked_thunk_strategy: LockedThunkStrategy,
+    pub allocation_strategy: AllocationStrategy,
+    pub shadow_stack: Option<ShadowStackFunctions>,
+    /// Insert reference count operations and free objects when no one refers
+    /// to them. Objects are freed with the heap deallocation instruction of
+    /// fmm and reference counts are updated non-atomically. So it is available
+    /// only in a single thread. Cyclic references made by recursive functions
+    /// are never freed.
+    pub reference_counting: bool,
+    /// Allocate closures and boxed constructor payloads bound by let
+    /// expressions on stack if they do not escape from their scopes. It is
+    /// not available with shadow stacks or reference counting.
+    pub stack_allocation: bool,
+    /// Call a runtime function of the name with no arguments when fields are
+    /// taken from values built by other constructors. It must not return.
+    /// Such access is undefined behavior if no function is given.
+    pub constructor_field_failure: Option<String>,
+    /// Size of words on a target in bytes. Algebraic data types represented
+    /// with pointer tags are available only if it is 8.
+    pub word_size: usize,
+}
+
+impl CompileConfiguration {
+    pub(crate) fn validate(&self) -> Result<(), CompileError> {
+        if self.reference_counting && self.thread_safety == ThreadSafety::MultiThreaded {
+            return Err(CompileError::InvalidConfiguration(
+                "reference counts are not updated atomically in multiple threads".into(),
+            ));
+        }
+
+        if self.stack_allocation && (self.shadow_stack.is_some() || self.reference_counting) {
+            return Err(CompileError::InvalidConfiguration(
+                "stack allocation cannot be used with shadow stacks or reference counting".into(),
+            ));
+        }
+
+        match (self.thread_safety, &self.locked_thunk_strategy) {
+            (ThreadSafety::SingleThreaded, LockedThunkStrategy::Spin) => {
+                Err(CompileError::InvalidConfiguration(
+                    "spin locks of thunks never finish in a single thread".into(),
+                ))
+            }
+            (ThreadSafety::MultiThreaded, LockedThunkStrategy::BlackHole(_)) => {
+                Err(CompileError::InvalidConfiguration(
+                    "black holes cannot tell reentrance from evaluation in other threads".into(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for CompileConfiguration {
+    fn default() -> Self {
+        Self {
+            thread_safety: ThreadSafety::MultiThreaded,
+            locked_thunk_strategy: LockedThunkStrategy::Spin,
+            allocation_strategy: AllocationStrategy::Default,
+            shadow_stack: None,
+            reference_counting: false,
+            stack_allocation: false,
+            constructor_field_failure: None,
+            word_size: 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ThreadSafety {
+    /// Entry functions of closures are loaded and updated atomically so that
+    /// thunks can be forced by multiple threads. Only known global functions
+    /// which are not thunks are called without loading them.
+    MultiThreaded,
+    /// Entry functions of closures are loaded and updated with plain memory
+    /// operations. Closures must not be shared across threads.
+    SingleThreaded,
+}
+
+/// Behavior on forcing thunks under evaluation.
+///
+/// Runtime functions named here receive a pointer to the thunk forced, which
+/// points to its entry function pointer, and return nothing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LockedThunkStrategy {
+    /// Spin until the evaluation of the thunk finishes. It is available only
+    /// in multiple threads.
+    Spin,
+    /// Call a runtime function which waits for the thunk to be evaluated before
+    /// forcing it again.
+    Wait(String),
+    /// Call a runtime function which reports an infinite loop. It must not
+    /// return. It is available only in a single thread.
+    BlackHole(String),
+}
+
+/// Strategy of heap allocation.
+///
+/// Runtime functions named here receive sizes of objects in bytes as pointer
+/// integers and return pointers to allocated memory. The memory must be
+/// aligned to 8 bytes so that pointers to it can carry tags in their low bits.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AllocationStrategy {
+    /// Use the heap allocation instruction of fmm for all objects.
+    Default,
+    /// Call different runtime functions for different kinds of objects.
+    Functions(AllocationFunctions),
+    /// Call a runtime function which receives tags of object kinds as pointer
+    /// integers in its second arguments.
+    Tagged(String),
+    /// Call a runtime function which receives tags of object kinds as pointer
+    /// integers and pointers to layout descriptors of objects in its second
+    /// and third arguments.
+    ///
+    /// A layout descriptor is a record of pointer integers which consists of
+    /// an object size in bytes, a list of byte offsets of pointers in the
+    /// object, and a list of variants. Each list is preceded by its length.
+    /// A variant is a list of conditions followed by a list of offsets of
+    /// pointers which are valid only if all the conditions hold. A condition
+    /// is a triple of an offset and a size of an unsigned integer in the
+    /// object and a value it must be equal to. Variants describe algebraic
+    /// data types whose layouts depend on their tags and thunks whose layouts
+    /// depend on their entry functions. Runtimes are expected to store the
+    /// descriptors in object headers so that collectors can find them.
+    /// Algebraic data types represented with pointer tags are rejected as
+    /// descriptors cannot describe tagged pointers.
+    Described(String),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllocationFunctions {
+    pub closure: String,
+    pub constructor: String,
+    pub atomic: String,
+}
+
+/// Kinds of heap objects. Their discriminants are passed to runtime functions
+/// as tags.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AllocationKind {
+    /// Closures including thunks and partial applications.
+    Closure = 0,
+    /// Payloads of boxed constructors which might contain pointers.
+    Constructor = 1,
+    /// Payloads of boxed constructors which contain no pointers.
+    Atomic = 2,
+    /// Arrays whose payloads are their lengths as pointer integers followed
+    /// by their elements. Layout descriptors passed with them describe their
+    /// elements.
+    Array = 3,
+}
+
+/// Runtime functions which maintain shadow stacks of GC roots.
+///
+/// Entry functions push local variables and temporary values containing
+/// pointers into stack slots and register them as roots with layout
+/// descriptors of the slots. The values are reloaded from the slots after
+/// allocation so that collectors can move objects and update the slots.
+///
+/// Allocation functions must return memory filled with zeros as closures of
+/// recursive definitions are pushed before they are initialized. Roots might
+/// also point to static data which collectors must not move. Algebraic data
+/// types represented with pointer tags are rejected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShadowStackFunctions {
+    /// A function which receives a pointer to a stack slot and a pointer to
+    /// its layout descriptor and returns nothing.
+    pub push: String,
+    /// A function which returns the current height of a shadow stack as a
+    /// pointer integer.
+    pub save: String,
+    /// A function which receives a height returned by `save` and pops roots
+    /// above it.
+    pub restore: String,
+}
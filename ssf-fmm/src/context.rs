@@ -0,0 +1,247 @@
+use crate::configuration::CompileConfiguration;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+};
+
+type KnownFunction = (fmm::build::TypedExpression, fmm::build::TypedExpression);
+type PartiallyAppliedEntryFunction = (
+    fmm::types::Function,
+    fmm::types::Record,
+    fmm::build::TypedExpression,
+);
+
+pub struct Context {
+    module_builder: fmm::build::ModuleBuilder,
+    configuration: CompileConfiguration,
+    // Pairs of closure pointers and their entry functions keyed by variable
+    // names. Closure pointers are compared with the ones bound to variables at
+    // call sites so that shadowed names are never called directly.
+    known_functions: RefCell<HashMap<String, Vec<KnownFunction>>>,
+    layout_descriptors: RefCell<Vec<(fmm::types::Type, fmm::build::TypedExpression)>>,
+    // Pairs of values loaded from shadow stack slots and the slots. Values
+    // are reloaded from the slots on every use so that moving collectors can
+    // update them.
+    shadow_stack_slots: RefCell<Vec<(fmm::build::TypedExpression, fmm::build::TypedExpression)>>,
+    drop_functions: RefCell<Vec<(fmm::types::Type, fmm::build::TypedExpression)>>,
+    array_drop_functions: RefCell<Vec<(fmm::types::Type, fmm::build::TypedExpression)>>,
+    constant_payloads: RefCell<Vec<(fmm::build::TypedExpression, fmm::build::TypedExpression)>>,
+    apply_functions: RefCell<Vec<(fmm::types::Function, fmm::build::TypedExpression)>>,
+    partially_applied_entry_functions: RefCell<Vec<PartiallyAppliedEntryFunction>>,
+    algebraic_tags: Vec<(fmm::types::Record, BTreeMap<u64, usize>)>,
+}
+
+impl Context {
+    pub fn new(
+        configuration: CompileConfiguration,
+        algebraic_tags: Vec<(fmm::types::Record, BTreeMap<u64, usize>)>,
+    ) -> Self {
+        Self {
+            module_builder: fmm::build::ModuleBuilder::new(),
+            configuration,
+            known_functions: Default::default(),
+            layout_descriptors: Default::default(),
+            shadow_stack_slots: Default::default(),
+            drop_functions: Default::default(),
+            array_drop_functions: Default::default(),
+            constant_payloads: Default::default(),
+            apply_functions: Default::default(),
+            partially_applied_entry_functions: Default::default(),
+            algebraic_tags,
+        }
+    }
+
+    pub fn module_builder(&self) -> &fmm::build::ModuleBuilder {
+        &self.module_builder
+    }
+
+    pub fn configuration(&self) -> &CompileConfiguration {
+        &self.configuration
+    }
+
+    pub fn add_known_function(
+        &self,
+        name: impl Into<String>,
+        closure_pointer: fmm::build::TypedExpression,
+        entry_function: fmm::build::TypedExpression,
+    ) {
+        self.known_functions
+            .borrow_mut()
+            .entry(name.into())
+            .or_default()
+            .push((closure_pointer, entry_function));
+    }
+
+    pub fn find_known_function(
+        &self,
+        name: &str,
+        closure_pointer: &fmm::build::TypedExpression,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.known_functions
+            .borrow()
+            .get(name)?
+            .iter()
+            .find(|(known_closure_pointer, _)| known_closure_pointer == closure_pointer)
+            .map(|(_, entry_function)| entry_function.clone())
+    }
+
+    pub fn add_layout_descriptor(
+        &self,
+        type_: fmm::types::Type,
+        descriptor: fmm::build::TypedExpression,
+    ) {
+        self.layout_descriptors
+            .borrow_mut()
+            .push((type_, descriptor));
+    }
+
+    pub fn find_layout_descriptor(
+        &self,
+        type_: &fmm::types::Type,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.layout_descriptors
+            .borrow()
+            .iter()
+            .find(|(known_type, _)| known_type == type_)
+            .map(|(_, descriptor)| descriptor.clone())
+    }
+
+    pub fn add_shadow_stack_slot(
+        &self,
+        value: fmm::build::TypedExpression,
+        slot: fmm::build::TypedExpression,
+    ) {
+        self.shadow_stack_slots.borrow_mut().push((value, slot));
+    }
+
+    pub fn find_shadow_stack_slot(
+        &self,
+        value: &fmm::build::TypedExpression,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.shadow_stack_slots
+            .borrow()
+            .iter()
+            .find(|(known_value, _)| known_value == value)
+            .map(|(_, slot)| slot.clone())
+    }
+
+    pub fn add_drop_function(
+        &self,
+        type_: fmm::types::Type,
+        drop_function: fmm::build::TypedExpression,
+    ) {
+        self.drop_functions
+            .borrow_mut()
+            .push((type_, drop_function));
+    }
+
+    pub fn find_drop_function(
+        &self,
+        type_: &fmm::types::Type,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.drop_functions
+            .borrow()
+            .iter()
+            .find(|(known_type, _)| known_type == type_)
+            .map(|(_, drop_function)| drop_function.clone())
+    }
+
+    pub fn add_array_drop_function(
+        &self,
+        element_type: fmm::types::Type,
+        drop_function: fmm::build::TypedExpression,
+    ) {
+        self.array_drop_functions
+            .borrow_mut()
+            .push((element_type, drop_function));
+    }
+
+    pub fn find_array_drop_function(
+        &self,
+        element_type: &fmm::types::Type,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.array_drop_functions
+            .borrow()
+            .iter()
+            .find(|(known_type, _)| known_type == element_type)
+            .map(|(_, drop_function)| drop_function.clone())
+    }
+
+    pub fn add_constant_payload(
+        &self,
+        payload: fmm::build::TypedExpression,
+        pointer: fmm::build::TypedExpression,
+    ) {
+        self.constant_payloads.borrow_mut().push((payload, pointer));
+    }
+
+    pub fn find_constant_payload(
+        &self,
+        payload: &fmm::build::TypedExpression,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.constant_payloads
+            .borrow()
+            .iter()
+            .find(|(known_payload, _)| known_payload == payload)
+            .map(|(_, pointer)| pointer.clone())
+    }
+
+    pub fn add_apply_function(
+        &self,
+        type_: fmm::types::Function,
+        function: fmm::build::TypedExpression,
+    ) {
+        self.apply_functions.borrow_mut().push((type_, function));
+    }
+
+    pub fn find_apply_function(
+        &self,
+        type_: &fmm::types::Function,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.apply_functions
+            .borrow()
+            .iter()
+            .find(|(known_type, _)| known_type == type_)
+            .map(|(_, function)| function.clone())
+    }
+
+    pub fn add_partially_applied_entry_function(
+        &self,
+        entry_function_type: fmm::types::Function,
+        environment_type: fmm::types::Record,
+        function: fmm::build::TypedExpression,
+    ) {
+        self.partially_applied_entry_functions.borrow_mut().push((
+            entry_function_type,
+            environment_type,
+            function,
+        ));
+    }
+
+    pub fn find_partially_applied_entry_function(
+        &self,
+        entry_function_type: &fmm::types::Function,
+        environment_type: &fmm::types::Record,
+    ) -> Option<fmm::build::TypedExpression> {
+        self.partially_applied_entry_functions
+            .borrow()
+            .iter()
+            .find(|(known_entry_function_type, known_environment_type, _)| {
+                known_entry_function_type == entry_function_type
+                    && known_environment_type == environment_type
+            })
+            .map(|(_, _, function)| function.clone())
+    }
+
+    // Tags are mapped to indices of union members.
+    pub fn find_algebraic_tags(&self, type_: &fmm::types::Record) -> Option<&BTreeMap<u64, usize>> {
+        self.algebraic_tags
+            .iter()
+            .find(|(known_type, _)| known_type == type_)
+            .map(|(_, tags)| tags)
+    }
+
+    pub fn into_module(self) -> fmm::ir::Module {
+        self.module_builder.into_module()
+    }
+}
@@ -1,11 +1,11 @@
-use super::types;
+use super::{context::Context, reference_counts, types};
 
-pub fn compile_declaration(
-    module_builder: &fmm::build::ModuleBuilder,
-    declaration: &ssf::ir::Declaration,
-) {
-    module_builder.declare_variable(
+pub fn compile_declaration(context: &Context, declaration: &ssf::ir::Declaration) {
+    context.module_builder().declare_variable(
         declaration.name(),
-        types::compile_unsized_closure(declaration.type_()),
+        reference_counts::compile_static_object_type(
+            context,
+            types::compile_unsized_closure(declaration.type_()),
+        ),
     );
 }
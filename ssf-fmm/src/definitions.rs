@@ -1,18 +1,27 @@
-use crate::{entry_functions, expressions, types};
+use crate::{context::Context, entry_functions, expressions, reference_counts, types};
 use std::collections::HashMap;
 
 pub fn compile_definition(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     definition: &ssf::ir::Definition,
+    entry_function_name: &str,
     global_variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<(), fmm::build::BuildError> {
-    module_builder.define_variable(
+    context.module_builder().define_variable(
         definition.name(),
-        fmm::build::record(vec![
-            entry_functions::compile(module_builder, definition, global_variables)?,
-            expressions::compile_arity(definition.arguments().iter().count()).into(),
-            fmm::ir::Undefined::new(types::compile_closure_payload(definition)).into(),
-        ]),
+        reference_counts::compile_static_object(
+            context,
+            fmm::build::record(vec![
+                entry_functions::compile(
+                    context,
+                    entry_function_name,
+                    definition,
+                    global_variables,
+                )?,
+                expressions::compile_arity(definition.arguments().iter().count()).into(),
+                fmm::ir::Undefined::new(types::compile_closure_payload(definition)).into(),
+            ]),
+        ),
         fmm::ir::VariableDefinitionOptions::new().set_mutable(definition.is_thunk()),
     );
 
@@ -1,4 +1,8 @@
-use crate::{expressions, types};
+use crate::{
+    configuration::{LockedThunkStrategy, ThreadSafety},
+    context::Context,
+    expressions, reference_counts, shadow_stacks, types,
+};
 use std::collections::HashMap;
 
 const ENVIRONMENT_NAME: &str = "_env";
@@ -10,28 +14,31 @@ fn function_definition_options() -> fmm::ir::FunctionDefinitionOptions {
 }
 
 pub fn compile(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
+    entry_function_name: &str,
     definition: &ssf::ir::Definition,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
     Ok(if definition.is_thunk() {
-        compile_thunk(module_builder, definition, variables)?
+        compile_thunk(context, entry_function_name, definition, variables)?
     } else {
-        compile_non_thunk(module_builder, definition, variables)?
+        compile_non_thunk(context, entry_function_name, definition, variables)?
     })
 }
 
 fn compile_non_thunk(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
+    entry_function_name: &str,
     definition: &ssf::ir::Definition,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    module_builder.define_anonymous_function(
+    context.module_builder().define_function(
+        entry_function_name,
         compile_arguments(definition),
         types::compile(definition.result_type()),
         |instruction_builder| {
             Ok(instruction_builder.return_(compile_body(
-                module_builder,
+                context,
                 &instruction_builder,
                 definition,
                 variables,
@@ -42,174 +49,392 @@ fn compile_non_thunk(
 }
 
 fn compile_thunk(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
+    entry_function_name: &str,
     definition: &ssf::ir::Definition,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
     compile_first_thunk_entry(
-        module_builder,
+        context,
+        entry_function_name,
         definition,
-        compile_normal_thunk_entry(module_builder, definition)?,
-        compile_locked_thunk_entry(module_builder, definition)?,
+        compile_normal_thunk_entry(context, entry_function_name, definition)?,
+        compile_locked_thunk_entry(context, definition)?,
         variables,
     )
 }
 
 fn compile_body(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     definition: &ssf::ir::Definition,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    expressions::compile(
-        module_builder,
+    let height = shadow_stacks::compile_save(context, instruction_builder)?;
+    let local_variables = definition
+        .environment()
+        .iter()
+        .enumerate()
+        .map(|(index, free_variable)| {
+            Ok((
+                free_variable.name().into(),
+                instruction_builder.load(fmm::build::record_address(
+                    fmm::build::bit_cast(
+                        fmm::types::Pointer::new(types::compile_environment(definition)),
+                        compile_environment_pointer(),
+                    ),
+                    index,
+                )?)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .chain(definition.arguments().iter().map(|argument| {
+            (
+                argument.name().into(),
+                fmm::build::variable(argument.name(), types::compile(argument.type_())),
+            )
+        }))
+        .map(|(name, value)| {
+            Ok((
+                name,
+                shadow_stacks::compile_push(context, instruction_builder, &value)?,
+            ))
+        })
+        .collect::<Result<Vec<(String, _)>, fmm::build::BuildError>>()?;
+
+    let value = expressions::compile(
+        context,
         instruction_builder,
         definition.body(),
         &variables
             .clone()
             .into_iter()
-            .chain(
-                definition
-                    .environment()
-                    .iter()
-                    .enumerate()
-                    .map(|(index, free_variable)| {
-                        Ok((
-                            free_variable.name().into(),
-                            instruction_builder.load(fmm::build::record_address(
-                                fmm::build::bit_cast(
-                                    fmm::types::Pointer::new(types::compile_environment(
-                                        definition,
-                                    )),
-                                    compile_environment_pointer(),
-                                ),
-                                index,
-                            )?)?,
-                        ))
-                    })
-                    .collect::<Result<Vec<_>, _>>()?,
-            )
-            .chain(definition.arguments().iter().map(|argument| {
-                (
-                    argument.name().into(),
-                    fmm::build::variable(argument.name(), types::compile(argument.type_())),
-                )
-            }))
+            .chain(local_variables)
             .collect(),
-    )
+    )?;
+
+    shadow_stacks::compile_restore(context, instruction_builder, height)?;
+
+    // Arguments are owned by callees while environments are owned by closures.
+    for argument in definition.arguments() {
+        reference_counts::compile_release(
+            context,
+            instruction_builder,
+            &fmm::build::variable(argument.name(), types::compile(argument.type_())),
+        )?;
+    }
+
+    Ok(value)
 }
 
 fn compile_first_thunk_entry(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
+    entry_function_name: &str,
     definition: &ssf::ir::Definition,
     normal_entry_function: fmm::build::TypedExpression,
     lock_entry_function: fmm::build::TypedExpression,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    let entry_function_name = module_builder.generate_name();
     let entry_function_type = types::compile_entry_function_from_definition(definition);
     let arguments = compile_arguments(definition);
 
-    module_builder.define_function(
-        &entry_function_name,
+    context.module_builder().define_function(
+        entry_function_name,
         arguments.clone(),
         types::compile(definition.result_type()),
-        |instruction_builder| {
-            instruction_builder.if_(
-                instruction_builder.compare_and_swap(
-                    compile_entry_function_pointer_pointer(definition)?,
-                    fmm::build::variable(&entry_function_name, entry_function_type.clone()),
-                    lock_entry_function.clone(),
-                    fmm::ir::AtomicOrdering::SequentiallyConsistent,
-                    fmm::ir::AtomicOrdering::SequentiallyConsistent,
-                ),
-                |instruction_builder| {
-                    let value =
-                        compile_body(module_builder, &instruction_builder, definition, variables)?;
-
-                    instruction_builder.store(
-                        value.clone(),
-                        fmm::build::bit_cast(
-                            fmm::types::Pointer::new(types::compile(definition.result_type())),
-                            compile_environment_pointer(),
-                        ),
-                    );
-                    instruction_builder.atomic_store(
-                        normal_entry_function.clone(),
+        |instruction_builder| match context.configuration().thread_safety {
+            ThreadSafety::MultiThreaded => {
+                instruction_builder.if_(
+                    instruction_builder.compare_and_swap(
                         compile_entry_function_pointer_pointer(definition)?,
+                        fmm::build::variable(entry_function_name, entry_function_type.clone()),
+                        lock_entry_function.clone(),
                         fmm::ir::AtomicOrdering::SequentiallyConsistent,
-                    );
-
-                    Ok(instruction_builder.return_(value))
-                },
-                |instruction_builder| {
-                    Ok(instruction_builder.return_(
-                        instruction_builder.call(
-                            instruction_builder.atomic_load(
-                                compile_entry_function_pointer_pointer(definition)?,
-                                fmm::ir::AtomicOrdering::SequentiallyConsistent,
+                        fmm::ir::AtomicOrdering::SequentiallyConsistent,
+                    ),
+                    |instruction_builder| {
+                        compile_thunk_update(
+                            context,
+                            &instruction_builder,
+                            definition,
+                            normal_entry_function.clone(),
+                            variables,
+                        )
+                    },
+                    |instruction_builder| {
+                        Ok(instruction_builder.return_(
+                            instruction_builder.call(
+                                compile_load_entry_function(
+                                    context,
+                                    &instruction_builder,
+                                    definition,
+                                )?,
+                                arguments
+                                    .iter()
+                                    .map(|argument| {
+                                        fmm::build::variable(
+                                            argument.name(),
+                                            argument.type_().clone(),
+                                        )
+                                    })
+                                    .collect(),
                             )?,
-                            arguments
-                                .iter()
-                                .map(|argument| {
-                                    fmm::build::variable(argument.name(), argument.type_().clone())
-                                })
-                                .collect(),
-                        )?,
-                    ))
-                },
-            )?;
+                        ))
+                    },
+                )?;
 
-            Ok(instruction_builder.unreachable())
+                Ok(instruction_builder.unreachable())
+            }
+            // Thunks are never forced concurrently in a single thread. So we
+            // only need to lock them against reentrance.
+            ThreadSafety::SingleThreaded => {
+                compile_store_entry_function(
+                    context,
+                    &instruction_builder,
+                    lock_entry_function.clone(),
+                    compile_entry_function_pointer_pointer(definition)?,
+                );
+
+                compile_thunk_update(
+                    context,
+                    &instruction_builder,
+                    definition,
+                    normal_entry_function.clone(),
+                    variables,
+                )
+            }
         },
         function_definition_options(),
     )
 }
 
+fn compile_thunk_update(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    definition: &ssf::ir::Definition,
+    normal_entry_function: fmm::build::TypedExpression,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::ir::Block, fmm::build::BuildError> {
+    let height = shadow_stacks::compile_save(context, instruction_builder)?;
+    // Thunks can be moved by collectors while their bodies are evaluated.
+    let entry_function_pointer = shadow_stacks::compile_push(
+        context,
+        instruction_builder,
+        &compile_entry_function_pointer_pointer(definition)?,
+    )?;
+
+    let value = compile_body(context, instruction_builder, definition, variables)?;
+
+    let entry_function_pointer =
+        shadow_stacks::compile_load(context, instruction_builder, &entry_function_pointer)?;
+    shadow_stacks::compile_restore(context, instruction_builder, height)?;
+
+    let environment_pointer: fmm::build::TypedExpression = fmm::build::record_address(
+        fmm::build::bit_cast(
+            fmm::types::Pointer::new(types::compile_sized_closure(definition)),
+            entry_function_pointer.clone(),
+        ),
+        2,
+    )?
+    .into();
+
+    // Values are shared by thunks and callers while environments are dropped
+    // on update.
+    reference_counts::compile_retain(context, instruction_builder, &value)?;
+    reference_counts::compile_release(
+        context,
+        instruction_builder,
+        &instruction_builder.load(fmm::build::bit_cast(
+            fmm::types::Pointer::new(types::compile_environment(definition)),
+            environment_pointer.clone(),
+        ))?,
+    )?;
+    instruction_builder.store(
+        value.clone(),
+        fmm::build::bit_cast(
+            fmm::types::Pointer::new(types::compile(definition.result_type())),
+            environment_pointer,
+        ),
+    );
+    compile_store_entry_function(
+        context,
+        instruction_builder,
+        normal_entry_function,
+        entry_function_pointer,
+    );
+
+    Ok(instruction_builder.return_(value))
+}
+
 fn compile_normal_thunk_entry(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
+    entry_function_name: &str,
     definition: &ssf::ir::Definition,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    module_builder.define_anonymous_function(
+    context.module_builder().define_function(
+        get_normal_thunk_entry_function_name(entry_function_name),
         compile_arguments(definition),
         types::compile(definition.result_type()),
-        |instruction_builder| compile_normal_body(&instruction_builder, definition),
+        |instruction_builder| compile_normal_body(context, &instruction_builder, definition),
         function_definition_options(),
     )
 }
 
+// Normal entry functions of thunks are named after their initial ones so that
+// layout descriptors can refer to them before they are compiled.
+pub fn get_normal_thunk_entry_function_name(entry_function_name: &str) -> String {
+    format!("{}_normal", entry_function_name)
+}
+
 fn compile_locked_thunk_entry(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     definition: &ssf::ir::Definition,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    let entry_function_name = module_builder.generate_name();
+    let entry_function_name = context.module_builder().generate_name();
 
-    module_builder.define_function(
+    context.module_builder().define_function(
         &entry_function_name,
         compile_arguments(definition),
         types::compile(definition.result_type()),
         |instruction_builder| {
+            instruction_builder.if_(
+                fmm::build::comparison_operation(
+                    fmm::ir::ComparisonOperator::Equal,
+                    fmm::build::bit_cast(
+                        fmm::types::Primitive::PointerInteger,
+                        compile_load_entry_function(context, &instruction_builder, definition)?,
+                    ),
+                    fmm::build::bit_cast(
+                        fmm::types::Primitive::PointerInteger,
+                        fmm::build::variable(
+                            &entry_function_name,
+                            types::compile_entry_function_from_definition(definition),
+                        ),
+                    ),
+                )?,
+                |instruction_builder| {
+                    compile_locked_body(
+                        context,
+                        &instruction_builder,
+                        definition,
+                        &entry_function_name,
+                    )
+                },
+                |instruction_builder| {
+                    compile_normal_body(context, &instruction_builder, definition)
+                },
+            )?;
+
+            Ok(instruction_builder.unreachable())
+        },
+        function_definition_options(),
+    )
+}
+
+fn compile_locked_body(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    definition: &ssf::ir::Definition,
+    locked_entry_function_name: &str,
+) -> Result<fmm::ir::Block, fmm::build::BuildError> {
+    let compile_runtime_call = |name| {
+        instruction_builder.call(
+            context.module_builder().declare_function(
+                name,
+                fmm::types::Function::new(
+                    vec![types::compile_generic_pointer().into()],
+                    fmm::types::Record::new(vec![]),
+                    fmm::types::CallingConvention::Target,
+                ),
+            ),
+            vec![fmm::build::bit_cast(
+                types::compile_generic_pointer(),
+                compile_entry_function_pointer_pointer(definition)?,
+            )
+            .into()],
+        )
+    };
+
+    match &context.configuration().locked_thunk_strategy {
+        LockedThunkStrategy::Spin => {
+            instruction_builder.call(
+                compile_spin_function(context, definition, locked_entry_function_name)?,
+                vec![compile_entry_function_pointer_pointer(definition)?],
+            )?;
+        }
+        LockedThunkStrategy::Wait(name) => {
+            compile_runtime_call(name)?;
+        }
+        LockedThunkStrategy::BlackHole(name) => {
+            compile_runtime_call(name)?;
+
+            return Ok(instruction_builder.unreachable());
+        }
+    }
+
+    Ok(instruction_builder.return_(
+        instruction_builder.call(
+            compile_load_entry_function(context, instruction_builder, definition)?,
+            compile_arguments(definition)
+                .iter()
+                .map(|argument| fmm::build::variable(argument.name(), argument.type_().clone()))
+                .collect(),
+        )?,
+    ))
+}
+
+// Spin in a separate function instead of forcing thunks again so that callers
+// do not re-enter locked entry functions. It loops with tail calls as fmm has
+// no loop instruction.
+fn compile_spin_function(
+    context: &Context,
+    definition: &ssf::ir::Definition,
+    locked_entry_function_name: &str,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    const POINTER_NAME: &str = "pointer";
+
+    let function_name = context.module_builder().generate_name();
+    let entry_function_type = types::compile_entry_function_from_definition(definition);
+    let pointer_type = fmm::types::Pointer::new(entry_function_type.clone());
+    let function_type = fmm::types::Function::new(
+        vec![pointer_type.clone().into()],
+        fmm::types::Record::new(vec![]),
+        fmm::types::CallingConvention::Source,
+    );
+
+    context.module_builder().define_function(
+        &function_name,
+        vec![fmm::ir::Argument::new(POINTER_NAME, pointer_type.clone())],
+        fmm::types::Record::new(vec![]),
+        |instruction_builder| {
+            let pointer = fmm::build::variable(POINTER_NAME, pointer_type.clone());
+
             instruction_builder.if_(
                 fmm::build::comparison_operation(
                     fmm::ir::ComparisonOperator::Equal,
                     fmm::build::bit_cast(
                         fmm::types::Primitive::PointerInteger,
                         instruction_builder.atomic_load(
-                            compile_entry_function_pointer_pointer(definition)?,
+                            pointer.clone(),
                             fmm::ir::AtomicOrdering::SequentiallyConsistent,
                         )?,
                     ),
                     fmm::build::bit_cast(
                         fmm::types::Primitive::PointerInteger,
                         fmm::build::variable(
-                            &entry_function_name,
-                            types::compile_entry_function_from_definition(definition),
+                            locked_entry_function_name,
+                            entry_function_type.clone(),
                         ),
                     ),
                 )?,
-                // TODO Return to handle thunk locks asynchronously.
-                |instruction_builder| Ok(instruction_builder.unreachable()),
-                |instruction_builder| compile_normal_body(&instruction_builder, definition),
+                |instruction_builder| {
+                    Ok(instruction_builder.return_(instruction_builder.call(
+                        fmm::build::variable(&function_name, function_type.clone()),
+                        vec![pointer.clone()],
+                    )?))
+                },
+                |instruction_builder| Ok(instruction_builder.return_(fmm::build::record(vec![]))),
             )?;
 
             Ok(instruction_builder.unreachable())
@@ -219,30 +444,94 @@ fn compile_locked_thunk_entry(
 }
 
 fn compile_normal_body(
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     definition: &ssf::ir::Definition,
 ) -> Result<fmm::ir::Block, fmm::build::BuildError> {
-    Ok(
-        instruction_builder.return_(instruction_builder.load(fmm::build::bit_cast(
-            fmm::types::Pointer::new(types::compile(definition.result_type())),
-            compile_environment_pointer(),
-        ))?),
-    )
+    let value = instruction_builder.load(fmm::build::bit_cast(
+        fmm::types::Pointer::new(types::compile(definition.result_type())),
+        compile_environment_pointer(),
+    ))?;
+
+    reference_counts::compile_retain(context, instruction_builder, &value)?;
+
+    Ok(instruction_builder.return_(value))
 }
 
-fn compile_entry_function_pointer_pointer(
+fn compile_load_entry_function(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
     definition: &ssf::ir::Definition,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    // TODO Calculate entry function pointer properly.
-    // The offset should be calculated by allocating a record of
-    // { pointer, { pointer, arity, environment } }.
-    Ok(fmm::build::pointer_address(
-        fmm::build::bit_cast(
-            fmm::types::Pointer::new(types::compile_entry_function_from_definition(definition)),
-            compile_environment_pointer(),
+    let pointer = compile_entry_function_pointer_pointer(definition)?;
+
+    match context.configuration().thread_safety {
+        ThreadSafety::MultiThreaded => instruction_builder
+            .atomic_load(pointer, fmm::ir::AtomicOrdering::SequentiallyConsistent),
+        ThreadSafety::SingleThreaded => instruction_builder.load(pointer),
+    }
+}
+
+fn compile_store_entry_function(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    entry_function: fmm::build::TypedExpression,
+    entry_function_pointer: fmm::build::TypedExpression,
+) {
+    match context.configuration().thread_safety {
+        ThreadSafety::MultiThreaded => instruction_builder.atomic_store(
+            entry_function,
+            entry_function_pointer,
+            fmm::ir::AtomicOrdering::SequentiallyConsistent,
         ),
-        fmm::ir::Primitive::PointerInteger(-2),
-    )?
+        ThreadSafety::SingleThreaded => {
+            instruction_builder.store(entry_function, entry_function_pointer)
+        }
+    }
+}
+
+fn compile_entry_function_pointer_pointer(
+    definition: &ssf::ir::Definition,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    // Calculate an offset of environments in closures by aligning a size of
+    // closure headers so that it respects sizes and alignments of fields on
+    // each target.
+    let environment_alignment = fmm::build::align_of(types::compile_closure_payload(definition));
+    let environment_offset = fmm::build::arithmetic_operation(
+        fmm::ir::ArithmeticOperator::Multiply,
+        fmm::build::arithmetic_operation(
+            fmm::ir::ArithmeticOperator::Divide,
+            fmm::build::arithmetic_operation(
+                fmm::ir::ArithmeticOperator::Add,
+                fmm::build::size_of(fmm::types::Record::new(vec![
+                    types::compile_entry_function_from_definition(definition).into(),
+                    types::compile_arity().into(),
+                ])),
+                fmm::build::arithmetic_operation(
+                    fmm::ir::ArithmeticOperator::Subtract,
+                    environment_alignment.clone(),
+                    fmm::ir::Primitive::PointerInteger(1),
+                )?,
+            )?,
+            environment_alignment.clone(),
+        )?,
+        environment_alignment,
+    )?;
+
+    Ok(fmm::build::bit_cast(
+        fmm::types::Pointer::new(types::compile_entry_function_from_definition(definition)),
+        fmm::build::pointer_address(
+            fmm::build::bit_cast(
+                types::compile_generic_pointer(),
+                compile_environment_pointer(),
+            ),
+            fmm::build::arithmetic_operation(
+                fmm::ir::ArithmeticOperator::Subtract,
+                fmm::ir::Primitive::PointerInteger(0),
+                environment_offset,
+            )?,
+        )?,
+    )
     .into())
 }
 
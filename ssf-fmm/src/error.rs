@@ -5,7 +5,9 @@ use std::{
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum CompileError {
+    AlgebraicTagConflict(ssf::types::Algebraic),
     FmmBuild(fmm::build::BuildError),
+    InvalidConfiguration(String),
     TypeCheck(ssf::analysis::TypeCheckError),
 }
 
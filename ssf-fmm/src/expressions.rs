@@ -1,159 +1,655 @@
-use crate::{closures, entry_functions, function_applications, types};
+use crate::{
+    allocation, arrays, closures, context::Context, entry_functions, function_applications,
+    layout_descriptors, pointer_tags, reference_counts, shadow_stacks, types,
+};
 use std::collections::HashMap;
 
+// Cases with more alternatives than this are compiled into binary search on
+// their sorted tags or integers instead of linear chains of comparisons.
+const BINARY_SEARCH_THRESHOLD: usize = 4;
+
+const CONSTRUCTOR_FIELD_NAME_PREFIX: &str = "_field";
+
+enum PayloadMemory<'a> {
+    Heap,
+    Reused(&'a fmm::build::TypedExpression),
+    Stack,
+}
+
 pub fn compile_arity(arity: usize) -> fmm::ir::Primitive {
     fmm::ir::Primitive::PointerInteger(arity as i64)
 }
 
 pub fn compile(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    expression: &ssf::ir::Expression,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    compile_with_reuse(context, instruction_builder, expression, variables, None)
+}
+
+// Reuse pointers are passed only to expressions whose values are constructed
+// by boxed constructor applications of the same payload types.
+fn compile_with_reuse(
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     expression: &ssf::ir::Expression,
     variables: &HashMap<String, fmm::build::TypedExpression>,
+    reuse_pointer: Option<&fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
     let compile =
-        |expression, variables| compile(module_builder, instruction_builder, expression, variables);
+        |expression, variables| compile(context, instruction_builder, expression, variables);
 
     Ok(match expression {
         ssf::ir::Expression::ArithmeticOperation(operation) => {
-            compile_arithmetic_operation(module_builder, instruction_builder, operation, variables)?
-                .into()
+            compile_arithmetic_operation(context, instruction_builder, operation, variables)?.into()
+        }
+        ssf::ir::Expression::Array(array) => {
+            compile_array(context, instruction_builder, array, variables)?
+        }
+        ssf::ir::Expression::ArrayElement(element) => {
+            compile_array_element(context, instruction_builder, element, variables)?
+        }
+        ssf::ir::Expression::ArrayInitialization(initialization) => {
+            compile_array_initialization(context, instruction_builder, initialization, variables)?
+        }
+        ssf::ir::Expression::ArrayLength(length) => {
+            let array = compile(length.array(), variables)?;
+            let value = instruction_builder.load(arrays::compile_length_pointer(array.clone())?)?;
+
+            reference_counts::compile_release(context, instruction_builder, &array)?;
+
+            fmm::build::bit_cast(fmm::types::Primitive::Integer64, value).into()
         }
         ssf::ir::Expression::BitCast(bit_cast) => fmm::build::bit_cast(
             types::compile(bit_cast.type_()),
             compile(bit_cast.expression(), variables)?,
         )
         .into(),
+        ssf::ir::Expression::ByteString(string) => compile_byte_string(context, string)?,
         ssf::ir::Expression::Case(case) => {
-            compile_case(module_builder, instruction_builder, case, variables)?
+            compile_case(context, instruction_builder, case, variables)?
         }
         ssf::ir::Expression::ComparisonOperation(operation) => {
-            compile_comparison_operation(module_builder, instruction_builder, operation, variables)?
-                .into()
+            compile_comparison_operation(context, instruction_builder, operation, variables)?.into()
         }
         ssf::ir::Expression::ConstructorApplication(constructor_application) => {
-            let constructor = constructor_application.constructor();
-            let algebraic_type = constructor.algebraic_type();
-            let constructor_type =
-                algebraic_type.unfold().constructors()[&constructor.tag()].clone();
-
-            fmm::build::record(
-                if algebraic_type.is_singleton() {
-                    None
-                } else {
-                    Some(fmm::ir::Primitive::PointerInteger(constructor.tag() as i64).into())
-                }
-                .into_iter()
-                .chain(if constructor_type.is_enum() {
-                    None
-                } else {
-                    let payload = fmm::build::record(
-                        constructor_application
-                            .arguments()
-                            .iter()
-                            .map(|argument| compile(argument, variables))
-                            .collect::<Result<_, _>>()?,
-                    );
-                    let union_type = types::compile_constructor_union(algebraic_type);
-                    let member_index =
-                        types::get_constructor_union_index(algebraic_type, constructor.tag());
-
-                    Some(
-                        fmm::ir::Union::new(
-                            union_type.clone(),
-                            member_index,
-                            if constructor_type.is_boxed() {
-                                let pointer = instruction_builder
-                                    .allocate_heap(fmm::build::size_of(payload.type_().clone()));
-                                instruction_builder.store(
-                                    payload.clone(),
-                                    fmm::build::bit_cast(
-                                        fmm::types::Pointer::new(payload.type_().clone()),
-                                        pointer.clone(),
-                                    ),
-                                );
-
-                                fmm::ir::Expression::from(fmm::build::bit_cast(
-                                    union_type.members()[member_index].clone(),
-                                    pointer,
-                                ))
-                            } else {
-                                payload.into()
-                            },
-                        )
-                        .into(),
-                    )
-                })
-                .collect(),
-            )
-            .into()
+            compile_constructor_application(
+                context,
+                instruction_builder,
+                constructor_application,
+                variables,
+                reuse_pointer
+                    .map(PayloadMemory::Reused)
+                    .unwrap_or(PayloadMemory::Heap),
+            )?
+        }
+        ssf::ir::Expression::ConstructorField(field) => {
+            compile_constructor_field(context, instruction_builder, field, variables)?
         }
+        ssf::ir::Expression::Delay(delay) => compile_let_recursive(
+            context,
+            instruction_builder,
+            &ssf::ir::LetRecursive::new(
+                vec![delay.definition().clone()],
+                ssf::ir::Variable::new(delay.definition().name()),
+            ),
+            variables,
+            None,
+        )?,
+        ssf::ir::Expression::Force(force) => function_applications::compile(
+            context,
+            instruction_builder,
+            compile(force.expression(), variables)?,
+            &[fmm::build::record(vec![]).into()],
+        )?,
         ssf::ir::Expression::FunctionApplication(function_application) => {
-            function_applications::compile(
-                module_builder,
+            compile_function_application(
+                context,
                 instruction_builder,
-                compile(function_application.first_function(), variables)?,
-                &function_application
-                    .arguments()
-                    .into_iter()
-                    .map(|argument| compile(argument, variables))
-                    .collect::<Result<Vec<_>, _>>()?,
+                function_application,
+                variables,
+                false,
             )?
         }
+        ssf::ir::Expression::Lambda(lambda) => compile_let_recursive(
+            context,
+            instruction_builder,
+            &ssf::ir::LetRecursive::new(
+                vec![lambda.definition().clone()],
+                ssf::ir::Variable::new(lambda.definition().name()),
+            ),
+            variables,
+            None,
+        )?,
         ssf::ir::Expression::Let(let_) => {
-            compile_let(module_builder, instruction_builder, let_, variables)?
+            compile_let(context, instruction_builder, let_, variables, reuse_pointer)?
         }
         ssf::ir::Expression::LetRecursive(let_recursive) => compile_let_recursive(
-            module_builder,
+            context,
             instruction_builder,
             let_recursive,
             variables,
+            reuse_pointer,
         )?,
-        ssf::ir::Expression::Primitive(primitive) => compile_primitive(primitive).into(),
-        ssf::ir::Expression::Variable(variable) => variables[variable.name()].clone(),
+        ssf::ir::Expression::Load(load) => instruction_builder.load(fmm::build::bit_cast(
+            fmm::types::Pointer::new(types::compile_primitive(&load.type_())),
+            compile(load.pointer(), variables)?,
+        ))?,
+        ssf::ir::Expression::PointerOffset(offset) => {
+            compile_pointer_offset(context, instruction_builder, offset, variables)?
+        }
+        ssf::ir::Expression::Primitive(primitive) => compile_primitive(primitive),
+        ssf::ir::Expression::Record(record) => compile_record(
+            context,
+            instruction_builder,
+            record.type_(),
+            compile_rooted_expressions(context, instruction_builder, record.elements(), variables)?,
+        )?,
+        ssf::ir::Expression::RecordElement(element) => {
+            compile_record_element(context, instruction_builder, element, variables)?
+        }
+        ssf::ir::Expression::RecordUpdate(update) => {
+            compile_record_update(context, instruction_builder, update, variables)?
+        }
+        ssf::ir::Expression::Store(store) => {
+            let value = compile(store.value(), variables)?;
+            let pointer = compile(store.pointer(), variables)?;
+
+            instruction_builder.store(
+                value,
+                fmm::build::bit_cast(
+                    fmm::types::Pointer::new(types::compile_primitive(&store.type_())),
+                    pointer,
+                ),
+            );
+
+            compile(store.expression(), variables)?
+        }
+        ssf::ir::Expression::Variable(variable) => {
+            let value = shadow_stacks::compile_load(
+                context,
+                instruction_builder,
+                &variables[variable.name()],
+            )?;
+
+            reference_counts::compile_retain(context, instruction_builder, &value)?;
+
+            value
+        }
     })
 }
 
+// Values are pushed to shadow stacks one by one as the following expressions
+// might allocate memory. They need to be reloaded before use.
+fn compile_rooted_expressions<'a>(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    expressions: impl IntoIterator<Item = &'a ssf::ir::Expression>,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<Vec<fmm::build::TypedExpression>, fmm::build::BuildError> {
+    expressions
+        .into_iter()
+        .map(|expression| {
+            shadow_stacks::compile_push(
+                context,
+                instruction_builder,
+                &compile(context, instruction_builder, expression, variables)?,
+            )
+        })
+        .collect()
+}
+
+fn compile_constructor_application(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    constructor_application: &ssf::ir::ConstructorApplication,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+    payload_memory: PayloadMemory,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    if is_constant_constructor_application(constructor_application) {
+        return compile_constant_constructor_application(context, constructor_application);
+    }
+
+    let arguments = compile_rooted_expressions(
+        context,
+        instruction_builder,
+        constructor_application.arguments(),
+        variables,
+    )?;
+
+    compile_constructor(
+        constructor_application.constructor(),
+        shadow_stacks::compile_loads(context, instruction_builder, &arguments)?,
+        |payload| {
+            let pointer = match payload_memory {
+                PayloadMemory::Heap => allocation::compile_payload_allocation(
+                    context,
+                    instruction_builder,
+                    payload.type_().clone(),
+                )?,
+                PayloadMemory::Reused(reuse_pointer) => compile_reused_payload_allocation(
+                    context,
+                    instruction_builder,
+                    payload.type_(),
+                    reuse_pointer,
+                )?,
+                PayloadMemory::Stack => allocation::compile_stack_allocation(
+                    instruction_builder,
+                    payload.type_().clone(),
+                ),
+            };
+            let payload: fmm::build::TypedExpression = fmm::build::record(
+                shadow_stacks::compile_loads(context, instruction_builder, &arguments)?,
+            )
+            .into();
+
+            instruction_builder.store(
+                payload.clone(),
+                fmm::build::bit_cast(
+                    fmm::types::Pointer::new(payload.type_().clone()),
+                    pointer.clone(),
+                ),
+            );
+
+            Ok(pointer)
+        },
+    )
+}
+
+fn is_constant(expression: &ssf::ir::Expression) -> bool {
+    match expression {
+        ssf::ir::Expression::ConstructorApplication(constructor_application) => {
+            is_constant_constructor_application(constructor_application)
+        }
+        ssf::ir::Expression::ByteString(_) | ssf::ir::Expression::Primitive(_) => true,
+        _ => false,
+    }
+}
+
+// Pointer-tagged values are not constant because tags are added to addresses
+// at runtime.
+fn is_constant_constructor_application(
+    constructor_application: &ssf::ir::ConstructorApplication,
+) -> bool {
+    !types::is_pointer_tagged(constructor_application.constructor().algebraic_type())
+        && constructor_application.arguments().iter().all(is_constant)
+}
+
+// Constructor applications whose arguments are all constant are compiled into
+// immutable global variables shared by all evaluations of them.
+fn compile_constant_constructor_application(
+    context: &Context,
+    constructor_application: &ssf::ir::ConstructorApplication,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    compile_constructor(
+        constructor_application.constructor(),
+        constructor_application
+            .arguments()
+            .iter()
+            .map(|argument| {
+                Ok(match argument {
+                    ssf::ir::Expression::ByteString(string) => {
+                        compile_byte_string(context, string)?
+                    }
+                    ssf::ir::Expression::ConstructorApplication(constructor_application) => {
+                        compile_constant_constructor_application(context, constructor_application)?
+                    }
+                    ssf::ir::Expression::Primitive(primitive) => compile_primitive(primitive),
+                    _ => unreachable!(),
+                })
+            })
+            .collect::<Result<_, _>>()?,
+        |payload| compile_constant_payload(context, payload),
+    )
+}
+
+fn compile_constant_payload(
+    context: &Context,
+    payload: fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    if let Some(pointer) = context.find_constant_payload(&payload) {
+        return Ok(pointer);
+    }
+
+    let name = context.module_builder().generate_name();
+
+    context.module_builder().define_variable(
+        &name,
+        reference_counts::compile_static_object(context, payload.clone()),
+        fmm::ir::VariableDefinitionOptions::new()
+            .set_linkage(fmm::ir::Linkage::Internal)
+            .set_mutable(false),
+    );
+
+    let pointer: fmm::build::TypedExpression = fmm::build::bit_cast(
+        types::compile_generic_pointer(),
+        reference_counts::compile_static_object_pointer(context, &name, payload.type_().clone())?,
+    )
+    .into();
+
+    context.add_constant_payload(payload, pointer.clone());
+
+    Ok(pointer)
+}
+
+fn compile_constructor(
+    constructor: &ssf::ir::Constructor,
+    arguments: Vec<fmm::build::TypedExpression>,
+    compile_boxed_payload: impl FnOnce(
+        fmm::build::TypedExpression,
+    )
+        -> Result<fmm::build::TypedExpression, fmm::build::BuildError>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let algebraic_type = constructor.algebraic_type();
+    let constructor_type = constructor.constructor_type();
+
+    if types::is_pointer_tagged(algebraic_type) {
+        let index = types::get_constructor_union_index(algebraic_type, constructor.tag());
+
+        return if constructor_type.is_enum() {
+            Ok(pointer_tags::compile_enum(index))
+        } else {
+            pointer_tags::compile_tagged_pointer(
+                compile_boxed_payload(fmm::build::record(arguments).into())?,
+                index,
+            )
+        };
+    }
+
+    Ok(fmm::build::record(
+        if types::is_tagged(algebraic_type) {
+            Some(types::compile_constructor_tag(algebraic_type, constructor.tag()).into())
+        } else {
+            None
+        }
+        .into_iter()
+        .chain(if constructor_type.is_enum() {
+            types::get_null_pointer_niche(algebraic_type).map(|(_, boxed_tag)| {
+                let union_type = types::compile_constructor_union(algebraic_type);
+                let member_index = types::get_constructor_union_index(algebraic_type, boxed_tag);
+
+                fmm::ir::Union::new(
+                    union_type.clone(),
+                    member_index,
+                    fmm::build::bit_cast(
+                        union_type.members()[member_index].clone(),
+                        fmm::ir::Primitive::PointerInteger(0),
+                    )
+                    .into(),
+                )
+                .into()
+            })
+        } else {
+            let payload = fmm::build::record(arguments);
+            let union_type = types::compile_constructor_union(algebraic_type);
+            let member_index =
+                types::get_constructor_union_index(algebraic_type, constructor.tag());
+
+            Some(
+                fmm::ir::Union::new(
+                    union_type.clone(),
+                    member_index,
+                    if constructor_type.is_boxed() {
+                        fmm::build::bit_cast(
+                            union_type.members()[member_index].clone(),
+                            compile_boxed_payload(payload.into())?,
+                        )
+                        .into()
+                    } else {
+                        payload.into()
+                    },
+                )
+                .into(),
+            )
+        })
+        .collect(),
+    )
+    .into())
+}
+
+// Constructor fields are compiled as case expressions of single alternatives
+// which are unreachable if tags do not match.
+fn compile_constructor_field(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    field: &ssf::ir::ConstructorField,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let constructor = field.constructor();
+    let element_names = (0..constructor.constructor_type().elements().len())
+        .map(|index| format!("{}_{}", CONSTRUCTOR_FIELD_NAME_PREFIX, index))
+        .collect::<Vec<_>>();
+    let argument = compile(context, instruction_builder, field.argument(), variables)?;
+
+    instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::Equal,
+            compile_tag(instruction_builder, constructor.algebraic_type(), &argument)?,
+            types::compile_constructor_tag(constructor.algebraic_type(), constructor.tag()),
+        )?,
+        |instruction_builder| {
+            Ok(instruction_builder.branch(shadow_stacks::compile_block(
+                context,
+                &instruction_builder,
+                || {
+                    compile_algebraic_alternative(
+                        context,
+                        &instruction_builder,
+                        argument.clone(),
+                        &ssf::ir::AlgebraicAlternative::new(
+                            constructor.clone(),
+                            element_names.clone(),
+                            ssf::ir::Variable::new(&element_names[field.index()]),
+                        ),
+                        variables,
+                    )
+                },
+            )?))
+        },
+        |instruction_builder| {
+            if let Some(name) = &context.configuration().constructor_field_failure {
+                instruction_builder.call(
+                    context.module_builder().declare_function(
+                        name,
+                        fmm::types::Function::new(
+                            vec![],
+                            fmm::types::Record::new(vec![]),
+                            fmm::types::CallingConvention::Target,
+                        ),
+                    ),
+                    vec![],
+                )?;
+            }
+
+            Ok(instruction_builder.unreachable())
+        },
+    )
+}
+
+// Partially-applied closures are allocated on stack if they do not escape.
+fn compile_function_application(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    function_application: &ssf::ir::FunctionApplication,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+    stack_allocated: bool,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let function = function_application.first_function();
+    let closure_pointer = shadow_stacks::compile_push(
+        context,
+        instruction_builder,
+        &compile(context, instruction_builder, function, variables)?,
+    )?;
+    let arguments = compile_rooted_expressions(
+        context,
+        instruction_builder,
+        function_application.arguments(),
+        variables,
+    )?;
+    let closure_slot = if stack_allocated {
+        Some(function_applications::compile_closure_slot(
+            instruction_builder,
+            &closure_pointer,
+            &arguments,
+        ))
+    } else {
+        None
+    };
+
+    if let Some(entry_function) = function.to_variable().and_then(|variable| {
+        context.find_known_function(variable.name(), &variables[variable.name()])
+    }) {
+        function_applications::compile_known(
+            context,
+            instruction_builder,
+            entry_function,
+            closure_pointer,
+            &arguments,
+            closure_slot.as_ref(),
+        )
+    } else {
+        function_applications::compile_with_closure_slot(
+            context,
+            instruction_builder,
+            closure_pointer,
+            &arguments,
+            closure_slot.as_ref(),
+        )
+    }
+}
+
+fn compile_reused_payload_allocation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    type_: &fmm::types::Type,
+    reuse_pointer: &fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::Equal,
+            fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, reuse_pointer.clone()),
+            fmm::ir::Primitive::PointerInteger(0),
+        )?,
+        |instruction_builder| {
+            Ok(
+                instruction_builder.branch(allocation::compile_payload_allocation(
+                    context,
+                    &instruction_builder,
+                    type_.clone(),
+                )?),
+            )
+        },
+        |instruction_builder| Ok(instruction_builder.branch(reuse_pointer.clone())),
+    )
+}
+
 fn compile_case(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     case: &ssf::ir::Case,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    let compile = |expression| compile(module_builder, instruction_builder, expression, variables);
+    let compile = |expression| compile(context, instruction_builder, expression, variables);
 
     Ok(match case {
         ssf::ir::Case::Algebraic(case) => {
             let argument = compile(case.argument())?;
+            let mut alternatives = case.alternatives().to_vec();
+
+            if alternatives.len() > BINARY_SEARCH_THRESHOLD {
+                // Sorting is stable and only the first alternatives of the
+                // same constructors are reachable.
+                alternatives.sort_by_key(|alternative| alternative.constructor().tag());
+                alternatives.dedup_by_key(|alternative| alternative.constructor().tag());
+            }
 
-            compile_algebraic_alternatives(
-                module_builder,
+            let tag = if let Some(alternative) = case.alternatives().first() {
+                compile_tag(
+                    instruction_builder,
+                    alternative.constructor().algebraic_type(),
+                    &argument,
+                )?
+            } else {
+                fmm::ir::Primitive::PointerInteger(0).into()
+            };
+
+            compile_search(
                 instruction_builder,
-                if case
-                    .alternatives()
-                    .first()
-                    .map(|alternative| alternative.constructor().algebraic_type().is_singleton())
-                    .unwrap_or(true)
-                {
-                    fmm::ir::Primitive::PointerInteger(0).into()
-                } else {
-                    instruction_builder.deconstruct_record(argument.clone(), 0)?
+                &tag,
+                &alternatives,
+                &|alternative: &ssf::ir::AlgebraicAlternative| {
+                    let constructor = alternative.constructor();
+
+                    types::compile_constructor_tag(constructor.algebraic_type(), constructor.tag())
+                        .into()
+                },
+                &|instruction_builder: &fmm::build::InstructionBuilder,
+                  alternatives: &[ssf::ir::AlgebraicAlternative]| {
+                    compile_algebraic_alternatives(
+                        context,
+                        instruction_builder,
+                        tag.clone(),
+                        argument.clone(),
+                        alternatives,
+                        case.default_alternative(),
+                        variables,
+                    )
                 },
-                argument,
-                case.alternatives(),
-                case.default_alternative(),
-                variables,
             )?
             .unwrap()
         }
         ssf::ir::Case::Primitive(case) => {
-            compile_primitive_case(module_builder, instruction_builder, case, variables)?
+            compile_primitive_case(context, instruction_builder, case, variables)?
         }
     })
 }
 
+fn compile_tag(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    algebraic_type: &ssf::types::Algebraic,
+    argument: &fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(if types::is_pointer_tagged(algebraic_type) {
+        pointer_tags::compile_tag(argument)?
+    } else if types::is_tagged(algebraic_type) {
+        instruction_builder.deconstruct_record(argument.clone(), 0)?
+    } else if let Some((enum_tag, boxed_tag)) = types::get_null_pointer_niche(algebraic_type) {
+        instruction_builder.if_(
+            fmm::build::comparison_operation(
+                fmm::ir::ComparisonOperator::Equal,
+                fmm::build::bit_cast(
+                    fmm::types::Primitive::PointerInteger,
+                    instruction_builder.deconstruct_union(
+                        instruction_builder.deconstruct_record(argument.clone(), 0)?,
+                        types::get_constructor_union_index(algebraic_type, boxed_tag),
+                    )?,
+                ),
+                fmm::ir::Primitive::PointerInteger(0),
+            )?,
+            |instruction_builder| {
+                Ok(instruction_builder
+                    .branch(types::compile_constructor_tag(algebraic_type, enum_tag)))
+            },
+            |instruction_builder| {
+                Ok(instruction_builder
+                    .branch(types::compile_constructor_tag(algebraic_type, boxed_tag)))
+            },
+        )?
+    } else {
+        types::compile_constructor_tag(
+            algebraic_type,
+            *algebraic_type.constructors().keys().next().unwrap(),
+        )
+        .into()
+    })
+}
+
+// Arguments of algebraic cases are released at the ends of alternatives
+// unless their memory is reused.
 fn compile_algebraic_alternatives(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     tag: fmm::build::TypedExpression,
     argument: fmm::build::TypedExpression,
@@ -162,16 +658,19 @@ fn compile_algebraic_alternatives(
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<Option<fmm::build::TypedExpression>, fmm::build::BuildError> {
     Ok(match alternatives {
-        [] => default_alternative
-            .map(|default_alternative| {
-                compile(
-                    module_builder,
-                    instruction_builder,
-                    default_alternative,
-                    variables,
-                )
-            })
-            .transpose()?,
+        [] => {
+            if let Some(default_alternative) = default_alternative {
+                let value = shadow_stacks::compile_block(context, instruction_builder, || {
+                    compile(context, instruction_builder, default_alternative, variables)
+                })?;
+
+                reference_counts::compile_release(context, instruction_builder, &argument)?;
+
+                Some(value)
+            } else {
+                None
+            }
+        }
         [alternative, ..] => {
             let constructor = alternative.constructor();
 
@@ -179,61 +678,27 @@ fn compile_algebraic_alternatives(
                 fmm::build::comparison_operation(
                     fmm::ir::ComparisonOperator::Equal,
                     tag.clone(),
-                    fmm::ir::Primitive::PointerInteger(constructor.tag() as i64),
+                    types::compile_constructor_tag(constructor.algebraic_type(), constructor.tag()),
                 )?,
                 |instruction_builder| {
-                    Ok(instruction_builder.branch(compile(
-                        module_builder,
+                    Ok(instruction_builder.branch(shadow_stacks::compile_block(
+                        context,
                         &instruction_builder,
-                        alternative.expression(),
-                        &if constructor.constructor_type().is_enum() {
-                            variables.clone()
-                        } else {
-                            let mut payload = instruction_builder.deconstruct_union(
-                                instruction_builder.deconstruct_record(
-                                    argument.clone(),
-                                    (!constructor.algebraic_type().is_singleton()).into(),
-                                )?,
-                                types::get_constructor_union_index(
-                                    constructor.algebraic_type(),
-                                    constructor.tag(),
-                                ),
-                            )?;
-
-                            if constructor.constructor_type().is_boxed() {
-                                payload = instruction_builder.load(fmm::build::bit_cast(
-                                    types::compile_boxed_constructor(
-                                        constructor.constructor_type(),
-                                    ),
-                                    payload,
-                                ))?;
-                            }
-
-                            variables
-                                .clone()
-                                .into_iter()
-                                .chain(
-                                    alternative
-                                        .element_names()
-                                        .iter()
-                                        .enumerate()
-                                        .map(|(index, name)| {
-                                            Ok((
-                                                name.into(),
-                                                instruction_builder
-                                                    .deconstruct_record(payload.clone(), index)?,
-                                            ))
-                                        })
-                                        .collect::<Result<Vec<_>, _>>()?,
-                                )
-                                .collect()
+                        || {
+                            compile_algebraic_alternative(
+                                context,
+                                &instruction_builder,
+                                argument.clone(),
+                                alternative,
+                                variables,
+                            )
                         },
                     )?))
                 },
                 |instruction_builder| {
                     Ok(
                         if let Some(expression) = compile_algebraic_alternatives(
-                            module_builder,
+                            context,
                             &instruction_builder,
                             tag.clone(),
                             argument.clone(),
@@ -252,55 +717,281 @@ fn compile_algebraic_alternatives(
     })
 }
 
+fn compile_algebraic_alternative(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    argument: fmm::build::TypedExpression,
+    alternative: &ssf::ir::AlgebraicAlternative,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let constructor = alternative.constructor();
+
+    if constructor.constructor_type().is_enum() {
+        let value = compile(
+            context,
+            instruction_builder,
+            alternative.expression(),
+            variables,
+        )?;
+
+        reference_counts::compile_release(context, instruction_builder, &argument)?;
+
+        return Ok(value);
+    }
+
+    let mut payload = if types::is_pointer_tagged(constructor.algebraic_type()) {
+        pointer_tags::compile_untagged_pointer(&argument)?
+    } else {
+        instruction_builder.deconstruct_union(
+            instruction_builder.deconstruct_record(
+                argument.clone(),
+                types::is_tagged(constructor.algebraic_type()).into(),
+            )?,
+            types::get_constructor_union_index(constructor.algebraic_type(), constructor.tag()),
+        )?
+    };
+    let payload_pointer = if constructor.constructor_type().is_boxed() {
+        let pointer = fmm::build::bit_cast(
+            types::compile_boxed_constructor(constructor.constructor_type()),
+            payload,
+        );
+
+        payload = instruction_builder.load(pointer.clone())?;
+
+        Some(pointer)
+    } else {
+        None
+    };
+
+    let elements = alternative
+        .element_names()
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            Ok((
+                name.clone(),
+                shadow_stacks::compile_push(
+                    context,
+                    instruction_builder,
+                    &instruction_builder.deconstruct_record(payload.clone(), index)?,
+                )?,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let variables = variables
+        .clone()
+        .into_iter()
+        .chain(elements.iter().cloned())
+        .collect();
+
+    if let Some(payload_pointer) = payload_pointer.filter(|_| {
+        context.configuration().reference_counting
+            && is_payload_reused(constructor.constructor_type(), alternative.expression())
+    }) {
+        // Elements are moved out of payloads which might be reused.
+        for (_, element) in &elements {
+            reference_counts::compile_retain(context, instruction_builder, element)?;
+        }
+
+        let reuse_pointer =
+            reference_counts::compile_reuse_pointer(instruction_builder, payload_pointer.into())?;
+        let value = compile_with_reuse(
+            context,
+            instruction_builder,
+            alternative.expression(),
+            &variables,
+            Some(&reuse_pointer),
+        )?;
+
+        for (_, element) in &elements {
+            reference_counts::compile_release(context, instruction_builder, element)?;
+        }
+
+        Ok(value)
+    } else {
+        let value = compile(
+            context,
+            instruction_builder,
+            alternative.expression(),
+            &variables,
+        )?;
+
+        reference_counts::compile_release(context, instruction_builder, &argument)?;
+
+        Ok(value)
+    }
+}
+
+fn is_payload_reused(
+    constructor_type: &ssf::types::Constructor,
+    expression: &ssf::ir::Expression,
+) -> bool {
+    match expression {
+        ssf::ir::Expression::ConstructorApplication(application) => {
+            let other_type = application.constructor().constructor_type();
+
+            other_type.is_boxed()
+                && !is_constant_constructor_application(application)
+                && types::compile_unboxed_constructor(other_type)
+                    == types::compile_unboxed_constructor(constructor_type)
+        }
+        ssf::ir::Expression::Let(let_) => is_payload_reused(constructor_type, let_.expression()),
+        ssf::ir::Expression::LetRecursive(let_) => {
+            is_payload_reused(constructor_type, let_.expression())
+        }
+        _ => false,
+    }
+}
+
 fn compile_primitive_case(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     case: &ssf::ir::PrimitiveCase,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    let argument = compile(
-        module_builder,
+    let argument = compile_comparable(compile(
+        context,
         instruction_builder,
         case.argument(),
         variables,
-    )?;
+    )?);
+    let mut alternatives = case.alternatives().to_vec();
+
+    // Floating point numbers are not searched because their orders do not
+    // match equality of zeros.
+    if alternatives.len() > BINARY_SEARCH_THRESHOLD
+        && alternatives
+            .iter()
+            .all(|alternative| get_integer(alternative.primitive()).is_some())
+    {
+        alternatives.sort_by_key(|alternative| get_integer(alternative.primitive()));
+        alternatives.dedup_by_key(|alternative| get_integer(alternative.primitive()));
+
+        return Ok(compile_search(
+            instruction_builder,
+            &argument,
+            &alternatives,
+            &|alternative: &ssf::ir::PrimitiveAlternative| {
+                compile_comparable(compile_primitive(alternative.primitive()))
+            },
+            &|instruction_builder: &fmm::build::InstructionBuilder,
+              alternatives: &[ssf::ir::PrimitiveAlternative]| {
+                compile_primitive_alternatives(
+                    context,
+                    instruction_builder,
+                    argument.clone(),
+                    alternatives,
+                    case.default_alternative(),
+                    variables,
+                )
+            },
+        )?
+        .unwrap());
+    }
 
     Ok(compile_primitive_alternatives(
-        module_builder,
+        context,
         instruction_builder,
         argument,
-        case.alternatives(),
+        &alternatives,
         case.default_alternative(),
         variables,
     )?
     .unwrap())
 }
 
+fn get_integer(primitive: &ssf::ir::Primitive) -> Option<u64> {
+    match primitive {
+        ssf::ir::Primitive::Integer8(number) => Some((*number).into()),
+        ssf::ir::Primitive::Integer32(number) => Some((*number).into()),
+        ssf::ir::Primitive::Integer64(number) => Some(*number),
+        ssf::ir::Primitive::Boolean(_)
+        | ssf::ir::Primitive::Float32(_)
+        | ssf::ir::Primitive::Float64(_)
+        | ssf::ir::Primitive::NullPointer => None,
+    }
+}
+
+// Alternatives are sorted by their keys. Default alternatives are duplicated
+// at leaves of binary search.
+fn compile_search<T>(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+    alternatives: &[T],
+    compile_key: &impl Fn(&T) -> fmm::build::TypedExpression,
+    compile_alternatives: &impl Fn(
+        &fmm::build::InstructionBuilder,
+        &[T],
+    ) -> Result<
+        Option<fmm::build::TypedExpression>,
+        fmm::build::BuildError,
+    >,
+) -> Result<Option<fmm::build::TypedExpression>, fmm::build::BuildError> {
+    if alternatives.len() <= BINARY_SEARCH_THRESHOLD {
+        return compile_alternatives(instruction_builder, alternatives);
+    }
+
+    let (lower_alternatives, upper_alternatives) = alternatives.split_at(alternatives.len() / 2);
+    let compile_half = |instruction_builder: fmm::build::InstructionBuilder, alternatives| {
+        Ok(
+            if let Some(expression) = compile_search(
+                &instruction_builder,
+                value,
+                alternatives,
+                compile_key,
+                compile_alternatives,
+            )? {
+                instruction_builder.branch(expression)
+            } else {
+                instruction_builder.unreachable()
+            },
+        )
+    };
+
+    Ok(Some(instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::LessThan(false),
+            value.clone(),
+            compile_key(&upper_alternatives[0]),
+        )?,
+        |instruction_builder| compile_half(instruction_builder, lower_alternatives),
+        |instruction_builder| compile_half(instruction_builder, upper_alternatives),
+    )?))
+}
+
 fn compile_primitive_alternatives(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     argument: fmm::build::TypedExpression,
     alternatives: &[ssf::ir::PrimitiveAlternative],
     default_alternative: Option<&ssf::ir::Expression>,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<Option<fmm::build::TypedExpression>, fmm::build::BuildError> {
-    let compile = |expression| compile(module_builder, instruction_builder, expression, variables);
+    let compile = |instruction_builder: &fmm::build::InstructionBuilder,
+                   expression: &ssf::ir::Expression| {
+        shadow_stacks::compile_block(context, instruction_builder, || {
+            compile(context, instruction_builder, expression, variables)
+        })
+    };
 
     Ok(match alternatives {
-        [] => default_alternative.map(compile).transpose()?,
+        [] => default_alternative
+            .map(|expression| compile(instruction_builder, expression))
+            .transpose()?,
         [alternative, ..] => Some(instruction_builder.if_(
             fmm::build::comparison_operation(
                 fmm::ir::ComparisonOperator::Equal,
                 argument.clone(),
-                compile_primitive(alternative.primitive()),
+                compile_comparable(compile_primitive(alternative.primitive())),
             )?,
             |instruction_builder| {
-                Ok(instruction_builder.branch(compile(alternative.expression())?))
+                Ok(instruction_builder
+                    .branch(compile(&instruction_builder, alternative.expression())?))
             },
             |instruction_builder| {
                 Ok(
                     if let Some(expression) = compile_primitive_alternatives(
-                        module_builder,
+                        context,
                         &instruction_builder,
                         argument.clone(),
                         &alternatives[1..],
@@ -318,87 +1009,542 @@ fn compile_primitive_alternatives(
 }
 
 fn compile_let(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     let_: &ssf::ir::Let,
     variables: &HashMap<String, fmm::build::TypedExpression>,
+    reuse_pointer: Option<&fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    let compile =
-        |expression, variables| compile(module_builder, instruction_builder, expression, variables);
+    let stack_allocated =
+        context.configuration().stack_allocation && !ssf::analysis::is_let_escaping(let_);
+    let bound_value = match let_.bound_expression() {
+        ssf::ir::Expression::ConstructorApplication(constructor_application) if stack_allocated => {
+            compile_constructor_application(
+                context,
+                instruction_builder,
+                constructor_application,
+                variables,
+                PayloadMemory::Stack,
+            )?
+        }
+        ssf::ir::Expression::FunctionApplication(function_application) if stack_allocated => {
+            compile_function_application(
+                context,
+                instruction_builder,
+                function_application,
+                variables,
+                true,
+            )?
+        }
+        expression => compile(context, instruction_builder, expression, variables)?,
+    };
+
+    let bound_value = shadow_stacks::compile_push(context, instruction_builder, &bound_value)?;
 
-    compile(
+    let value = compile_with_reuse(
+        context,
+        instruction_builder,
         let_.expression(),
         &variables
             .clone()
             .drain()
-            .chain(vec![(
-                let_.name().into(),
-                compile(let_.bound_expression(), variables)?,
-            )])
+            .chain(vec![(let_.name().into(), bound_value.clone())])
             .collect(),
-    )
+        reuse_pointer,
+    )?;
+
+    reference_counts::compile_release(context, instruction_builder, &bound_value)?;
+
+    Ok(value)
 }
 
 fn compile_let_recursive(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     let_: &ssf::ir::LetRecursive,
     variables: &HashMap<String, fmm::build::TypedExpression>,
+    reuse_pointer: Option<&fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
     let mut variables = variables.clone();
-    let mut closure_pointers = HashMap::new();
+    let entry_function_names = let_
+        .definitions()
+        .iter()
+        .map(|definition| (definition.name(), context.module_builder().generate_name()))
+        .collect::<HashMap<_, _>>();
+    let stack_allocated =
+        context.configuration().stack_allocation && !ssf::analysis::is_let_recursive_escaping(let_);
 
     for definition in let_.definitions() {
-        let closure_pointer = instruction_builder.allocate_heap(fmm::build::size_of(
-            types::compile_sized_closure(definition),
-        ));
+        let closure_pointer = if stack_allocated {
+            allocation::compile_stack_allocation(
+                instruction_builder,
+                types::compile_sized_closure(definition),
+            )
+        } else if definition.is_thunk() {
+            allocation::compile_thunk_allocation(
+                context,
+                instruction_builder,
+                definition,
+                &entry_function_names[definition.name()],
+            )?
+        } else {
+            allocation::compile_closure_allocation(
+                context,
+                instruction_builder,
+                types::compile_sized_closure(definition),
+            )?
+        };
 
+        // Closures are pushed to shadow stacks before initialization as the
+        // other ones are allocated in the meantime.
         variables.insert(
             definition.name().into(),
-            fmm::build::bit_cast(
-                fmm::types::Pointer::new(types::compile_unsized_closure(definition.type_())),
-                closure_pointer.clone(),
-            )
-            .into(),
+            shadow_stacks::compile_push(
+                context,
+                instruction_builder,
+                &fmm::build::bit_cast(
+                    fmm::types::Pointer::new(types::compile_unsized_closure(definition.type_())),
+                    closure_pointer,
+                )
+                .into(),
+            )?,
         );
-        closure_pointers.insert(
-            definition.name(),
+    }
+
+    for definition in let_.definitions() {
+        let entry_function = entry_functions::compile(
+            context,
+            &entry_function_names[definition.name()],
+            definition,
+            &variables,
+        )?;
+        let free_variables = definition
+            .environment()
+            .iter()
+            .map(|free_variable| {
+                shadow_stacks::compile_load(
+                    context,
+                    instruction_builder,
+                    &variables[free_variable.name()],
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for free_variable in &free_variables {
+            reference_counts::compile_retain(context, instruction_builder, free_variable)?;
+        }
+
+        if !definition.is_thunk() {
+            context.add_known_function(
+                definition.name(),
+                variables[definition.name()].clone(),
+                entry_function.clone(),
+            );
+        }
+
+        instruction_builder.store(
+            closures::compile_closure_content(entry_function, free_variables),
             fmm::build::bit_cast(
                 fmm::types::Pointer::new(types::compile_sized_closure(definition)),
-                closure_pointer.clone(),
+                shadow_stacks::compile_load(
+                    context,
+                    instruction_builder,
+                    &variables[definition.name()],
+                )?,
             ),
         );
     }
 
+    let value = compile_with_reuse(
+        context,
+        instruction_builder,
+        let_.expression(),
+        &variables,
+        reuse_pointer,
+    )?;
+
     for definition in let_.definitions() {
+        reference_counts::compile_release(
+            context,
+            instruction_builder,
+            &variables[definition.name()],
+        )?;
+    }
+
+    Ok(value)
+}
+
+fn compile_array(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    array: &ssf::ir::Array,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let element_type = types::compile(array.type_().element());
+    let elements =
+        compile_rooted_expressions(context, instruction_builder, array.elements(), variables)?;
+    let length = fmm::ir::Primitive::PointerInteger(elements.len() as i64);
+    let pointer = allocation::compile_array_allocation(
+        context,
+        instruction_builder,
+        &element_type,
+        length.into(),
+    )?;
+
+    instruction_builder.store(length, arrays::compile_length_pointer(pointer.clone())?);
+
+    let elements_pointer = arrays::compile_elements_pointer(pointer.clone(), &element_type)?;
+
+    for (index, element) in shadow_stacks::compile_loads(context, instruction_builder, &elements)?
+        .into_iter()
+        .enumerate()
+    {
         instruction_builder.store(
-            closures::compile_closure_content(
-                entry_functions::compile(module_builder, definition, &variables)?,
-                definition
-                    .environment()
-                    .iter()
-                    .map(|free_variable| variables[free_variable.name()].clone())
-                    .collect(),
-            ),
-            closure_pointers[definition.name()].clone(),
+            element,
+            fmm::build::pointer_address(
+                elements_pointer.clone(),
+                fmm::ir::Primitive::PointerInteger(index as i64),
+            )?,
         );
     }
 
-    compile(
-        module_builder,
+    Ok(fmm::build::bit_cast(types::compile_array(), pointer).into())
+}
+
+// Negative indices are out of bounds because they are compared with lengths
+// as unsigned integers.
+fn compile_array_element(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    element: &ssf::ir::ArrayElement,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let array = compile(context, instruction_builder, element.array(), variables)?;
+    let index: fmm::build::TypedExpression = fmm::build::bit_cast(
+        fmm::types::Primitive::PointerInteger,
+        compile(context, instruction_builder, element.index(), variables)?,
+    )
+    .into();
+    let element_type = types::compile(element.type_().element());
+
+    let value = instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::LessThan(false),
+            index.clone(),
+            instruction_builder.load(arrays::compile_length_pointer(array.clone())?)?,
+        )?,
+        |instruction_builder| {
+            let value = instruction_builder.load(fmm::build::pointer_address(
+                arrays::compile_elements_pointer(array.clone(), &element_type)?,
+                index.clone(),
+            )?)?;
+
+            reference_counts::compile_retain(context, &instruction_builder, &value)?;
+
+            Ok(instruction_builder.branch(value))
+        },
+        |instruction_builder| {
+            Ok(instruction_builder.branch(compile(
+                context,
+                &instruction_builder,
+                element.default_element(),
+                variables,
+            )?))
+        },
+    )?;
+
+    reference_counts::compile_release(context, instruction_builder, &array)?;
+
+    Ok(value)
+}
+
+// Arrays of negative lengths are empty.
+fn compile_array_initialization(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    initialization: &ssf::ir::ArrayInitialization,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let length = compile(
+        context,
+        instruction_builder,
+        initialization.length(),
+        variables,
+    )?;
+    let function = compile(
+        context,
+        instruction_builder,
+        initialization.function(),
+        variables,
+    )?;
+    let length = instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::LessThan(true),
+            length.clone(),
+            fmm::ir::Primitive::Integer64(0),
+        )?,
+        |instruction_builder| Ok(instruction_builder.branch(fmm::ir::Primitive::PointerInteger(0))),
+        |instruction_builder| {
+            Ok(instruction_builder.branch(fmm::build::bit_cast(
+                fmm::types::Primitive::PointerInteger,
+                length.clone(),
+            )))
+        },
+    )?;
+    let function = shadow_stacks::compile_push(context, instruction_builder, &function)?;
+    let element_type = types::compile(initialization.type_().element());
+    let pointer = allocation::compile_array_allocation(
+        context,
+        instruction_builder,
+        &element_type,
+        length.clone(),
+    )?;
+
+    instruction_builder.store(
+        length.clone(),
+        arrays::compile_length_pointer(pointer.clone())?,
+    );
+
+    // Collectors might scan arrays before their elements are initialized.
+    if layout_descriptors::is_enabled(context.configuration())
+        && !types::is_pointer_free(&element_type)
+    {
+        arrays::compile_loop(
+            context,
+            instruction_builder,
+            fmm::build::bit_cast(
+                fmm::types::Pointer::new(fmm::types::Primitive::Integer8),
+                arrays::compile_elements_pointer(pointer.clone(), &element_type)?,
+            )
+            .into(),
+            fmm::build::arithmetic_operation(
+                fmm::ir::ArithmeticOperator::Multiply,
+                fmm::build::size_of(element_type.clone()),
+                length.clone(),
+            )?
+            .into(),
+            |instruction_builder, index, bytes_pointer| {
+                instruction_builder.store(
+                    fmm::ir::Primitive::Integer8(0),
+                    fmm::build::pointer_address(bytes_pointer, index)?,
+                );
+
+                Ok(())
+            },
+        )?;
+    }
+
+    let array = shadow_stacks::compile_push(
+        context,
         instruction_builder,
-        let_.expression(),
-        &variables,
+        &fmm::build::bit_cast(types::compile_array(), pointer).into(),
+    )?;
+    // Loop bodies reload the array and the function from slots as they are
+    // moved by collectors during initialization.
+    let compile_slot = |value: &fmm::build::TypedExpression| {
+        context.find_shadow_stack_slot(value).unwrap_or_else(|| {
+            let slot = instruction_builder.allocate_stack(value.type_().clone());
+            instruction_builder.store(value.clone(), slot.clone());
+            slot
+        })
+    };
+
+    arrays::compile_loop(
+        context,
+        instruction_builder,
+        fmm::build::record(vec![compile_slot(&array), compile_slot(&function)]).into(),
+        length,
+        |instruction_builder, index, slots| {
+            let function = instruction_builder
+                .load(instruction_builder.deconstruct_record(slots.clone(), 1)?)?;
+
+            reference_counts::compile_retain(context, instruction_builder, &function)?;
+
+            let element = function_applications::compile(
+                context,
+                instruction_builder,
+                function,
+                &[fmm::build::bit_cast(fmm::types::Primitive::Integer64, index.clone()).into()],
+            )?;
+
+            instruction_builder.store(
+                element,
+                fmm::build::pointer_address(
+                    arrays::compile_elements_pointer(
+                        instruction_builder
+                            .load(instruction_builder.deconstruct_record(slots, 0)?)?,
+                        &element_type,
+                    )?,
+                    index,
+                )?,
+            );
+
+            Ok(())
+        },
+    )?;
+
+    reference_counts::compile_release(
+        context,
+        instruction_builder,
+        &shadow_stacks::compile_load(context, instruction_builder, &function)?,
+    )?;
+
+    shadow_stacks::compile_load(context, instruction_builder, &array)
+}
+
+// Byte strings are compiled into immutable global variables in the same
+// layout as arrays.
+fn compile_byte_string(
+    context: &Context,
+    string: &ssf::ir::ByteString,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::bit_cast(
+        types::compile_array(),
+        compile_constant_payload(
+            context,
+            fmm::build::record(vec![
+                fmm::ir::Primitive::PointerInteger(string.bytes().len() as i64).into(),
+                fmm::build::record(
+                    string
+                        .bytes()
+                        .iter()
+                        .map(|&byte| fmm::ir::Primitive::Integer8(byte).into())
+                        .collect(),
+                )
+                .into(),
+            ])
+            .into(),
+        )?,
     )
+    .into())
+}
+
+fn compile_record(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    type_: &ssf::types::Record,
+    elements: Vec<fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let compile_payload = || -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+        Ok(fmm::build::record(shadow_stacks::compile_loads(
+            context,
+            instruction_builder,
+            &elements,
+        )?)
+        .into())
+    };
+
+    if !type_.is_boxed() {
+        return compile_payload();
+    }
+
+    let pointer = allocation::compile_payload_allocation(
+        context,
+        instruction_builder,
+        fmm::types::Record::new(
+            elements
+                .iter()
+                .map(|element| element.type_().clone())
+                .collect(),
+        ),
+    )?;
+    let payload = compile_payload()?;
+
+    instruction_builder.store(
+        payload.clone(),
+        fmm::build::bit_cast(
+            fmm::types::Pointer::new(payload.type_().clone()),
+            pointer.clone(),
+        ),
+    );
+
+    Ok(fmm::build::bit_cast(types::compile(&type_.clone().into()), pointer).into())
+}
+
+fn compile_record_element(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    element: &ssf::ir::RecordElement,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let record = compile(context, instruction_builder, element.record(), variables)?;
+    let value = if element.type_().is_boxed() {
+        instruction_builder.load(fmm::build::record_address(
+            fmm::build::bit_cast(types::compile_boxed_record(element.type_()), record.clone()),
+            element.index(),
+        )?)?
+    } else {
+        instruction_builder.deconstruct_record(record.clone(), element.index())?
+    };
+
+    reference_counts::compile_retain(context, instruction_builder, &value)?;
+    reference_counts::compile_release(context, instruction_builder, &record)?;
+
+    Ok(value)
+}
+
+// Elements not updated are copied from old records into new ones.
+fn compile_record_update(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    update: &ssf::ir::RecordUpdate,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let record = shadow_stacks::compile_push(
+        context,
+        instruction_builder,
+        &compile(context, instruction_builder, update.record(), variables)?,
+    )?;
+    let mut updated_elements = HashMap::new();
+
+    for (index, element) in update.elements() {
+        let element = shadow_stacks::compile_push(
+            context,
+            instruction_builder,
+            &compile(context, instruction_builder, element, variables)?,
+        )?;
+
+        if let Some(element) = updated_elements.insert(*index, element) {
+            reference_counts::compile_release(context, instruction_builder, &element)?;
+        }
+    }
+
+    let record = shadow_stacks::compile_load(context, instruction_builder, &record)?;
+    let old_elements = if update.type_().is_boxed() {
+        instruction_builder.load(fmm::build::bit_cast(
+            types::compile_boxed_record(update.type_()),
+            record.clone(),
+        ))?
+    } else {
+        record.clone()
+    };
+    let elements = (0..update.type_().elements().len())
+        .map(|index| {
+            Ok(if let Some(element) = updated_elements.remove(&index) {
+                element
+            } else {
+                let element =
+                    instruction_builder.deconstruct_record(old_elements.clone(), index)?;
+
+                reference_counts::compile_retain(context, instruction_builder, &element)?;
+
+                shadow_stacks::compile_push(context, instruction_builder, &element)?
+            })
+        })
+        .collect::<Result<Vec<_>, fmm::build::BuildError>>()?;
+
+    reference_counts::compile_release(context, instruction_builder, &record)?;
+
+    compile_record(context, instruction_builder, update.type_(), elements)
 }
 
 fn compile_arithmetic_operation(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     operation: &ssf::ir::ArithmeticOperation,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::ir::ArithmeticOperation, fmm::build::BuildError> {
-    let compile = |expression| compile(module_builder, instruction_builder, expression, variables);
+    let compile = |expression| compile(context, instruction_builder, expression, variables);
 
     let lhs = compile(operation.lhs())?;
     let rhs = compile(operation.rhs())?;
@@ -420,43 +1566,77 @@ fn compile_arithmetic_operation(
 }
 
 fn compile_comparison_operation(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     operation: &ssf::ir::ComparisonOperation,
     variables: &HashMap<String, fmm::build::TypedExpression>,
 ) -> Result<fmm::ir::ComparisonOperation, fmm::build::BuildError> {
-    let compile = |expression| compile(module_builder, instruction_builder, expression, variables);
+    let compile = |expression| compile(context, instruction_builder, expression, variables);
 
     let lhs = compile(operation.lhs())?;
     let rhs = compile(operation.rhs())?;
+    let signed = !matches!(lhs.type_(), fmm::types::Type::Pointer(_));
 
     fmm::build::comparison_operation(
         match operation.operator() {
             ssf::ir::ComparisonOperator::Equal => fmm::ir::ComparisonOperator::Equal,
             ssf::ir::ComparisonOperator::NotEqual => fmm::ir::ComparisonOperator::NotEqual,
             ssf::ir::ComparisonOperator::GreaterThan => {
-                fmm::ir::ComparisonOperator::GreaterThan(true)
+                fmm::ir::ComparisonOperator::GreaterThan(signed)
             }
             ssf::ir::ComparisonOperator::GreaterThanOrEqual => {
-                fmm::ir::ComparisonOperator::GreaterThanOrEqual(true)
+                fmm::ir::ComparisonOperator::GreaterThanOrEqual(signed)
             }
-            ssf::ir::ComparisonOperator::LessThan => fmm::ir::ComparisonOperator::LessThan(true),
+            ssf::ir::ComparisonOperator::LessThan => fmm::ir::ComparisonOperator::LessThan(signed),
             ssf::ir::ComparisonOperator::LessThanOrEqual => {
-                fmm::ir::ComparisonOperator::LessThanOrEqual(true)
+                fmm::ir::ComparisonOperator::LessThanOrEqual(signed)
             }
         },
-        lhs,
-        rhs,
+        compile_comparable(lhs),
+        compile_comparable(rhs),
     )
 }
 
-fn compile_primitive(primitive: &ssf::ir::Primitive) -> fmm::ir::Primitive {
+// Pointers are compared as unsigned integers of their addresses.
+fn compile_comparable(value: fmm::build::TypedExpression) -> fmm::build::TypedExpression {
+    if let fmm::types::Type::Pointer(_) = value.type_() {
+        fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, value).into()
+    } else {
+        value
+    }
+}
+
+// Pointers are offset by bytes as they are compiled into generic pointers.
+fn compile_pointer_offset(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    offset: &ssf::ir::PointerOffset,
+    variables: &HashMap<String, fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let compile = |expression| compile(context, instruction_builder, expression, variables);
+
+    Ok(fmm::build::pointer_address(
+        compile(offset.pointer())?,
+        fmm::build::bit_cast(
+            fmm::types::Primitive::PointerInteger,
+            compile(offset.offset())?,
+        ),
+    )?
+    .into())
+}
+
+fn compile_primitive(primitive: &ssf::ir::Primitive) -> fmm::build::TypedExpression {
     match primitive {
-        ssf::ir::Primitive::Boolean(boolean) => fmm::ir::Primitive::Boolean(*boolean),
-        ssf::ir::Primitive::Float32(number) => fmm::ir::Primitive::Float32(*number),
-        ssf::ir::Primitive::Float64(number) => fmm::ir::Primitive::Float64(*number),
-        ssf::ir::Primitive::Integer8(number) => fmm::ir::Primitive::Integer8(*number),
-        ssf::ir::Primitive::Integer32(number) => fmm::ir::Primitive::Integer32(*number),
-        ssf::ir::Primitive::Integer64(number) => fmm::ir::Primitive::Integer64(*number),
+        ssf::ir::Primitive::Boolean(boolean) => fmm::ir::Primitive::Boolean(*boolean).into(),
+        ssf::ir::Primitive::Float32(number) => fmm::ir::Primitive::Float32(*number).into(),
+        ssf::ir::Primitive::Float64(number) => fmm::ir::Primitive::Float64(*number).into(),
+        ssf::ir::Primitive::Integer8(number) => fmm::ir::Primitive::Integer8(*number).into(),
+        ssf::ir::Primitive::Integer32(number) => fmm::ir::Primitive::Integer32(*number).into(),
+        ssf::ir::Primitive::Integer64(number) => fmm::ir::Primitive::Integer64(*number).into(),
+        ssf::ir::Primitive::NullPointer => fmm::build::bit_cast(
+            types::compile_generic_pointer(),
+            fmm::ir::Primitive::PointerInteger(0),
+        )
+        .into(),
     }
 }
@@ -1,19 +1,24 @@
 use crate::{
-    expressions,
+    context::Context,
+    expressions, reference_counts,
     types::{self, FUNCTION_ARGUMENT_OFFSET},
 };
 
 pub fn compile_foreign_declaration(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     declaration: &ssf::ir::ForeignDeclaration,
 ) -> Result<(), fmm::build::BuildError> {
-    module_builder.define_variable(
+    context.module_builder().define_variable(
         declaration.name(),
-        fmm::build::record(vec![
-            compile_entry_function(module_builder, declaration)?,
-            expressions::compile_arity(declaration.type_().arguments().into_iter().count()).into(),
-            fmm::ir::Undefined::new(types::compile_unsized_environment()).into(),
-        ]),
+        reference_counts::compile_static_object(
+            context,
+            fmm::build::record(vec![
+                compile_entry_function(context, declaration)?,
+                expressions::compile_arity(declaration.type_().arguments().into_iter().count())
+                    .into(),
+                fmm::ir::Undefined::new(types::compile_unsized_environment()).into(),
+            ]),
+        ),
         fmm::ir::VariableDefinitionOptions::new()
             .set_linkage(fmm::ir::Linkage::Internal)
             .set_mutable(false),
@@ -23,7 +28,7 @@ pub fn compile_foreign_declaration(
 }
 
 fn compile_entry_function(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     declaration: &ssf::ir::ForeignDeclaration,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
     let arguments = vec![fmm::ir::Argument::new(
@@ -46,13 +51,13 @@ fn compile_entry_function(
     let foreign_function_type =
         types::compile_foreign_function(declaration.type_(), declaration.calling_convention());
 
-    module_builder.define_anonymous_function(
+    context.module_builder().define_anonymous_function(
         arguments.clone(),
         foreign_function_type.result().clone(),
         |instruction_builder| {
             Ok(instruction_builder.return_(
                 instruction_builder.call(
-                    module_builder.declare_function(
+                    context.module_builder().declare_function(
                         declaration.foreign_name(),
                         foreign_function_type.clone(),
                     ),
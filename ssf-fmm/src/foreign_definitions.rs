@@ -1,7 +1,7 @@
-use crate::{function_applications, types};
+use crate::{context::Context, function_applications, types};
 
 pub fn compile_foreign_definition(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     definition: &ssf::ir::ForeignDefinition,
     function_type: &ssf::types::Function,
     global_variable: &fmm::build::TypedExpression,
@@ -16,13 +16,13 @@ pub fn compile_foreign_definition(
         .map(|(index, type_)| fmm::ir::Argument::new(format!("arg_{index}"), type_.clone()))
         .collect::<Vec<_>>();
 
-    module_builder.define_function(
+    context.module_builder().define_function(
         definition.foreign_name(),
         arguments.clone(),
         foreign_function_type.result().clone(),
         |instruction_builder| {
             Ok(instruction_builder.return_(function_applications::compile(
-                module_builder,
+                context,
                 &instruction_builder,
                 global_variable.clone(),
                 &arguments
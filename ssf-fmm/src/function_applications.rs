@@ -1,50 +1,143 @@
-use super::{closures, expressions, types};
+use super::{
+    allocation, closures, context::Context, expressions, reference_counts, shadow_stacks, types,
+};
+
+const CLOSURE_ARGUMENT_NAME: &str = "_closure";
 
 pub fn compile(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     closure_pointer: fmm::build::TypedExpression,
     arguments: &[fmm::build::TypedExpression],
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
-    compile_with_min_arity(
-        module_builder,
+    compile_with_closure_slot(
+        context,
         instruction_builder,
         closure_pointer,
         arguments,
-        1,
+        None,
     )
 }
 
+// Closure slots are used instead of heap if closures are partially applied
+// to all the arguments.
+//
+// Applications of multiple arguments to closures of unknown arities call
+// apply functions shared by all applications of the same types so that
+// dispatch on arities is not duplicated at every call site.
+pub fn compile_with_closure_slot(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    closure_pointer: fmm::build::TypedExpression,
+    arguments: &[fmm::build::TypedExpression],
+    closure_slot: Option<&fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    if closure_slot.is_none() && arguments.len() > 1 {
+        instruction_builder.call(
+            compile_apply_function(
+                context,
+                closure_pointer.type_(),
+                &arguments
+                    .iter()
+                    .map(|argument| argument.type_().clone())
+                    .collect::<Vec<_>>(),
+            )?,
+            shadow_stacks::compile_loads(
+                context,
+                instruction_builder,
+                &vec![closure_pointer]
+                    .into_iter()
+                    .chain(arguments.iter().cloned())
+                    .collect::<Vec<_>>(),
+            )?,
+        )
+    } else {
+        compile_with_min_arity(
+            context,
+            instruction_builder,
+            closure_pointer,
+            arguments,
+            1,
+            closure_slot,
+        )
+    }
+}
+
+pub fn compile_known(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    entry_function: fmm::build::TypedExpression,
+    closure_pointer: fmm::build::TypedExpression,
+    arguments: &[fmm::build::TypedExpression],
+    closure_slot: Option<&fmm::build::TypedExpression>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let arity = types::get_arity(entry_function.type_().to_function().unwrap());
+
+    if arguments.len() < arity {
+        return compile_with_closure_slot(
+            context,
+            instruction_builder,
+            closure_pointer,
+            arguments,
+            closure_slot,
+        );
+    }
+
+    let result = instruction_builder.call(
+        entry_function,
+        vec![closures::compile_environment_pointer(
+            shadow_stacks::compile_load(context, instruction_builder, &closure_pointer)?,
+        )?]
+        .into_iter()
+        .chain(shadow_stacks::compile_loads(
+            context,
+            instruction_builder,
+            &arguments[..arity],
+        )?)
+        .collect(),
+    )?;
+
+    reference_counts::compile_release(context, instruction_builder, &closure_pointer)?;
+
+    compile(context, instruction_builder, result, &arguments[arity..])
+}
+
 fn compile_with_min_arity(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     closure_pointer: fmm::build::TypedExpression,
     arguments: &[fmm::build::TypedExpression],
     min_arity: usize,
+    closure_slot: Option<&fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
     Ok(if arguments.is_empty() {
-        closure_pointer
+        shadow_stacks::compile_load(context, instruction_builder, &closure_pointer)?
     } else if arguments.len() < min_arity {
         compile_create_closure(
-            module_builder,
+            context,
             instruction_builder,
             closure_pointer,
             arguments,
+            closure_slot,
         )?
     } else if types::get_arity(get_entry_function_type(&closure_pointer)) == min_arity {
-        compile_direct_call(instruction_builder, closure_pointer, arguments)?
+        compile_owned_direct_call(context, instruction_builder, closure_pointer, arguments)?
     } else {
         instruction_builder.if_(
             fmm::build::comparison_operation(
                 fmm::ir::ComparisonOperator::Equal,
-                closures::compile_load_arity(instruction_builder, closure_pointer.clone())?,
+                closures::compile_load_arity(
+                    instruction_builder,
+                    shadow_stacks::compile_load(context, instruction_builder, &closure_pointer)?,
+                )?,
                 expressions::compile_arity(min_arity),
             )?,
             |instruction_builder| {
                 Ok(instruction_builder.branch(compile(
-                    module_builder,
+                    context,
                     &instruction_builder,
-                    compile_direct_call(
+                    compile_owned_direct_call(
+                        context,
                         &instruction_builder,
                         closure_pointer.clone(),
                         &arguments[..min_arity],
@@ -54,42 +147,184 @@ fn compile_with_min_arity(
             },
             |instruction_builder| {
                 Ok(instruction_builder.branch(compile_with_min_arity(
-                    module_builder,
+                    context,
                     &instruction_builder,
                     closure_pointer.clone(),
                     arguments,
                     min_arity + 1,
+                    closure_slot,
                 )?))
             },
         )?
     })
 }
 
+fn compile_apply_function(
+    context: &Context,
+    closure_pointer_type: &fmm::types::Type,
+    argument_types: &[fmm::types::Type],
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let function_type = fmm::types::Function::new(
+        vec![closure_pointer_type.clone()]
+            .into_iter()
+            .chain(argument_types.iter().cloned())
+            .collect(),
+        compile_application_result_type(closure_pointer_type, argument_types.len()),
+        fmm::types::CallingConvention::Source,
+    );
+
+    if let Some(function) = context.find_apply_function(&function_type) {
+        return Ok(function);
+    }
+
+    let arguments = argument_types
+        .iter()
+        .enumerate()
+        .map(|(index, type_)| fmm::ir::Argument::new(format!("arg_{index}"), type_.clone()))
+        .collect::<Vec<_>>();
+
+    let function = context.module_builder().define_anonymous_function(
+        vec![fmm::ir::Argument::new(
+            CLOSURE_ARGUMENT_NAME,
+            closure_pointer_type.clone(),
+        )]
+        .into_iter()
+        .chain(arguments.iter().cloned())
+        .collect(),
+        function_type.result().clone(),
+        |instruction_builder| {
+            Ok(instruction_builder.return_(shadow_stacks::compile_block(
+                context,
+                &instruction_builder,
+                || {
+                    // Arguments are applied after calls which might allocate
+                    // memory.
+                    let arguments = shadow_stacks::compile_pushes(
+                        context,
+                        &instruction_builder,
+                        &vec![fmm::build::variable(
+                            CLOSURE_ARGUMENT_NAME,
+                            closure_pointer_type.clone(),
+                        )]
+                        .into_iter()
+                        .chain(arguments.iter().map(|argument| {
+                            fmm::build::variable(argument.name(), argument.type_().clone())
+                        }))
+                        .collect::<Vec<_>>(),
+                    )?;
+
+                    compile_with_min_arity(
+                        context,
+                        &instruction_builder,
+                        arguments[0].clone(),
+                        &arguments[1..],
+                        1,
+                        None,
+                    )
+                },
+            )?))
+        },
+        fmm::ir::FunctionDefinitionOptions::new()
+            .set_calling_convention(fmm::types::CallingConvention::Source),
+    )?;
+
+    context.add_apply_function(function_type, function.clone());
+
+    Ok(function)
+}
+
+fn compile_application_result_type(
+    closure_pointer_type: &fmm::types::Type,
+    argument_count: usize,
+) -> fmm::types::Type {
+    let entry_function_type = get_entry_function_type_from_pointer(closure_pointer_type);
+    let arity = types::get_arity(entry_function_type);
+
+    if argument_count <= arity {
+        types::compile_curried_entry_function(entry_function_type, argument_count)
+            .result()
+            .clone()
+    } else {
+        compile_application_result_type(entry_function_type.result(), argument_count - arity)
+    }
+}
+
+fn compile_owned_direct_call(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    closure_pointer: fmm::build::TypedExpression,
+    arguments: &[fmm::build::TypedExpression],
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let result = compile_direct_call(
+        context,
+        instruction_builder,
+        closure_pointer.clone(),
+        arguments,
+    )?;
+
+    reference_counts::compile_release(context, instruction_builder, &closure_pointer)?;
+
+    Ok(result)
+}
+
 fn compile_direct_call(
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     closure_pointer: fmm::build::TypedExpression,
     arguments: &[fmm::build::TypedExpression],
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let closure_pointer =
+        shadow_stacks::compile_load(context, instruction_builder, &closure_pointer)?;
+
     instruction_builder.call(
         fmm::build::bit_cast(
             types::compile_curried_entry_function(
                 get_entry_function_type(&closure_pointer),
                 arguments.len(),
             ),
-            closures::compile_load_entry_pointer(instruction_builder, closure_pointer.clone())?,
+            closures::compile_load_entry_pointer(
+                context,
+                instruction_builder,
+                closure_pointer.clone(),
+            )?,
         ),
         vec![closures::compile_environment_pointer(closure_pointer)?]
             .into_iter()
-            .chain(arguments.iter().cloned())
+            .chain(shadow_stacks::compile_loads(
+                context,
+                instruction_builder,
+                arguments,
+            )?)
             .collect(),
     )
 }
 
+pub fn compile_closure_slot(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    closure_pointer: &fmm::build::TypedExpression,
+    arguments: &[fmm::build::TypedExpression],
+) -> fmm::build::TypedExpression {
+    // Entry functions of partially-applied closures have different types but
+    // the same size.
+    allocation::compile_stack_allocation(
+        instruction_builder,
+        types::compile_raw_closure(
+            get_entry_function_type(closure_pointer).clone(),
+            types::compile_raw_environment(
+                vec![closure_pointer.type_().clone()]
+                    .into_iter()
+                    .chain(arguments.iter().map(|argument| argument.type_().clone())),
+            ),
+        ),
+    )
+}
+
 fn compile_create_closure(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     instruction_builder: &fmm::build::InstructionBuilder,
     closure_pointer: fmm::build::TypedExpression,
     arguments: &[fmm::build::TypedExpression],
+    closure_slot: Option<&fmm::build::TypedExpression>,
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
     let entry_function_type = get_entry_function_type(&closure_pointer);
 
@@ -108,30 +343,48 @@ fn compile_create_closure(
         fmm::types::CallingConvention::Source,
     );
 
-    let closure = closures::compile_closure_content(
-        compile_partially_applied_entry_function(
-            module_builder,
-            &target_entry_function_type,
-            closure_pointer.type_(),
-            &arguments
-                .iter()
-                .map(|argument| argument.type_())
+    let entry_function = compile_partially_applied_entry_function(
+        context,
+        &target_entry_function_type,
+        closure_pointer.type_(),
+        &arguments
+            .iter()
+            .map(|argument| argument.type_())
+            .collect::<Vec<_>>(),
+    )?;
+    let height = shadow_stacks::compile_save(context, instruction_builder)?;
+    let environment = shadow_stacks::compile_pushes(
+        context,
+        instruction_builder,
+        &shadow_stacks::compile_loads(
+            context,
+            instruction_builder,
+            &vec![closure_pointer]
+                .into_iter()
+                .chain(arguments.iter().cloned())
                 .collect::<Vec<_>>(),
         )?,
-        vec![closure_pointer]
-            .into_iter()
-            .chain(arguments.iter().cloned())
-            .collect::<Vec<_>>(),
-    );
-    let closure_pointer =
-        instruction_builder.allocate_heap(fmm::build::size_of(closure.type_().clone()));
+    )?;
+    let closure_type =
+        closures::compile_closure_content(entry_function.clone(), environment.clone())
+            .type_()
+            .clone();
+    let closure_pointer = if let Some(closure_slot) = closure_slot {
+        closure_slot.clone()
+    } else {
+        allocation::compile_closure_allocation(context, instruction_builder, closure_type.clone())?
+    };
     instruction_builder.store(
-        closure.clone(),
+        closures::compile_closure_content(
+            entry_function,
+            shadow_stacks::compile_loads(context, instruction_builder, &environment)?,
+        ),
         fmm::build::bit_cast(
-            fmm::types::Pointer::new(closure.type_().clone()),
+            fmm::types::Pointer::new(closure_type),
             closure_pointer.clone(),
         ),
     );
+    shadow_stacks::compile_restore(context, instruction_builder, height)?;
 
     Ok(fmm::build::bit_cast(
         fmm::types::Pointer::new(types::compile_raw_closure(
@@ -144,11 +397,26 @@ fn compile_create_closure(
 }
 
 fn compile_partially_applied_entry_function(
-    module_builder: &fmm::build::ModuleBuilder,
+    context: &Context,
     entry_function_type: &fmm::types::Function,
     closure_pointer_type: &fmm::types::Type,
     argument_types: &[&fmm::types::Type],
 ) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let environment_type = fmm::types::Record::new(
+        vec![closure_pointer_type.clone()]
+            .into_iter()
+            .chain(argument_types.iter().cloned().cloned())
+            .collect(),
+    );
+
+    // Entry functions are shared by all partial applications of the same
+    // types.
+    if let Some(function) =
+        context.find_partially_applied_entry_function(entry_function_type, &environment_type)
+    {
+        return Ok(function);
+    }
+
     let curried_entry_function_type = types::compile_curried_entry_function(entry_function_type, 1);
     let arguments = curried_entry_function_type
         .arguments()
@@ -157,19 +425,16 @@ fn compile_partially_applied_entry_function(
         .map(|(index, type_)| fmm::ir::Argument::new(format!("arg_{index}"), type_.clone()))
         .collect::<Vec<_>>();
 
-    module_builder.define_anonymous_function(
+    let function = context.module_builder().define_anonymous_function(
         arguments.clone(),
         curried_entry_function_type.result().clone(),
         |instruction_builder| {
             let environment = instruction_builder.load(fmm::build::bit_cast(
-                fmm::types::Pointer::new(fmm::types::Record::new(
-                    vec![closure_pointer_type.clone()]
-                        .into_iter()
-                        .chain(argument_types.iter().cloned().cloned())
-                        .collect(),
-                )),
+                fmm::types::Pointer::new(environment_type.clone()),
                 fmm::build::variable(arguments[0].name(), arguments[0].type_().clone()),
             ))?;
+            // Closures and arguments in environments are borrowed from
+            // partially-applied closures.
             let closure_pointer = instruction_builder.deconstruct_record(environment.clone(), 0)?;
             let arguments = (0..argument_types.len())
                 .map(|index| instruction_builder.deconstruct_record(environment.clone(), index + 1))
@@ -179,9 +444,13 @@ fn compile_partially_applied_entry_function(
                 ))])
                 .collect::<Result<Vec<_>, _>>()?;
 
+            for argument in &arguments[..argument_types.len()] {
+                reference_counts::compile_retain(context, &instruction_builder, argument)?;
+            }
+
             Ok(instruction_builder.return_(
                 if types::get_arity(get_entry_function_type(&closure_pointer)) == arguments.len() {
-                    compile_direct_call(&instruction_builder, closure_pointer, &arguments)?
+                    compile_direct_call(context, &instruction_builder, closure_pointer, &arguments)?
                 } else {
                     instruction_builder.if_(
                         fmm::build::comparison_operation(
@@ -194,17 +463,25 @@ fn compile_partially_applied_entry_function(
                         )?,
                         |instruction_builder| {
                             Ok(instruction_builder.branch(compile_direct_call(
+                                context,
                                 &instruction_builder,
                                 closure_pointer.clone(),
                                 &arguments,
                             )?))
                         },
                         |instruction_builder| {
+                            reference_counts::compile_retain(
+                                context,
+                                &instruction_builder,
+                                &closure_pointer,
+                            )?;
+
                             Ok(instruction_builder.branch(compile_create_closure(
-                                module_builder,
+                                context,
                                 &instruction_builder,
                                 closure_pointer.clone(),
                                 &arguments,
+                                None,
                             )?))
                         },
                     )?
@@ -213,12 +490,25 @@ fn compile_partially_applied_entry_function(
         },
         fmm::ir::FunctionDefinitionOptions::new()
             .set_calling_convention(fmm::types::CallingConvention::Source),
-    )
+    )?;
+
+    context.add_partially_applied_entry_function(
+        entry_function_type.clone(),
+        environment_type,
+        function.clone(),
+    );
+
+    Ok(function)
 }
 
 fn get_entry_function_type(closure_pointer: &fmm::build::TypedExpression) -> &fmm::types::Function {
-    closure_pointer
-        .type_()
+    get_entry_function_type_from_pointer(closure_pointer.type_())
+}
+
+fn get_entry_function_type_from_pointer(
+    closure_pointer_type: &fmm::types::Type,
+) -> &fmm::types::Function {
+    closure_pointer_type
         .to_pointer()
         .unwrap()
         .element()
@@ -0,0 +1,604 @@
+use crate::{
+    configuration::{AllocationStrategy, CompileConfiguration},
+    context::Context,
+    types,
+};
+
+// Paths are indices of record fields and union members from the beginning of
+// objects. Their offsets are computed by targets.
+type Path = Vec<usize>;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Layout {
+    pointers: Vec<Path>,
+    variants: Vec<Variant>,
+}
+
+impl Layout {
+    fn extend(&mut self, other: Self) {
+        self.pointers.extend(other.pointers);
+        self.variants.extend(other.variants);
+    }
+}
+
+// Pointers in variants are valid only if all of their conditions hold.
+#[derive(Clone, Debug, PartialEq)]
+struct Variant {
+    conditions: Vec<Condition>,
+    pointers: Vec<Path>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Condition {
+    path: Path,
+    value: fmm::build::TypedExpression,
+}
+
+// Thunks contain their environments while their entry functions are initial
+// ones and their values after the entry functions are updated to normal ones.
+// They contain neither of them reachable from the thunks while they are
+// locked.
+pub struct ThunkEntryFunctions {
+    pub initial: fmm::build::TypedExpression,
+    pub normal: fmm::build::TypedExpression,
+}
+
+pub fn is_enabled(configuration: &CompileConfiguration) -> bool {
+    configuration.shadow_stack.is_some()
+        || matches!(
+            configuration.allocation_strategy,
+            AllocationStrategy::Described(_)
+        )
+}
+
+pub fn compile_layout_descriptor(
+    context: &Context,
+    type_: &fmm::types::Type,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let descriptor = if let Some(descriptor) = context.find_layout_descriptor(type_) {
+        descriptor
+    } else {
+        let descriptor =
+            compile_new_layout_descriptor(context, type_, &compute_layout(context, type_, None))?;
+
+        context.add_layout_descriptor(type_.clone(), descriptor.clone());
+
+        descriptor
+    };
+
+    Ok(fmm::build::bit_cast(types::compile_generic_pointer(), descriptor).into())
+}
+
+// Layout descriptors of thunks are not shared among different definitions
+// because their conditions refer to their own entry functions.
+pub fn compile_thunk_layout_descriptor(
+    context: &Context,
+    type_: &fmm::types::Type,
+    entry_functions: &ThunkEntryFunctions,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::bit_cast(
+        types::compile_generic_pointer(),
+        compile_new_layout_descriptor(
+            context,
+            type_,
+            &compute_layout(context, type_, Some(entry_functions)),
+        )?,
+    )
+    .into())
+}
+
+fn compile_new_layout_descriptor(
+    context: &Context,
+    type_: &fmm::types::Type,
+    layout: &Layout,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(context.module_builder().define_variable(
+        context.module_builder().generate_name(),
+        fmm::build::record(compile_layout_descriptor_fields(type_, layout)?),
+        fmm::ir::VariableDefinitionOptions::new()
+            .set_linkage(fmm::ir::Linkage::Internal)
+            .set_mutable(false),
+    ))
+}
+
+fn compile_layout_descriptor_fields(
+    type_: &fmm::types::Type,
+    layout: &Layout,
+) -> Result<Vec<fmm::build::TypedExpression>, fmm::build::BuildError> {
+    let mut fields = vec![fmm::build::size_of(type_.clone())];
+
+    fields.extend(compile_pointer_offsets(type_, &layout.pointers)?);
+    fields.push(compile_count(layout.variants.len()));
+
+    for variant in &layout.variants {
+        fields.push(compile_count(variant.conditions.len()));
+
+        for condition in &variant.conditions {
+            let (offset, field_type) = compile_offset(type_, &condition.path)?;
+
+            fields.extend(vec![
+                offset,
+                fmm::build::size_of(field_type),
+                condition.value.clone(),
+            ]);
+        }
+
+        fields.extend(compile_pointer_offsets(type_, &variant.pointers)?);
+    }
+
+    Ok(fields)
+}
+
+fn compile_pointer_offsets(
+    type_: &fmm::types::Type,
+    paths: &[Path],
+) -> Result<Vec<fmm::build::TypedExpression>, fmm::build::BuildError> {
+    let mut fields = vec![compile_count(paths.len())];
+
+    for path in paths {
+        fields.push(compile_offset(type_, path)?.0);
+    }
+
+    Ok(fields)
+}
+
+fn compile_count(count: usize) -> fmm::build::TypedExpression {
+    fmm::ir::Primitive::PointerInteger(count as i64).into()
+}
+
+// Offsets are addresses of fields in objects at null pointers so that they
+// respect sizes and alignments of fields on each target.
+fn compile_offset(
+    type_: &fmm::types::Type,
+    path: &[usize],
+) -> Result<(fmm::build::TypedExpression, fmm::types::Type), fmm::build::BuildError> {
+    let mut pointer: fmm::build::TypedExpression = fmm::build::bit_cast(
+        fmm::types::Pointer::new(type_.clone()),
+        fmm::ir::Primitive::PointerInteger(0),
+    )
+    .into();
+    let mut type_ = type_.clone();
+
+    for &index in path {
+        let (field_pointer, field_type) = match &type_ {
+            fmm::types::Type::Record(record) => (
+                fmm::build::record_address(pointer, index)?.into(),
+                record.fields()[index].clone(),
+            ),
+            fmm::types::Type::Union(union) => {
+                let member = union.members()[index].clone();
+
+                (
+                    fmm::build::bit_cast(fmm::types::Pointer::new(member.clone()), pointer).into(),
+                    member,
+                )
+            }
+            fmm::types::Type::Function(_)
+            | fmm::types::Type::Pointer(_)
+            | fmm::types::Type::Primitive(_) => unreachable!(),
+        };
+
+        pointer = field_pointer;
+        type_ = field_type;
+    }
+
+    Ok((
+        fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, pointer).into(),
+        type_,
+    ))
+}
+
+fn compute_layout(
+    context: &Context,
+    type_: &fmm::types::Type,
+    thunk_entry_functions: Option<&ThunkEntryFunctions>,
+) -> Layout {
+    match type_ {
+        fmm::types::Type::Function(_) | fmm::types::Type::Primitive(_) => Layout::default(),
+        fmm::types::Type::Pointer(_) => Layout {
+            pointers: vec![vec![]],
+            variants: vec![],
+        },
+        fmm::types::Type::Record(record) => {
+            let mut layout = Layout::default();
+
+            for (index, field) in record.fields().iter().enumerate() {
+                if let fmm::types::Type::Union(union) = field {
+                    layout.extend(compute_union_layout(
+                        context,
+                        record,
+                        union,
+                        index,
+                        thunk_entry_functions,
+                    ));
+                } else {
+                    layout.extend(prefix_layout(
+                        index,
+                        compute_layout(context, field, thunk_entry_functions),
+                    ));
+                }
+            }
+
+            layout
+        }
+        // Unions are always contained in records with their discriminants.
+        fmm::types::Type::Union(_) => unreachable!(),
+    }
+}
+
+// Unions are only in algebraic data types and thunks. Untagged unions have
+// single members except the ones of null pointer niches where the other
+// members contain no pointers.
+fn compute_union_layout(
+    context: &Context,
+    record: &fmm::types::Record,
+    union: &fmm::types::Union,
+    index: usize,
+    thunk_entry_functions: Option<&ThunkEntryFunctions>,
+) -> Layout {
+    if types::is_pointer_free(&union.clone().into()) {
+        return Layout::default();
+    }
+
+    let compute_member_layout = |member_index: usize| {
+        prefix_layout(
+            index,
+            prefix_layout(
+                member_index,
+                compute_layout(
+                    context,
+                    &union.members()[member_index],
+                    thunk_entry_functions,
+                ),
+            ),
+        )
+    };
+
+    if record.fields().len() == 1 {
+        let mut layout = Layout::default();
+
+        for member_index in 0..union.members().len() {
+            layout.extend(compute_member_layout(member_index));
+        }
+
+        layout
+    } else if let Some(tags) = context.find_algebraic_tags(record) {
+        Layout {
+            pointers: vec![],
+            variants: tags
+                .iter()
+                .flat_map(|(tag, member_index)| {
+                    compute_variants(
+                        Condition {
+                            path: vec![0],
+                            value: fmm::ir::Primitive::PointerInteger(*tag as i64).into(),
+                        },
+                        compute_member_layout(*member_index),
+                    )
+                })
+                .collect(),
+        }
+    } else if let Some(entry_functions) = thunk_entry_functions {
+        Layout {
+            pointers: vec![],
+            variants: [&entry_functions.initial, &entry_functions.normal]
+                .iter()
+                .enumerate()
+                .flat_map(|(member_index, entry_function)| {
+                    compute_variants(
+                        Condition {
+                            path: vec![0],
+                            value: fmm::build::bit_cast(
+                                fmm::types::Primitive::PointerInteger,
+                                (*entry_function).clone(),
+                            )
+                            .into(),
+                        },
+                        compute_member_layout(member_index),
+                    )
+                })
+                .collect(),
+        }
+    } else {
+        unreachable!()
+    }
+}
+
+fn compute_variants(condition: Condition, layout: Layout) -> Vec<Variant> {
+    vec![Variant {
+        conditions: vec![condition.clone()],
+        pointers: layout.pointers,
+    }]
+    .into_iter()
+    .chain(layout.variants.into_iter().map(|variant| {
+        Variant {
+            conditions: vec![condition.clone()]
+                .into_iter()
+                .chain(variant.conditions)
+                .collect(),
+            pointers: variant.pointers,
+        }
+    }))
+    .filter(|variant| !variant.pointers.is_empty())
+    .collect()
+}
+
+fn prefix_layout(index: usize, layout: Layout) -> Layout {
+    let prefix_path = |path: Path| vec![index].into_iter().chain(path).collect::<Path>();
+
+    Layout {
+        pointers: layout.pointers.into_iter().map(prefix_path).collect(),
+        variants: layout
+            .variants
+            .into_iter()
+            .map(|variant| Variant {
+                conditions: variant
+                    .conditions
+                    .into_iter()
+                    .map(|condition| Condition {
+                        path: prefix_path(condition.path),
+                        value: condition.value,
+                    })
+                    .collect(),
+                pointers: variant.pointers.into_iter().map(prefix_path).collect(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebraic_tags;
+    use std::collections::BTreeMap;
+
+    fn create_context(algebraic_types: &[ssf::types::Algebraic]) -> Context {
+        Context::new(
+            Default::default(),
+            algebraic_tags::collect_algebraic_tags(algebraic_types).unwrap(),
+        )
+    }
+
+    fn pointer_type() -> fmm::types::Type {
+        fmm::types::Pointer::new(fmm::types::Primitive::Float64).into()
+    }
+
+    #[test]
+    fn compute_layout_of_primitive() {
+        assert_eq!(
+            compute_layout(
+                &create_context(&[]),
+                &fmm::types::Primitive::Float64.into(),
+                None
+            ),
+            Layout::default()
+        );
+    }
+
+    #[test]
+    fn compute_layout_of_pointer() {
+        assert_eq!(
+            compute_layout(&create_context(&[]), &pointer_type(), None),
+            Layout {
+                pointers: vec![vec![]],
+                variants: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn compute_layout_of_nested_record() {
+        assert_eq!(
+            compute_layout(
+                &create_context(&[]),
+                &fmm::types::Record::new(vec![
+                    fmm::types::Primitive::Integer8.into(),
+                    pointer_type(),
+                    fmm::types::Record::new(vec![
+                        pointer_type(),
+                        fmm::types::Primitive::Float64.into(),
+                    ])
+                    .into(),
+                ])
+                .into(),
+                None
+            ),
+            Layout {
+                pointers: vec![vec![1], vec![2, 0]],
+                variants: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn compute_layout_of_tagged_algebraic_type() {
+        let algebraic_type = ssf::types::Algebraic::with_tags(
+            vec![
+                (
+                    3,
+                    ssf::types::Constructor::unboxed(vec![ssf::types::Primitive::Float64.into()]),
+                ),
+                (
+                    7,
+                    ssf::types::Constructor::unboxed(vec![ssf::types::Array::new(
+                        ssf::types::Primitive::Float64,
+                    )
+                    .into()]),
+                ),
+            ]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>(),
+        );
+
+        assert_eq!(
+            compute_layout(
+                &create_context(&[algebraic_type.clone()]),
+                &types::compile_algebraic(&algebraic_type).into(),
+                None
+            ),
+            Layout {
+                pointers: vec![],
+                variants: vec![Variant {
+                    conditions: vec![Condition {
+                        path: vec![0],
+                        value: fmm::ir::Primitive::PointerInteger(7).into(),
+                    }],
+                    pointers: vec![vec![1, 1, 0]],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn compute_layout_of_nested_tagged_algebraic_types() {
+        let inner_type = ssf::types::Algebraic::new(vec![
+            ssf::types::Constructor::unboxed(vec![ssf::types::Primitive::Float64.into()]),
+            ssf::types::Constructor::unboxed(vec![ssf::types::Array::new(
+                ssf::types::Primitive::Float64,
+            )
+            .into()]),
+        ]);
+        let outer_type = ssf::types::Algebraic::new(vec![
+            ssf::types::Constructor::unboxed(vec![]),
+            ssf::types::Constructor::unboxed(vec![inner_type.clone().into()]),
+            ssf::types::Constructor::unboxed(vec![ssf::types::Primitive::Float64.into()]),
+        ]);
+
+        assert_eq!(
+            compute_layout(
+                &create_context(&[inner_type, outer_type.clone()]),
+                &types::compile_algebraic(&outer_type).into(),
+                None
+            ),
+            Layout {
+                pointers: vec![],
+                variants: vec![Variant {
+                    conditions: vec![
+                        Condition {
+                            path: vec![0],
+                            value: fmm::ir::Primitive::PointerInteger(1).into(),
+                        },
+                        Condition {
+                            path: vec![1, 1, 0, 0],
+                            value: fmm::ir::Primitive::PointerInteger(1).into(),
+                        },
+                    ],
+                    pointers: vec![vec![1, 1, 0, 1, 1, 0]],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn compute_layout_of_boxed_constructor_with_null_pointer_niche() {
+        let algebraic_type = ssf::types::Algebraic::new(vec![
+            ssf::types::Constructor::unboxed(vec![]),
+            ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+        ]);
+
+        assert_eq!(
+            compute_layout(
+                &create_context(&[algebraic_type.clone()]),
+                &types::compile_algebraic(&algebraic_type).into(),
+                None
+            ),
+            Layout {
+                pointers: vec![vec![0, 1]],
+                variants: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn compute_layout_of_thunk() {
+        let entry_function_type = fmm::types::Function::new(
+            vec![],
+            fmm::types::Primitive::Float64,
+            fmm::types::CallingConvention::Source,
+        );
+        let initial_entry_function = fmm::build::variable("initial", entry_function_type.clone());
+        let normal_entry_function = fmm::build::variable("normal", entry_function_type.clone());
+
+        assert_eq!(
+            compute_layout(
+                &create_context(&[]),
+                &types::compile_raw_closure(
+                    entry_function_type,
+                    fmm::types::Union::new(vec![
+                        fmm::types::Record::new(vec![pointer_type()]).into(),
+                        pointer_type(),
+                    ]),
+                )
+                .into(),
+                Some(&ThunkEntryFunctions {
+                    initial: initial_entry_function.clone().into(),
+                    normal: normal_entry_function.clone().into(),
+                })
+            ),
+            Layout {
+                pointers: vec![],
+                variants: vec![
+                    Variant {
+                        conditions: vec![Condition {
+                            path: vec![0],
+                            value: fmm::build::bit_cast(
+                                fmm::types::Primitive::PointerInteger,
+                                initial_entry_function,
+                            )
+                            .into(),
+                        }],
+                        pointers: vec![vec![2, 0, 0]],
+                    },
+                    Variant {
+                        conditions: vec![Condition {
+                            path: vec![0],
+                            value: fmm::build::bit_cast(
+                                fmm::types::Primitive::PointerInteger,
+                                normal_entry_function,
+                            )
+                            .into(),
+                        }],
+                        pointers: vec![vec![2, 1]],
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn compile_fields_of_layout_descriptor() {
+        let type_ = fmm::types::Type::from(fmm::types::Record::new(vec![
+            fmm::types::Primitive::Integer8.into(),
+            pointer_type(),
+        ]));
+
+        assert_eq!(
+            compile_layout_descriptor_fields(
+                &type_,
+                &Layout {
+                    pointers: vec![vec![1]],
+                    variants: vec![Variant {
+                        conditions: vec![Condition {
+                            path: vec![0],
+                            value: fmm::ir::Primitive::PointerInteger(42).into(),
+                        }],
+                        pointers: vec![vec![1]],
+                    }],
+                }
+            )
+            .unwrap(),
+            vec![
+                fmm::build::size_of(type_.clone()),
+                fmm::ir::Primitive::PointerInteger(1).into(),
+                compile_offset(&type_, &[1]).unwrap().0,
+                fmm::ir::Primitive::PointerInteger(1).into(),
+                fmm::ir::Primitive::PointerInteger(1).into(),
+                compile_offset(&type_, &[0]).unwrap().0,
+                fmm::build::size_of(fmm::types::Primitive::Integer8),
+                fmm::ir::Primitive::PointerInteger(42).into(),
+                fmm::ir::Primitive::PointerInteger(1).into(),
+                compile_offset(&type_, &[1]).unwrap().0,
+            ]
+        );
+    }
+}
@@ -1,4 +1,10 @@
+mod algebraic_layouts;
+mod algebraic_tags;
+mod allocation;
+mod arrays;
 mod closures;
+mod configuration;
+mod context;
 mod declarations;
 mod definitions;
 mod entry_functions;
@@ -7,8 +13,18 @@ mod expressions;
 mod foreign_declarations;
 mod foreign_definitions;
 mod function_applications;
+mod layout_descriptors;
+mod pointer_tags;
+mod reference_counts;
+mod shadow_stacks;
 mod types;
 
+pub use algebraic_layouts::{get_algebraic_layout, AlgebraicLayout};
+pub use configuration::{
+    AllocationFunctions, AllocationKind, AllocationStrategy, CompileConfiguration,
+    LockedThunkStrategy, ShadowStackFunctions, ThreadSafety,
+};
+use context::Context;
 use declarations::compile_declaration;
 use definitions::compile_definition;
 pub use error::CompileError;
@@ -16,23 +32,69 @@ use foreign_declarations::compile_foreign_declaration;
 use foreign_definitions::compile_foreign_definition;
 use std::collections::HashMap;
 
-pub fn compile(module: &ssf::ir::Module) -> Result<fmm::ir::Module, CompileError> {
+pub fn compile(
+    module: &ssf::ir::Module,
+    configuration: &CompileConfiguration,
+) -> Result<fmm::ir::Module, CompileError> {
+    configuration.validate()?;
     ssf::analysis::check_types(module)?;
 
-    let module_builder = fmm::build::ModuleBuilder::new();
+    let module = &ssf::analysis::eliminate_thunks(module);
+    let algebraic_types = algebraic_tags::collect_algebraic_types(module);
+
+    if algebraic_types.iter().any(types::is_pointer_tagged) {
+        if layout_descriptors::is_enabled(configuration) {
+            return Err(CompileError::InvalidConfiguration(
+                "pointer tags are not supported by layout descriptors".into(),
+            ));
+        } else if configuration.word_size != types::WORD_SIZE {
+            return Err(CompileError::InvalidConfiguration(
+                "pointer tags are available only on targets of 64-bit words".into(),
+            ));
+        }
+    }
+
+    let context = Context::new(
+        configuration.clone(),
+        if configuration.reference_counting || layout_descriptors::is_enabled(configuration) {
+            algebraic_tags::collect_algebraic_tags(&algebraic_types)?
+        } else {
+            vec![]
+        },
+    );
 
     for declaration in module.foreign_declarations() {
-        compile_foreign_declaration(&module_builder, declaration)?;
+        compile_foreign_declaration(&context, declaration)?;
     }
 
     for declaration in module.declarations() {
-        compile_declaration(&module_builder, declaration);
+        compile_declaration(&context, declaration);
     }
 
-    let global_variables = compile_global_variables(module);
+    let global_variables = compile_global_variables(&context, module)?;
+    let entry_function_names = module
+        .definitions()
+        .iter()
+        .map(|_| context.module_builder().generate_name())
+        .collect::<Vec<_>>();
+
+    for (definition, entry_function_name) in module.definitions().iter().zip(&entry_function_names)
+    {
+        if !definition.is_thunk() {
+            context.add_known_function(
+                definition.name(),
+                global_variables[definition.name()].clone(),
+                fmm::build::variable(
+                    entry_function_name,
+                    types::compile_entry_function_from_definition(definition),
+                ),
+            );
+        }
+    }
 
-    for definition in module.definitions() {
-        compile_definition(&module_builder, definition, &global_variables)?;
+    for (definition, entry_function_name) in module.definitions().iter().zip(&entry_function_names)
+    {
+        compile_definition(&context, definition, entry_function_name, &global_variables)?;
     }
 
     let types = module
@@ -55,52 +117,56 @@ pub fn compile(module: &ssf::ir::Module) -> Result<fmm::ir::Module, CompileError
 
     for definition in module.foreign_definitions() {
         compile_foreign_definition(
-            &module_builder,
+            &context,
             definition,
             types[definition.name()],
             &global_variables[definition.name()],
         )?;
     }
 
-    Ok(module_builder.into_module())
+    Ok(context.into_module())
 }
 
 fn compile_global_variables(
+    context: &Context,
     module: &ssf::ir::Module,
-) -> HashMap<String, fmm::build::TypedExpression> {
+) -> Result<HashMap<String, fmm::build::TypedExpression>, fmm::build::BuildError> {
     module
         .foreign_declarations()
         .iter()
         .map(|declaration| {
-            (
+            Ok((
                 declaration.name().into(),
-                fmm::build::variable(
+                reference_counts::compile_static_object_pointer(
+                    context,
                     declaration.name(),
-                    fmm::types::Pointer::new(types::compile_unsized_closure(declaration.type_())),
-                ),
-            )
+                    types::compile_unsized_closure(declaration.type_()),
+                )?,
+            ))
         })
         .chain(module.declarations().iter().map(|declaration| {
-            (
+            Ok((
                 declaration.name().into(),
-                fmm::build::variable(
+                reference_counts::compile_static_object_pointer(
+                    context,
                     declaration.name(),
-                    fmm::types::Pointer::new(types::compile_unsized_closure(declaration.type_())),
-                ),
-            )
+                    types::compile_unsized_closure(declaration.type_()),
+                )?,
+            ))
         }))
         .chain(module.definitions().iter().map(|definition| {
-            (
+            Ok((
                 definition.name().into(),
                 fmm::build::bit_cast(
                     fmm::types::Pointer::new(types::compile_unsized_closure(definition.type_())),
-                    fmm::build::variable(
+                    reference_counts::compile_static_object_pointer(
+                        context,
                         definition.name(),
-                        fmm::types::Pointer::new(types::compile_sized_closure(definition)),
-                    ),
+                        types::compile_sized_closure(definition),
+                    )?,
                 )
                 .into(),
-            )
+            ))
         }))
         .collect()
 }
@@ -110,7 +176,14 @@ mod tests {
     use super::*;
 
     fn compile_module(module: &ssf::ir::Module) {
-        let mut module = compile(module).unwrap();
+        compile_module_with_configuration(module, &Default::default());
+    }
+
+    fn compile_module_with_configuration(
+        module: &ssf::ir::Module,
+        configuration: &CompileConfiguration,
+    ) {
+        let mut module = compile(module, configuration).unwrap();
 
         compile_final_module(&module);
         fmm::analysis::cps::transform(&mut module, fmm::types::Record::new(vec![])).unwrap();
@@ -153,11 +226,189 @@ mod tests {
         assert!(output.status.success());
     }
 
+    fn create_reference_counting_configuration() -> CompileConfiguration {
+        CompileConfiguration {
+            thread_safety: ThreadSafety::SingleThreaded,
+            locked_thunk_strategy: LockedThunkStrategy::BlackHole("black_hole".into()),
+            reference_counting: true,
+            ..Default::default()
+        }
+    }
+
+    fn run_module(
+        module: &ssf::ir::Module,
+        configuration: &CompileConfiguration,
+        main_source: &str,
+    ) -> String {
+        run_module_with_malloc_configuration(module, configuration, None, main_source)
+    }
+
+    fn run_module_with_malloc_configuration(
+        module: &ssf::ir::Module,
+        configuration: &CompileConfiguration,
+        malloc_configuration: Option<fmm_c::MallocConfiguration>,
+        main_source: &str,
+    ) -> String {
+        let module = compile(module, configuration).unwrap();
+
+        fmm::analysis::type_check::check(&module).unwrap();
+
+        let directory = tempfile::tempdir().unwrap();
+        let module_path = directory.path().join("module.c");
+        let main_path = directory.path().join("main.c");
+        let executable_path = directory.path().join("main");
+
+        std::fs::write(
+            &module_path,
+            fmm_c::compile(&module, malloc_configuration).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(&main_path, main_source).unwrap();
+
+        let output = std::process::Command::new("clang")
+            .arg("-o")
+            .arg(&executable_path)
+            .arg(&module_path)
+            .arg(&main_path)
+            .output()
+            .unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let output = std::process::Command::new(&executable_path)
+            .output()
+            .unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+        assert!(output.status.success());
+
+        String::from_utf8_lossy(&output.stdout).into()
+    }
+
     #[test]
     fn compile_empty_module() {
         compile_module(&ssf::ir::Module::new(vec![], vec![], vec![], vec![]));
     }
 
+    mod algebraic_layouts {
+        use super::*;
+
+        fn create_algebraic(
+            constructors: Vec<ssf::types::Constructor>,
+            representation: ssf::types::Representation,
+        ) -> ssf::types::Algebraic {
+            ssf::types::Algebraic::with_representation(
+                constructors
+                    .into_iter()
+                    .enumerate()
+                    .map(|(tag, constructor)| (tag as u64, constructor))
+                    .collect(),
+                representation,
+            )
+        }
+
+        #[test]
+        fn get_untagged_layout() {
+            assert_eq!(
+                get_algebraic_layout(&ssf::types::Algebraic::new(vec![
+                    ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()])
+                ])),
+                AlgebraicLayout::Untagged
+            );
+        }
+
+        #[test]
+        fn get_tagged_layout() {
+            assert_eq!(
+                get_algebraic_layout(&ssf::types::Algebraic::new(vec![
+                    ssf::types::Constructor::unboxed(vec![]),
+                    ssf::types::Constructor::unboxed(vec![]),
+                ])),
+                AlgebraicLayout::Tagged { tag_size: 1 }
+            );
+        }
+
+        #[test]
+        fn get_ffi_compatible_layout() {
+            assert_eq!(
+                get_algebraic_layout(&create_algebraic(
+                    vec![
+                        ssf::types::Constructor::unboxed(vec![]),
+                        ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                    ],
+                    ssf::types::Representation::FfiCompatible,
+                )),
+                AlgebraicLayout::Tagged { tag_size: 8 }
+            );
+        }
+
+        #[test]
+        fn get_null_pointer_niche_layout() {
+            assert_eq!(
+                get_algebraic_layout(&ssf::types::Algebraic::new(vec![
+                    ssf::types::Constructor::unboxed(vec![]),
+                    ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                ])),
+                AlgebraicLayout::NullPointerNiche {
+                    enum_tag: 0,
+                    boxed_tag: 1
+                }
+            );
+        }
+
+        #[test]
+        fn get_pointer_tagged_layout() {
+            assert_eq!(
+                get_algebraic_layout(&create_algebraic(
+                    vec![
+                        ssf::types::Constructor::unboxed(vec![]),
+                        ssf::types::Constructor::boxed(vec![ssf::types::Type::Index(0)]),
+                        ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                    ],
+                    ssf::types::Representation::PointerTagged,
+                )),
+                AlgebraicLayout::PointerTagged { tag_bits: 3 }
+            );
+        }
+
+        #[test]
+        fn fall_back_from_pointer_tagged_layout_with_unaligned_payload() {
+            assert_eq!(
+                get_algebraic_layout(&create_algebraic(
+                    vec![
+                        ssf::types::Constructor::boxed(
+                            vec![ssf::types::Primitive::Integer8.into()]
+                        ),
+                        ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                    ],
+                    ssf::types::Representation::PointerTagged,
+                )),
+                AlgebraicLayout::Tagged { tag_size: 1 }
+            );
+        }
+
+        #[test]
+        fn fall_back_from_pointer_tagged_layout_with_unboxed_payload() {
+            assert_eq!(
+                get_algebraic_layout(&create_algebraic(
+                    vec![
+                        ssf::types::Constructor::unboxed(vec![
+                            ssf::types::Primitive::Float64.into()
+                        ]),
+                        ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                    ],
+                    ssf::types::Representation::PointerTagged,
+                )),
+                AlgebraicLayout::Tagged { tag_size: 1 }
+            );
+        }
+    }
+
     mod foreign_declarations {
         use super::*;
 
@@ -376,144 +627,2665 @@ mod tests {
                 ],
             ));
         }
-    }
-
-    mod expressions {
-        use super::*;
 
         #[test]
-        fn compile_let() {
-            compile_module(&ssf::ir::Module::new(
-                vec![],
-                vec![],
-                vec![],
-                vec![ssf::ir::Definition::new(
-                    "f",
-                    vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
-                    ssf::ir::Let::new(
-                        "y",
-                        ssf::types::Primitive::Float64,
-                        ssf::ir::Variable::new("x"),
-                        ssf::ir::Variable::new("y"),
-                    ),
-                    ssf::types::Primitive::Float64,
-                )],
-            ));
+        fn compile_thunk_in_single_thread() {
+            compile_module_with_configuration(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![
+                        ssf::ir::Definition::thunk(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Variable::new("x"),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::Definition::new(
+                            "g",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::FunctionApplication::new(
+                                ssf::ir::Variable::new("f"),
+                                ssf::ir::Variable::new("x"),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                    ],
+                ),
+                &CompileConfiguration {
+                    thread_safety: ThreadSafety::SingleThreaded,
+                    locked_thunk_strategy: LockedThunkStrategy::BlackHole("black_hole".into()),
+                    ..Default::default()
+                },
+            );
         }
 
         #[test]
-        fn compile_let_recursive() {
-            compile_module(&ssf::ir::Module::new(
-                vec![],
-                vec![],
-                vec![],
-                vec![ssf::ir::Definition::new(
-                    "f",
-                    vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
-                    ssf::ir::LetRecursive::new(
-                        vec![ssf::ir::Definition::new(
+        fn compile_thunk_with_wait_function() {
+            compile_module_with_configuration(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![
+                        ssf::ir::Definition::thunk(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Variable::new("x"),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::Definition::new(
                             "g",
-                            vec![ssf::ir::Argument::new("y", ssf::types::Primitive::Float64)],
-                            ssf::ir::ArithmeticOperation::new(
-                                ssf::ir::ArithmeticOperator::Add,
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::FunctionApplication::new(
+                                ssf::ir::Variable::new("f"),
                                 ssf::ir::Variable::new("x"),
-                                ssf::ir::Variable::new("y"),
                             ),
                             ssf::types::Primitive::Float64,
-                        )],
-                        ssf::ir::FunctionApplication::new(
-                            ssf::ir::Variable::new("g"),
-                            ssf::ir::Primitive::Float64(42.0),
                         ),
-                    ),
-                    ssf::types::Primitive::Float64,
-                )],
-            ));
+                    ],
+                ),
+                &CompileConfiguration {
+                    locked_thunk_strategy: LockedThunkStrategy::Wait("wait_thunk".into()),
+                    ..Default::default()
+                },
+            );
         }
 
         #[test]
-        fn compile_let_recursive_with_curried_function() {
-            compile_module(&ssf::ir::Module::new(
-                vec![],
-                vec![],
-                vec![],
-                vec![ssf::ir::Definition::new(
-                    "f",
-                    vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
-                    ssf::ir::LetRecursive::new(
-                        vec![ssf::ir::Definition::new(
+        fn compile_thunk_with_black_hole_function() {
+            compile_module_with_configuration(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![
+                        ssf::ir::Definition::thunk(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Variable::new("x"),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::Definition::new(
                             "g",
-                            vec![ssf::ir::Argument::new("y", ssf::types::Primitive::Float64)],
-                            ssf::ir::LetRecursive::new(
-                                vec![ssf::ir::Definition::new(
-                                    "h",
-                                    vec![ssf::ir::Argument::new(
-                                        "z",
-                                        ssf::types::Primitive::Float64,
-                                    )],
-                                    ssf::ir::ArithmeticOperation::new(
-                                        ssf::ir::ArithmeticOperator::Add,
-                                        ssf::ir::ArithmeticOperation::new(
-                                            ssf::ir::ArithmeticOperator::Add,
-                                            ssf::ir::Variable::new("x"),
-                                            ssf::ir::Variable::new("y"),
-                                        ),
-                                        ssf::ir::Variable::new("z"),
-                                    ),
-                                    ssf::types::Primitive::Float64,
-                                )],
-                                ssf::ir::Variable::new("h"),
-                            ),
-                            ssf::types::Function::new(
-                                ssf::types::Primitive::Float64,
-                                ssf::types::Primitive::Float64,
-                            ),
-                        )],
-                        ssf::ir::FunctionApplication::new(
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
                             ssf::ir::FunctionApplication::new(
-                                ssf::ir::Variable::new("g"),
-                                ssf::ir::Primitive::Float64(42.0),
+                                ssf::ir::Variable::new("f"),
+                                ssf::ir::Variable::new("x"),
                             ),
-                            ssf::ir::Primitive::Float64(42.0),
+                            ssf::types::Primitive::Float64,
                         ),
-                    ),
-                    ssf::types::Primitive::Float64,
-                )],
-            ));
+                    ],
+                ),
+                &CompileConfiguration {
+                    thread_safety: ThreadSafety::SingleThreaded,
+                    locked_thunk_strategy: LockedThunkStrategy::BlackHole("black_hole".into()),
+                    ..Default::default()
+                },
+            );
         }
 
-        mod algebraic_cases {
-            use super::*;
-
-            #[test]
-            fn compile_with_singleton_enum() {
-                let algebraic_type =
-                    ssf::types::Algebraic::new(vec![ssf::types::Constructor::unboxed(vec![])]);
-
-                compile_module(&ssf::ir::Module::new(
-                    vec![],
+        fn run_thunk(configuration: &CompileConfiguration) -> String {
+            run_module(
+                &ssf::ir::Module::new(
                     vec![],
+                    vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
                     vec![],
-                    vec![ssf::ir::Definition::new(
+                    vec![ssf::ir::Definition::thunk(
                         "f",
-                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
-                        ssf::ir::AlgebraicCase::new(
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::ArithmeticOperation::new(
+                            ssf::ir::ArithmeticOperator::Add,
                             ssf::ir::Variable::new("x"),
-                            vec![ssf::ir::AlgebraicAlternative::new(
-                                ssf::ir::Constructor::new(algebraic_type, 0),
-                                vec![],
-                                ssf::ir::Primitive::Float64(42.0),
-                            )],
-                            None,
+                            ssf::ir::Primitive::Float64(1.0),
                         ),
                         ssf::types::Primitive::Float64,
                     )],
-                ));
-            }
+                ),
+                configuration,
+                r#"
+                    #include <stdio.h>
+                    #include <stdlib.h>
 
-            #[test]
-            fn compile_with_1_element_singleton() {
-                let algebraic_type = ssf::types::Algebraic::new(vec![
+                    double f_foreign(double);
+
+                    void black_hole(void *thunk) {
+                      exit(1);
+                    }
+
+                    int main() {
+                      printf("%g\n", f_foreign(41));
+                      printf("%g\n", f_foreign(0));
+                      return 0;
+                    }
+                "#,
+            )
+        }
+
+        #[test]
+        fn run_thunk_in_multiple_threads() {
+            assert_eq!(run_thunk(&Default::default()), "42\n42\n");
+        }
+
+        fn run_function_application(configuration: &CompileConfiguration) -> String {
+            let function_type = ssf::types::Function::new(
+                ssf::types::Primitive::Float64,
+                ssf::types::Primitive::Float64,
+            );
+
+            run_module(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![ssf::ir::ForeignDefinition::new("h", "h_foreign")],
+                    vec![],
+                    vec![
+                        ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::ArithmeticOperation::new(
+                                ssf::ir::ArithmeticOperator::Add,
+                                ssf::ir::Variable::new("x"),
+                                ssf::ir::Primitive::Float64(1.0),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::Definition::new(
+                            "g",
+                            vec![
+                                ssf::ir::Argument::new("f", function_type),
+                                ssf::ir::Argument::new("x", ssf::types::Primitive::Float64),
+                            ],
+                            ssf::ir::FunctionApplication::new(
+                                ssf::ir::Variable::new("f"),
+                                ssf::ir::Variable::new("x"),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::Definition::new(
+                            "h",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Let::new(
+                                "y",
+                                ssf::types::Lazy::new(ssf::types::Primitive::Float64),
+                                ssf::ir::Delay::new(
+                                    ssf::ir::FunctionApplication::new(
+                                        ssf::ir::FunctionApplication::new(
+                                            ssf::ir::Variable::new("g"),
+                                            ssf::ir::Variable::new("f"),
+                                        ),
+                                        ssf::ir::Variable::new("x"),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                                ssf::ir::ArithmeticOperation::new(
+                                    ssf::ir::ArithmeticOperator::Add,
+                                    ssf::ir::Force::new(ssf::ir::Variable::new("y")),
+                                    ssf::ir::Force::new(ssf::ir::Variable::new("y")),
+                                ),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                    ],
+                ),
+                configuration,
+                r#"
+                    #include <stdio.h>
+                    #include <stdlib.h>
+
+                    double h_foreign(double);
+
+                    void black_hole(void *thunk) {
+                      exit(1);
+                    }
+
+                    int main() {
+                      printf("%g\n", h_foreign(41));
+                      return 0;
+                    }
+                "#,
+            )
+        }
+
+        #[test]
+        fn run_function_application_in_multiple_threads() {
+            assert_eq!(run_function_application(&Default::default()), "84\n");
+        }
+
+        #[test]
+        fn run_function_application_in_single_thread() {
+            assert_eq!(
+                run_function_application(&CompileConfiguration {
+                    thread_safety: ThreadSafety::SingleThreaded,
+                    locked_thunk_strategy: LockedThunkStrategy::BlackHole("black_hole".into()),
+                    ..Default::default()
+                }),
+                "84\n"
+            );
+        }
+
+        #[test]
+        fn run_thunk_in_single_thread() {
+            assert_eq!(
+                run_thunk(&CompileConfiguration {
+                    thread_safety: ThreadSafety::SingleThreaded,
+                    locked_thunk_strategy: LockedThunkStrategy::BlackHole("black_hole".into()),
+                    ..Default::default()
+                }),
+                "42\n42\n"
+            );
+        }
+
+        #[test]
+        fn fail_to_compile_spin_lock_in_single_thread() {
+            assert!(matches!(
+                compile(
+                    &ssf::ir::Module::new(vec![], vec![], vec![], vec![]),
+                    &CompileConfiguration {
+                        thread_safety: ThreadSafety::SingleThreaded,
+                        ..Default::default()
+                    },
+                ),
+                Err(CompileError::InvalidConfiguration(_))
+            ));
+        }
+
+        #[test]
+        fn fail_to_compile_stack_allocation_with_shadow_stack() {
+            assert!(matches!(
+                compile(
+                    &ssf::ir::Module::new(vec![], vec![], vec![], vec![]),
+                    &CompileConfiguration {
+                        allocation_strategy: AllocationStrategy::Described("allocate".into()),
+                        shadow_stack: Some(ShadowStackFunctions {
+                            push: "push_root".into(),
+                            save: "save_roots".into(),
+                            restore: "restore_roots".into(),
+                        }),
+                        stack_allocation: true,
+                        ..Default::default()
+                    },
+                ),
+                Err(CompileError::InvalidConfiguration(_))
+            ));
+        }
+
+        #[test]
+        fn fail_to_compile_stack_allocation_with_reference_counting() {
+            assert!(matches!(
+                compile(
+                    &ssf::ir::Module::new(vec![], vec![], vec![], vec![]),
+                    &CompileConfiguration {
+                        stack_allocation: true,
+                        ..create_reference_counting_configuration()
+                    },
+                ),
+                Err(CompileError::InvalidConfiguration(_))
+            ));
+        }
+
+        #[test]
+        fn fail_to_compile_black_hole_in_multiple_threads() {
+            assert!(matches!(
+                compile(
+                    &ssf::ir::Module::new(vec![], vec![], vec![], vec![]),
+                    &CompileConfiguration {
+                        locked_thunk_strategy: LockedThunkStrategy::BlackHole("black_hole".into()),
+                        ..Default::default()
+                    },
+                ),
+                Err(CompileError::InvalidConfiguration(_))
+            ));
+        }
+
+        #[test]
+        fn run_thunk_with_black_hole() {
+            assert_eq!(
+                run_module(
+                    &ssf::ir::Module::new(
+                        vec![],
+                        vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                        vec![],
+                        vec![ssf::ir::Definition::thunk(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::FunctionApplication::new(
+                                ssf::ir::Variable::new("f"),
+                                ssf::ir::Variable::new("x"),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        )],
+                    ),
+                    &CompileConfiguration {
+                        thread_safety: ThreadSafety::SingleThreaded,
+                        locked_thunk_strategy: LockedThunkStrategy::BlackHole("black_hole".into()),
+                        ..Default::default()
+                    },
+                    r#"
+                        #include <stdio.h>
+                        #include <stdlib.h>
+
+                        double f_foreign(double);
+
+                        void black_hole(void *thunk) {
+                          printf("black hole\n");
+                          exit(0);
+                        }
+
+                        int main() {
+                          f_foreign(42);
+                          return 1;
+                        }
+                    "#,
+                ),
+                "black hole\n"
+            );
+        }
+
+        fn run_thunk_in_threads(configuration: &CompileConfiguration) -> String {
+            run_module(
+                &ssf::ir::Module::new(
+                    vec![ssf::ir::ForeignDeclaration::new(
+                        "g",
+                        "g_foreign",
+                        ssf::types::Function::new(
+                            ssf::types::Primitive::Float64,
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::CallingConvention::Target,
+                    )],
+                    vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                    vec![],
+                    vec![ssf::ir::Definition::thunk(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::FunctionApplication::new(
+                            ssf::ir::Variable::new("g"),
+                            ssf::ir::Variable::new("x"),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ),
+                configuration,
+                r#"
+                    #include <pthread.h>
+                    #include <stdio.h>
+                    #include <unistd.h>
+
+                    double f_foreign(double);
+
+                    double g_foreign(double x) {
+                      usleep(100000);
+                      return x;
+                    }
+
+                    void wait_thunk(void *thunk) {
+                      usleep(1000);
+                    }
+
+                    void *run(void *result) {
+                      *(double *)result = f_foreign(42);
+                      return NULL;
+                    }
+
+                    int main() {
+                      pthread_t threads[2];
+                      double results[2];
+
+                      for (int i = 0; i < 2; i++) {
+                        pthread_create(&threads[i], NULL, run, &results[i]);
+                      }
+
+                      for (int i = 0; i < 2; i++) {
+                        pthread_join(threads[i], NULL);
+                        printf("%g\n", results[i]);
+                      }
+
+                      return 0;
+                    }
+                "#,
+            )
+        }
+
+        #[test]
+        fn run_thunk_in_threads_with_spin_lock() {
+            assert_eq!(run_thunk_in_threads(&Default::default()), "42\n42\n");
+        }
+
+        #[test]
+        fn run_thunk_in_threads_with_wait_function() {
+            assert_eq!(
+                run_thunk_in_threads(&CompileConfiguration {
+                    locked_thunk_strategy: LockedThunkStrategy::Wait("wait_thunk".into()),
+                    ..Default::default()
+                }),
+                "42\n42\n"
+            );
+        }
+    }
+
+    mod allocation {
+        use super::*;
+
+        fn compile_with_allocation_strategy(allocation_strategy: AllocationStrategy) {
+            let atomic_type =
+                ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                ])]);
+            let pointer_type =
+                ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Pointer.into(),
+                ])]);
+
+            compile_module_with_configuration(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![
+                        ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::ConstructorApplication::new(
+                                ssf::ir::Constructor::new(atomic_type.clone(), 0),
+                                vec![ssf::ir::Variable::new("x").into()],
+                            ),
+                            atomic_type,
+                        ),
+                        ssf::ir::Definition::new(
+                            "g",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Pointer)],
+                            ssf::ir::ConstructorApplication::new(
+                                ssf::ir::Constructor::new(pointer_type.clone(), 0),
+                                vec![ssf::ir::Variable::new("x").into()],
+                            ),
+                            pointer_type,
+                        ),
+                        ssf::ir::Definition::new(
+                            "h",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::LetRecursive::new(
+                                vec![ssf::ir::Definition::new(
+                                    "y",
+                                    vec![ssf::ir::Argument::new(
+                                        "z",
+                                        ssf::types::Primitive::Float64,
+                                    )],
+                                    ssf::ir::Variable::new("x"),
+                                    ssf::types::Primitive::Float64,
+                                )],
+                                ssf::ir::Variable::new("y"),
+                            ),
+                            ssf::types::Function::new(
+                                ssf::types::Primitive::Float64,
+                                ssf::types::Primitive::Float64,
+                            ),
+                        ),
+                    ],
+                ),
+                &CompileConfiguration {
+                    allocation_strategy,
+                    ..Default::default()
+                },
+            );
+        }
+
+        #[test]
+        fn compile_with_allocation_functions() {
+            compile_with_allocation_strategy(AllocationStrategy::Functions(AllocationFunctions {
+                closure: "allocate_closure".into(),
+                constructor: "allocate_constructor".into(),
+                atomic: "allocate_atomic".into(),
+            }));
+        }
+
+        #[test]
+        fn compile_with_described_allocation_function() {
+            compile_with_allocation_strategy(AllocationStrategy::Described(
+                "allocate_described".into(),
+            ));
+        }
+
+        #[test]
+        fn compile_with_tagged_allocation_function() {
+            compile_with_allocation_strategy(AllocationStrategy::Tagged("allocate_tagged".into()));
+        }
+
+        #[test]
+        fn compile_with_stack_allocation() {
+            let algebraic_type =
+                ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                ])]);
+            let function_type = ssf::types::Function::new(
+                ssf::types::Primitive::Float64,
+                ssf::types::Primitive::Float64,
+            );
+
+            compile_module_with_configuration(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![
+                        ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Let::new(
+                                "y",
+                                algebraic_type.clone(),
+                                ssf::ir::ConstructorApplication::new(
+                                    ssf::ir::Constructor::new(algebraic_type.clone(), 0),
+                                    vec![ssf::ir::Variable::new("x").into()],
+                                ),
+                                ssf::ir::AlgebraicCase::new(
+                                    ssf::ir::Variable::new("y"),
+                                    vec![ssf::ir::AlgebraicAlternative::new(
+                                        ssf::ir::Constructor::new(algebraic_type, 0),
+                                        vec!["z".into()],
+                                        ssf::ir::Variable::new("z"),
+                                    )],
+                                    None,
+                                ),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::Definition::new(
+                            "g",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::LetRecursive::new(
+                                vec![ssf::ir::Definition::new(
+                                    "h",
+                                    vec![
+                                        ssf::ir::Argument::new("y", ssf::types::Primitive::Float64),
+                                        ssf::ir::Argument::new("z", ssf::types::Primitive::Float64),
+                                    ],
+                                    ssf::ir::Variable::new("x"),
+                                    ssf::types::Primitive::Float64,
+                                )],
+                                ssf::ir::Let::new(
+                                    "y",
+                                    function_type,
+                                    ssf::ir::FunctionApplication::new(
+                                        ssf::ir::Variable::new("h"),
+                                        ssf::ir::Variable::new("x"),
+                                    ),
+                                    ssf::ir::FunctionApplication::new(
+                                        ssf::ir::Variable::new("y"),
+                                        ssf::ir::Variable::new("x"),
+                                    ),
+                                ),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                    ],
+                ),
+                &CompileConfiguration {
+                    stack_allocation: true,
+                    ..Default::default()
+                },
+            );
+        }
+
+        #[test]
+        fn run_with_stack_allocation() {
+            let algebraic_type =
+                ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                ])]);
+            let arithmetic_operation = |operator, lhs: &str, rhs: &str| {
+                ssf::ir::ArithmeticOperation::new(
+                    operator,
+                    ssf::ir::Variable::new(lhs),
+                    ssf::ir::Variable::new(rhs),
+                )
+            };
+
+            assert_eq!(
+                run_module_with_malloc_configuration(
+                    &ssf::ir::Module::new(
+                        vec![],
+                        vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                        vec![],
+                        vec![
+                            ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::Let::new(
+                                    "y",
+                                    algebraic_type.clone(),
+                                    ssf::ir::ConstructorApplication::new(
+                                        ssf::ir::Constructor::new(algebraic_type.clone(), 0),
+                                        vec![ssf::ir::Variable::new("x").into()],
+                                    ),
+                                    ssf::ir::Let::new(
+                                        "z",
+                                        ssf::types::Function::new(
+                                            ssf::types::Primitive::Float64,
+                                            ssf::types::Primitive::Float64,
+                                        ),
+                                        ssf::ir::FunctionApplication::new(
+                                            ssf::ir::Variable::new("g"),
+                                            ssf::ir::Variable::new("x"),
+                                        ),
+                                        // Calls in the middle use the same stack
+                                        // region as the closure and payload.
+                                        ssf::ir::Let::new(
+                                            "w",
+                                            ssf::types::Primitive::Float64,
+                                            ssf::ir::FunctionApplication::new(
+                                                ssf::ir::Variable::new("h"),
+                                                ssf::ir::Variable::new("x"),
+                                            ),
+                                            ssf::ir::FunctionApplication::new(
+                                                ssf::ir::Variable::new("z"),
+                                                ssf::ir::AlgebraicCase::new(
+                                                    ssf::ir::Variable::new("y"),
+                                                    vec![ssf::ir::AlgebraicAlternative::new(
+                                                        ssf::ir::Constructor::new(
+                                                            algebraic_type,
+                                                            0,
+                                                        ),
+                                                        vec!["v".into()],
+                                                        arithmetic_operation(
+                                                            ssf::ir::ArithmeticOperator::Add,
+                                                            "v",
+                                                            "w",
+                                                        ),
+                                                    )],
+                                                    None,
+                                                ),
+                                            ),
+                                        ),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            ),
+                            ssf::ir::Definition::new(
+                                "g",
+                                vec![
+                                    ssf::ir::Argument::new("x", ssf::types::Primitive::Float64),
+                                    ssf::ir::Argument::new("y", ssf::types::Primitive::Float64),
+                                ],
+                                arithmetic_operation(ssf::ir::ArithmeticOperator::Add, "x", "y"),
+                                ssf::types::Primitive::Float64,
+                            ),
+                            ssf::ir::Definition::new(
+                                "h",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64),],
+                                arithmetic_operation(
+                                    ssf::ir::ArithmeticOperator::Multiply,
+                                    "x",
+                                    "x",
+                                ),
+                                ssf::types::Primitive::Float64,
+                            ),
+                        ],
+                    ),
+                    &CompileConfiguration {
+                        stack_allocation: true,
+                        ..Default::default()
+                    },
+                    Some(fmm_c::MallocConfiguration {
+                        malloc_function_name: "allocate_heap".into(),
+                        realloc_function_name: "reallocate_heap".into(),
+                        free_function_name: "free_heap".into(),
+                    }),
+                    r#"
+                        #include <stdint.h>
+                        #include <stdio.h>
+                        #include <stdlib.h>
+
+                        static intptr_t allocation_count = 0;
+
+                        void *allocate_heap(size_t size) {
+                          allocation_count++;
+                          return malloc(size);
+                        }
+
+                        void *reallocate_heap(void *pointer, size_t size) {
+                          return realloc(pointer, size);
+                        }
+
+                        void free_heap(void *pointer) {
+                          free(pointer);
+                        }
+
+                        double f_foreign(double);
+
+                        int main() {
+                          printf("%g\n", f_foreign(2));
+                          printf("%ld\n", (long)allocation_count);
+                          return 0;
+                        }
+                    "#,
+                ),
+                "8\n0\n"
+            );
+        }
+    }
+
+    mod reference_counts {
+        use super::*;
+
+        #[test]
+        fn compile_with_reference_counting() {
+            let algebraic_type = ssf::types::Algebraic::new(vec![
+                ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                ssf::types::Constructor::unboxed(vec![]),
+            ]);
+
+            compile_module_with_configuration(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![
+                        ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Let::new(
+                                "y",
+                                algebraic_type.clone(),
+                                ssf::ir::ConstructorApplication::new(
+                                    ssf::ir::Constructor::new(algebraic_type.clone(), 0),
+                                    vec![ssf::ir::Variable::new("x").into()],
+                                ),
+                                ssf::ir::AlgebraicCase::new(
+                                    ssf::ir::Variable::new("y"),
+                                    vec![ssf::ir::AlgebraicAlternative::new(
+                                        ssf::ir::Constructor::new(algebraic_type, 0),
+                                        vec!["z".into()],
+                                        ssf::ir::Variable::new("z"),
+                                    )],
+                                    Some(ssf::ir::Primitive::Float64(42.0).into()),
+                                ),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::ir::Definition::new(
+                            "g",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::LetRecursive::new(
+                                vec![
+                                    ssf::ir::Definition::new(
+                                        "y",
+                                        vec![ssf::ir::Argument::new(
+                                            "z",
+                                            ssf::types::Primitive::Float64,
+                                        )],
+                                        ssf::ir::Variable::new("x"),
+                                        ssf::types::Primitive::Float64,
+                                    ),
+                                    ssf::ir::Definition::thunk(
+                                        "z",
+                                        vec![ssf::ir::Argument::new(
+                                            "w",
+                                            ssf::types::Primitive::Float64,
+                                        )],
+                                        ssf::ir::FunctionApplication::new(
+                                            ssf::ir::Variable::new("y"),
+                                            ssf::ir::Variable::new("w"),
+                                        ),
+                                        ssf::types::Primitive::Float64,
+                                    ),
+                                ],
+                                ssf::ir::FunctionApplication::new(
+                                    ssf::ir::Variable::new("z"),
+                                    ssf::ir::Variable::new("x"),
+                                ),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        ),
+                    ],
+                ),
+                &create_reference_counting_configuration(),
+            );
+        }
+
+        #[test]
+        fn compile_with_reused_constructor() {
+            let list_type = ssf::types::Algebraic::new(vec![
+                ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                    ssf::types::Type::Index(0),
+                ]),
+                ssf::types::Constructor::unboxed(vec![]),
+            ]);
+
+            compile_module_with_configuration(
+                &ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", list_type.clone())],
+                        ssf::ir::AlgebraicCase::new(
+                            ssf::ir::Variable::new("x"),
+                            vec![ssf::ir::AlgebraicAlternative::new(
+                                ssf::ir::Constructor::new(list_type.clone(), 0),
+                                vec!["y".into(), "z".into()],
+                                ssf::ir::Let::new(
+                                    "w",
+                                    ssf::types::Primitive::Float64,
+                                    ssf::ir::ArithmeticOperation::new(
+                                        ssf::ir::ArithmeticOperator::Add,
+                                        ssf::ir::Variable::new("y"),
+                                        ssf::ir::Primitive::Float64(1.0),
+                                    ),
+                                    ssf::ir::ConstructorApplication::new(
+                                        ssf::ir::Constructor::new(list_type.clone(), 0),
+                                        vec![
+                                            ssf::ir::Variable::new("w").into(),
+                                            ssf::ir::Variable::new("z").into(),
+                                        ],
+                                    ),
+                                ),
+                            )],
+                            Some(ssf::ir::Variable::new("x").into()),
+                        ),
+                        list_type,
+                    )],
+                ),
+                &create_reference_counting_configuration(),
+            );
+        }
+
+        #[test]
+        fn fail_to_compile_reference_counting_in_multiple_threads() {
+            assert!(matches!(
+                compile(
+                    &ssf::ir::Module::new(vec![], vec![], vec![], vec![]),
+                    &CompileConfiguration {
+                        reference_counting: true,
+                        ..Default::default()
+                    },
+                ),
+                Err(CompileError::InvalidConfiguration(_))
+            ));
+        }
+
+        fn run_with_counted_free(module: &ssf::ir::Module) -> String {
+            run_module_with_malloc_configuration(
+                module,
+                &create_reference_counting_configuration(),
+                Some(fmm_c::MallocConfiguration {
+                    malloc_function_name: "allocate_heap".into(),
+                    realloc_function_name: "reallocate_heap".into(),
+                    free_function_name: "free_heap".into(),
+                }),
+                r#"
+                    #include <stdint.h>
+                    #include <stdio.h>
+                    #include <stdlib.h>
+
+                    static intptr_t allocation_count = 0;
+                    static intptr_t free_count = 0;
+
+                    void *allocate_heap(size_t size) {
+                      allocation_count++;
+                      return malloc(size);
+                    }
+
+                    void *reallocate_heap(void *pointer, size_t size) {
+                      return realloc(pointer, size);
+                    }
+
+                    void free_heap(void *pointer) {
+                      free_count++;
+                      free(pointer);
+                    }
+
+                    double f_foreign(double);
+
+                    int main() {
+                      printf("%g\n", f_foreign(42));
+                      printf("%ld\n", (long)allocation_count);
+                      printf("%ld\n", (long)free_count);
+                      return 0;
+                    }
+                "#,
+            )
+        }
+
+        #[test]
+        fn run_with_tagged_type() {
+            let boxed_type =
+                ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                ])]);
+            let algebraic_type = ssf::types::Algebraic::new(vec![
+                ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                ssf::types::Constructor::boxed(vec![
+                    boxed_type.clone().into(),
+                    boxed_type.clone().into(),
+                ]),
+            ]);
+            let boxed = ssf::ir::Constructor::new(boxed_type, 0);
+
+            assert_eq!(
+                run_with_counted_free(&ssf::ir::Module::new(
+                    vec![],
+                    vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::Let::new(
+                            "y",
+                            algebraic_type.clone(),
+                            ssf::ir::ConstructorApplication::new(
+                                ssf::ir::Constructor::new(algebraic_type.clone(), 1),
+                                vec![
+                                    ssf::ir::ConstructorApplication::new(
+                                        boxed.clone(),
+                                        vec![ssf::ir::Variable::new("x").into()],
+                                    )
+                                    .into(),
+                                    ssf::ir::ConstructorApplication::new(
+                                        boxed.clone(),
+                                        vec![ssf::ir::ArithmeticOperation::new(
+                                            ssf::ir::ArithmeticOperator::Add,
+                                            ssf::ir::Variable::new("x"),
+                                            ssf::ir::Primitive::Float64(1.0),
+                                        )
+                                        .into()],
+                                    )
+                                    .into(),
+                                ],
+                            ),
+                            ssf::ir::AlgebraicCase::new(
+                                ssf::ir::Variable::new("y"),
+                                vec![
+                                    ssf::ir::AlgebraicAlternative::new(
+                                        ssf::ir::Constructor::new(algebraic_type.clone(), 0),
+                                        vec!["z".into()],
+                                        ssf::ir::Variable::new("z"),
+                                    ),
+                                    ssf::ir::AlgebraicAlternative::new(
+                                        ssf::ir::Constructor::new(algebraic_type, 1),
+                                        vec!["z".into(), "w".into()],
+                                        ssf::ir::ArithmeticOperation::new(
+                                            ssf::ir::ArithmeticOperator::Add,
+                                            ssf::ir::ConstructorField::new(
+                                                boxed.clone(),
+                                                0,
+                                                ssf::ir::Variable::new("z"),
+                                            ),
+                                            ssf::ir::ConstructorField::new(
+                                                boxed,
+                                                0,
+                                                ssf::ir::Variable::new("w"),
+                                            ),
+                                        ),
+                                    ),
+                                ],
+                                None,
+                            ),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                )),
+                "85\n3\n3\n"
+            );
+        }
+
+        fn create_list_type() -> ssf::types::Algebraic {
+            ssf::types::Algebraic::new(vec![
+                ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                    ssf::types::Type::Index(0),
+                ]),
+                ssf::types::Constructor::unboxed(vec![]),
+            ])
+        }
+
+        // Payloads of unique cells matched by case expressions are reused for
+        // new cells.
+        fn run_with_reused_constructor(
+            argument: impl Into<ssf::ir::Expression>,
+            compile_body: impl Fn(ssf::ir::Expression) -> ssf::ir::Expression,
+        ) -> String {
+            let list_type = create_list_type();
+            let cons = ssf::ir::Constructor::new(list_type.clone(), 0);
+
+            run_with_counted_free(&ssf::ir::Module::new(
+                vec![],
+                vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                vec![],
+                vec![
+                    ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        compile_body(
+                            ssf::ir::FunctionApplication::new(
+                                ssf::ir::Variable::new("head"),
+                                ssf::ir::AlgebraicCase::new(
+                                    argument,
+                                    vec![ssf::ir::AlgebraicAlternative::new(
+                                        cons.clone(),
+                                        vec!["y".into(), "z".into()],
+                                        ssf::ir::ConstructorApplication::new(
+                                            cons.clone(),
+                                            vec![
+                                                ssf::ir::ArithmeticOperation::new(
+                                                    ssf::ir::ArithmeticOperator::Add,
+                                                    ssf::ir::Variable::new("y"),
+                                                    ssf::ir::Primitive::Float64(1.0),
+                                                )
+                                                .into(),
+                                                ssf::ir::Variable::new("z").into(),
+                                            ],
+                                        ),
+                                    )],
+                                    None,
+                                ),
+                            )
+                            .into(),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    ),
+                    ssf::ir::Definition::new(
+                        "head",
+                        vec![ssf::ir::Argument::new("x", list_type.clone())],
+                        ssf::ir::AlgebraicCase::new(
+                            ssf::ir::Variable::new("x"),
+                            vec![ssf::ir::AlgebraicAlternative::new(
+                                cons,
+                                vec!["y".into(), "z".into()],
+                                ssf::ir::Variable::new("y"),
+                            )],
+                            Some(ssf::ir::Primitive::Float64(0.0).into()),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    ),
+                    ssf::ir::Definition::new(
+                        "id",
+                        vec![ssf::ir::Argument::new("x", list_type.clone())],
+                        ssf::ir::Variable::new("x"),
+                        list_type,
+                    ),
+                ],
+            ))
+        }
+
+        fn create_list(element: impl Into<ssf::ir::Expression>) -> ssf::ir::Expression {
+            let list_type = create_list_type();
+
+            ssf::ir::ConstructorApplication::new(
+                ssf::ir::Constructor::new(list_type.clone(), 0),
+                vec![
+                    element.into(),
+                    ssf::ir::ConstructorApplication::new(
+                        ssf::ir::Constructor::new(list_type, 1),
+                        vec![],
+                    )
+                    .into(),
+                ],
+            )
+            .into()
+        }
+
+        #[test]
+        fn run_with_reused_unique_constructor() {
+            assert_eq!(
+                run_with_reused_constructor(create_list(ssf::ir::Variable::new("x")), |body| body),
+                "43\n1\n1\n"
+            );
+        }
+
+        #[test]
+        fn run_with_reused_shared_constructor() {
+            assert_eq!(
+                run_with_reused_constructor(
+                    ssf::ir::FunctionApplication::new(
+                        ssf::ir::Variable::new("id"),
+                        ssf::ir::Variable::new("y"),
+                    ),
+                    |body| {
+                        ssf::ir::Let::new(
+                            "y",
+                            create_list_type(),
+                            create_list(ssf::ir::Variable::new("x")),
+                            ssf::ir::ArithmeticOperation::new(
+                                ssf::ir::ArithmeticOperator::Add,
+                                body,
+                                ssf::ir::FunctionApplication::new(
+                                    ssf::ir::Variable::new("head"),
+                                    ssf::ir::Variable::new("y"),
+                                ),
+                            ),
+                        )
+                        .into()
+                    },
+                ),
+                "85\n2\n2\n"
+            );
+        }
+
+        // Static constants have reference counts of zero and are never freed
+        // nor reused.
+        #[test]
+        fn run_with_constant_constructor() {
+            assert_eq!(
+                run_with_reused_constructor(
+                    create_list(ssf::ir::Primitive::Float64(1.0)),
+                    |body| {
+                        ssf::ir::ArithmeticOperation::new(
+                            ssf::ir::ArithmeticOperator::Add,
+                            body.clone(),
+                            body,
+                        )
+                        .into()
+                    },
+                ),
+                "4\n2\n2\n"
+            );
+        }
+    }
+
+    mod shadow_stacks {
+        use super::*;
+
+        #[test]
+        fn fail_to_compile_pointer_tags_with_shadow_stack() {
+            let algebraic_type = ssf::types::Algebraic::with_representation(
+                vec![
+                    (0, ssf::types::Constructor::unboxed(vec![])),
+                    (
+                        1,
+                        ssf::types::Constructor::boxed(vec![ssf::types::Type::Index(0)]),
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+                ssf::types::Representation::PointerTagged,
+            );
+
+            assert!(matches!(
+                compile(
+                    &ssf::ir::Module::new(
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
+                            ssf::ir::Variable::new("x"),
+                            algebraic_type,
+                        )],
+                    ),
+                    &CompileConfiguration {
+                        shadow_stack: Some(ShadowStackFunctions {
+                            push: "push_root".into(),
+                            save: "save_roots".into(),
+                            restore: "restore_roots".into(),
+                        }),
+                        ..Default::default()
+                    },
+                ),
+                Err(CompileError::InvalidConfiguration(_))
+            ));
+        }
+
+        #[test]
+        fn run_with_shadow_stack() {
+            let algebraic_type =
+                ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                ])]);
+
+            assert_eq!(
+                run_module(
+                    &ssf::ir::Module::new(
+                        vec![ssf::ir::ForeignDeclaration::new(
+                            "g",
+                            "collect",
+                            ssf::types::Function::new(
+                                ssf::types::Primitive::Float64,
+                                ssf::types::Primitive::Float64,
+                            ),
+                            ssf::ir::CallingConvention::Target,
+                        )],
+                        vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                        vec![],
+                        vec![ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Let::new(
+                                "y",
+                                algebraic_type.clone(),
+                                ssf::ir::ConstructorApplication::new(
+                                    ssf::ir::Constructor::new(algebraic_type, 0),
+                                    vec![ssf::ir::Variable::new("x").into()],
+                                ),
+                                ssf::ir::FunctionApplication::new(
+                                    ssf::ir::Variable::new("g"),
+                                    ssf::ir::Variable::new("x"),
+                                ),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        )],
+                    ),
+                    &CompileConfiguration {
+                        allocation_strategy: AllocationStrategy::Described("allocate".into()),
+                        shadow_stack: Some(ShadowStackFunctions {
+                            push: "push_root".into(),
+                            save: "save_roots".into(),
+                            restore: "restore_roots".into(),
+                        }),
+                        ..Default::default()
+                    },
+                    r#"
+                        #include <stdint.h>
+                        #include <stdio.h>
+                        #include <stdlib.h>
+
+                        static void *roots[64];
+                        static intptr_t *descriptors[64];
+                        static intptr_t root_count = 0;
+
+                        void *allocate(intptr_t size, intptr_t kind, void *descriptor) {
+                          return malloc(size);
+                        }
+
+                        void push_root(void *root, intptr_t *descriptor) {
+                          roots[root_count] = root;
+                          descriptors[root_count] = descriptor;
+                          root_count++;
+                        }
+
+                        intptr_t save_roots() {
+                          return root_count;
+                        }
+
+                        void restore_roots(intptr_t count) {
+                          root_count = count;
+                        }
+
+                        double collect(double x) {
+                          for (intptr_t i = 0; i < root_count; i++) {
+                            printf("%ld %ld\n", (long)descriptors[i][0], (long)descriptors[i][1]);
+                          }
+
+                          return x;
+                        }
+
+                        double f_foreign(double);
+
+                        int main() {
+                          printf("%g\n", f_foreign(42));
+                          printf("%ld\n", (long)root_count);
+                          return 0;
+                        }
+                    "#,
+                ),
+                "8 1\n8 1\n42\n0\n"
+            );
+        }
+
+        #[test]
+        fn run_with_moving_collector() {
+            let boxed_type =
+                ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                    ssf::types::Primitive::Float64.into(),
+                ])]);
+            let pair_type = ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                boxed_type.clone().into(),
+                boxed_type.clone().into(),
+            ])]);
+            let boxed = ssf::ir::Constructor::new(boxed_type.clone(), 0);
+            let pair = ssf::ir::Constructor::new(pair_type.clone(), 0);
+
+            assert_eq!(
+                run_module(
+                    &ssf::ir::Module::new(
+                        vec![],
+                        vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                        vec![],
+                        vec![
+                            ssf::ir::Definition::new(
+                                "g",
+                                vec![
+                                    ssf::ir::Argument::new("x", boxed_type.clone()),
+                                    ssf::ir::Argument::new("y", boxed_type.clone()),
+                                ],
+                                ssf::ir::ArithmeticOperation::new(
+                                    ssf::ir::ArithmeticOperator::Add,
+                                    ssf::ir::ConstructorField::new(
+                                        boxed.clone(),
+                                        0,
+                                        ssf::ir::Variable::new("x"),
+                                    ),
+                                    ssf::ir::ConstructorField::new(
+                                        boxed.clone(),
+                                        0,
+                                        ssf::ir::Variable::new("y"),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            ),
+                            ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::Let::new(
+                                    "y",
+                                    pair_type,
+                                    ssf::ir::ConstructorApplication::new(
+                                        pair.clone(),
+                                        vec![
+                                            ssf::ir::ConstructorApplication::new(
+                                                boxed.clone(),
+                                                vec![ssf::ir::Variable::new("x").into()],
+                                            )
+                                            .into(),
+                                            ssf::ir::ConstructorApplication::new(
+                                                boxed,
+                                                vec![ssf::ir::ArithmeticOperation::new(
+                                                    ssf::ir::ArithmeticOperator::Add,
+                                                    ssf::ir::Variable::new("x"),
+                                                    ssf::ir::Primitive::Float64(1.0),
+                                                )
+                                                .into()],
+                                            )
+                                            .into(),
+                                        ],
+                                    ),
+                                    ssf::ir::Let::new(
+                                        "z",
+                                        ssf::types::Function::new(
+                                            boxed_type.clone(),
+                                            ssf::types::Primitive::Float64,
+                                        ),
+                                        ssf::ir::FunctionApplication::new(
+                                            ssf::ir::Variable::new("g"),
+                                            ssf::ir::ConstructorField::new(
+                                                pair.clone(),
+                                                0,
+                                                ssf::ir::Variable::new("y"),
+                                            ),
+                                        ),
+                                        ssf::ir::FunctionApplication::new(
+                                            ssf::ir::Variable::new("z"),
+                                            ssf::ir::ConstructorField::new(
+                                                pair,
+                                                1,
+                                                ssf::ir::Variable::new("y"),
+                                            ),
+                                        ),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            ),
+                        ],
+                    ),
+                    &CompileConfiguration {
+                        allocation_strategy: AllocationStrategy::Described("allocate".into()),
+                        shadow_stack: Some(ShadowStackFunctions {
+                            push: "push_root".into(),
+                            save: "save_roots".into(),
+                            restore: "restore_roots".into(),
+                        }),
+                        ..Default::default()
+                    },
+                    // Every allocation copies objects reachable from roots and
+                    // poisons all the old ones.
+                    r#"
+                        #include <stdint.h>
+                        #include <stdio.h>
+                        #include <stdlib.h>
+                        #include <string.h>
+
+                        struct header {
+                          intptr_t size;
+                          intptr_t *descriptor;
+                          void *forward;
+                        };
+
+                        static void *roots[64];
+                        static intptr_t *root_descriptors[64];
+                        static intptr_t root_count = 0;
+                        static void *objects[64];
+                        static intptr_t object_count = 0;
+                        static void *new_objects[64];
+                        static intptr_t new_object_count = 0;
+
+                        static struct header *get_header(void *object) {
+                          return (struct header *)object - 1;
+                        }
+
+                        static int is_object(void *pointer) {
+                          for (intptr_t i = 0; i < object_count; i++) {
+                            if (objects[i] == pointer) {
+                              return 1;
+                            }
+                          }
+
+                          return 0;
+                        }
+
+                        static void *copy(void *object);
+
+                        static void scan_pointer(char *base, intptr_t offset) {
+                          void **field = (void **)(base + offset);
+
+                          if (is_object(*field)) {
+                            *field = copy(*field);
+                          }
+                        }
+
+                        static void scan(char *base, intptr_t *descriptor) {
+                          intptr_t *p = descriptor + 1;
+                          intptr_t count = *p++;
+
+                          for (intptr_t i = 0; i < count; i++) {
+                            scan_pointer(base, *p++);
+                          }
+
+                          intptr_t variant_count = *p++;
+
+                          for (intptr_t i = 0; i < variant_count; i++) {
+                            intptr_t condition_count = *p++;
+                            int matched = 1;
+
+                            for (intptr_t j = 0; j < condition_count; j++, p += 3) {
+                              uint64_t value = 0;
+                              memcpy(&value, base + p[0], p[1]);
+                              matched = matched && (intptr_t)value == p[2];
+                            }
+
+                            intptr_t pointer_count = *p++;
+
+                            for (intptr_t j = 0; j < pointer_count; j++, p++) {
+                              if (matched) {
+                                scan_pointer(base, *p);
+                              }
+                            }
+                          }
+                        }
+
+                        static void *copy(void *object) {
+                          struct header *header = get_header(object);
+
+                          if (header->forward) {
+                            return header->forward;
+                          }
+
+                          struct header *new_header = malloc(sizeof(struct header) + header->size);
+                          memcpy(new_header, header, sizeof(struct header) + header->size);
+                          new_header->forward = NULL;
+                          header->forward = new_header + 1;
+                          new_objects[new_object_count++] = new_header + 1;
+                          scan((char *)(new_header + 1), header->descriptor);
+
+                          return header->forward;
+                        }
+
+                        static void collect() {
+                          new_object_count = 0;
+
+                          for (intptr_t i = 0; i < root_count; i++) {
+                            scan(roots[i], root_descriptors[i]);
+                          }
+
+                          for (intptr_t i = 0; i < object_count; i++) {
+                            memset(objects[i], 0xff, get_header(objects[i])->size);
+                          }
+
+                          memcpy(objects, new_objects, sizeof(void *) * new_object_count);
+                          object_count = new_object_count;
+                        }
+
+                        void *allocate(intptr_t size, intptr_t kind, intptr_t *descriptor) {
+                          collect();
+
+                          struct header *header = calloc(1, sizeof(struct header) + size);
+                          header->size = size;
+                          header->descriptor = descriptor;
+                          objects[object_count++] = header + 1;
+
+                          return header + 1;
+                        }
+
+                        void push_root(void *root, intptr_t *descriptor) {
+                          roots[root_count] = root;
+                          root_descriptors[root_count] = descriptor;
+                          root_count++;
+                        }
+
+                        intptr_t save_roots() {
+                          return root_count;
+                        }
+
+                        void restore_roots(intptr_t count) {
+                          root_count = count;
+                        }
+
+                        double f_foreign(double);
+
+                        int main() {
+                          printf("%g\n", f_foreign(42));
+                          printf("%ld\n", (long)root_count);
+                          return 0;
+                        }
+                    "#,
+                ),
+                "85\n0\n"
+            );
+        }
+    }
+
+    mod expressions {
+        use super::*;
+
+        #[test]
+        fn compile_let() {
+            compile_module(&ssf::ir::Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![ssf::ir::Definition::new(
+                    "f",
+                    vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                    ssf::ir::Let::new(
+                        "y",
+                        ssf::types::Primitive::Float64,
+                        ssf::ir::Variable::new("x"),
+                        ssf::ir::Variable::new("y"),
+                    ),
+                    ssf::types::Primitive::Float64,
+                )],
+            ));
+        }
+
+        #[test]
+        fn compile_let_recursive() {
+            compile_module(&ssf::ir::Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![ssf::ir::Definition::new(
+                    "f",
+                    vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                    ssf::ir::LetRecursive::new(
+                        vec![ssf::ir::Definition::new(
+                            "g",
+                            vec![ssf::ir::Argument::new("y", ssf::types::Primitive::Float64)],
+                            ssf::ir::ArithmeticOperation::new(
+                                ssf::ir::ArithmeticOperator::Add,
+                                ssf::ir::Variable::new("x"),
+                                ssf::ir::Variable::new("y"),
+                            ),
+                            ssf::types::Primitive::Float64,
+                        )],
+                        ssf::ir::FunctionApplication::new(
+                            ssf::ir::Variable::new("g"),
+                            ssf::ir::Primitive::Float64(42.0),
+                        ),
+                    ),
+                    ssf::types::Primitive::Float64,
+                )],
+            ));
+        }
+
+        #[test]
+        fn compile_let_recursive_with_curried_function() {
+            compile_module(&ssf::ir::Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![ssf::ir::Definition::new(
+                    "f",
+                    vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                    ssf::ir::LetRecursive::new(
+                        vec![ssf::ir::Definition::new(
+                            "g",
+                            vec![ssf::ir::Argument::new("y", ssf::types::Primitive::Float64)],
+                            ssf::ir::LetRecursive::new(
+                                vec![ssf::ir::Definition::new(
+                                    "h",
+                                    vec![ssf::ir::Argument::new(
+                                        "z",
+                                        ssf::types::Primitive::Float64,
+                                    )],
+                                    ssf::ir::ArithmeticOperation::new(
+                                        ssf::ir::ArithmeticOperator::Add,
+                                        ssf::ir::ArithmeticOperation::new(
+                                            ssf::ir::ArithmeticOperator::Add,
+                                            ssf::ir::Variable::new("x"),
+                                            ssf::ir::Variable::new("y"),
+                                        ),
+                                        ssf::ir::Variable::new("z"),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                )],
+                                ssf::ir::Variable::new("h"),
+                            ),
+                            ssf::types::Function::new(
+                                ssf::types::Primitive::Float64,
+                                ssf::types::Primitive::Float64,
+                            ),
+                        )],
+                        ssf::ir::FunctionApplication::new(
+                            ssf::ir::FunctionApplication::new(
+                                ssf::ir::Variable::new("g"),
+                                ssf::ir::Primitive::Float64(42.0),
+                            ),
+                            ssf::ir::Primitive::Float64(42.0),
+                        ),
+                    ),
+                    ssf::types::Primitive::Float64,
+                )],
+            ));
+        }
+
+        mod lambdas {
+            use super::*;
+
+            #[test]
+            fn compile() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::Lambda::new(
+                            vec![ssf::ir::Argument::new("y", ssf::types::Primitive::Float64)],
+                            ssf::ir::Variable::new("x"),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::types::Function::new(
+                            ssf::types::Primitive::Float64,
+                            ssf::types::Primitive::Float64,
+                        ),
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_with_captured_variable() {
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::FunctionApplication::new(
+                                    ssf::ir::Lambda::new(
+                                        vec![ssf::ir::Argument::new(
+                                            "y",
+                                            ssf::types::Primitive::Float64
+                                        )],
+                                        ssf::ir::ArithmeticOperation::new(
+                                            ssf::ir::ArithmeticOperator::Add,
+                                            ssf::ir::Variable::new("x"),
+                                            ssf::ir::Variable::new("y"),
+                                        ),
+                                        ssf::types::Primitive::Float64,
+                                    ),
+                                    ssf::ir::Primitive::Float64(1.0),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            )],
+                        ),
+                        &create_reference_counting_configuration(),
+                        r#"
+                            #include <stdio.h>
+
+                            double f_foreign(double);
+
+                            int main() {
+                              printf("%g\n", f_foreign(41));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "42\n"
+                );
+            }
+        }
+
+        mod arrays {
+            use super::*;
+
+            #[test]
+            fn compile_array() {
+                let array_type = ssf::types::Array::new(ssf::types::Primitive::Float64);
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::Array::new(
+                            array_type.clone(),
+                            vec![
+                                ssf::ir::Variable::new("x").into(),
+                                ssf::ir::Primitive::Float64(42.0).into(),
+                            ],
+                        ),
+                        array_type,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_array_length() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new(
+                            "x",
+                            ssf::types::Array::new(ssf::types::Primitive::Float64),
+                        )],
+                        ssf::ir::ArrayLength::new(ssf::ir::Variable::new("x")),
+                        ssf::types::Primitive::Integer64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_array_element() {
+                let array_type = ssf::types::Array::new(ssf::types::Primitive::Float64);
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", array_type.clone())],
+                        ssf::ir::ArrayElement::new(
+                            array_type,
+                            ssf::ir::Variable::new("x"),
+                            ssf::ir::Primitive::Integer64(0),
+                            ssf::ir::Primitive::Float64(42.0),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_array_initialization() {
+                let array_type = ssf::types::Array::new(ssf::types::Primitive::Integer64);
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new(
+                            "x",
+                            ssf::types::Primitive::Integer64,
+                        )],
+                        ssf::ir::ArrayInitialization::new(
+                            array_type.clone(),
+                            ssf::ir::Variable::new("x"),
+                            ssf::ir::Lambda::new(
+                                vec![ssf::ir::Argument::new(
+                                    "i",
+                                    ssf::types::Primitive::Integer64,
+                                )],
+                                ssf::ir::Variable::new("i"),
+                                ssf::types::Primitive::Integer64,
+                            ),
+                        ),
+                        array_type,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_recursive_type_with_array() {
+                let algebraic_type =
+                    ssf::types::Algebraic::new(vec![ssf::types::Constructor::unboxed(vec![
+                        ssf::types::Array::new(ssf::types::Type::Index(0)).into(),
+                    ])]);
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
+                        ssf::ir::Variable::new("x"),
+                        algebraic_type,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_with_initialization() {
+                let array_type = ssf::types::Array::new(ssf::types::Primitive::Integer64);
+                let add = |lhs: ssf::ir::Expression, rhs: ssf::ir::Expression| {
+                    ssf::ir::ArithmeticOperation::new(ssf::ir::ArithmeticOperator::Add, lhs, rhs)
+                };
+
+                for reference_counting in [false, true] {
+                    assert_eq!(
+                        run_module(
+                            &ssf::ir::Module::new(
+                                vec![],
+                                vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                                vec![],
+                                vec![ssf::ir::Definition::new(
+                                    "f",
+                                    vec![ssf::ir::Argument::new(
+                                        "x",
+                                        ssf::types::Primitive::Integer64
+                                    )],
+                                    ssf::ir::Let::new(
+                                        "a",
+                                        array_type.clone(),
+                                        ssf::ir::ArrayInitialization::new(
+                                            array_type.clone(),
+                                            ssf::ir::Variable::new("x"),
+                                            ssf::ir::Lambda::new(
+                                                vec![ssf::ir::Argument::new(
+                                                    "i",
+                                                    ssf::types::Primitive::Integer64,
+                                                )],
+                                                add(
+                                                    ssf::ir::Variable::new("i").into(),
+                                                    ssf::ir::Primitive::Integer64(40).into(),
+                                                ),
+                                                ssf::types::Primitive::Integer64,
+                                            ),
+                                        ),
+                                        add(
+                                            add(
+                                                ssf::ir::ArrayElement::new(
+                                                    array_type.clone(),
+                                                    ssf::ir::Variable::new("a"),
+                                                    ssf::ir::Primitive::Integer64(2),
+                                                    ssf::ir::Primitive::Integer64(0),
+                                                )
+                                                .into(),
+                                                ssf::ir::ArrayLength::new(ssf::ir::Variable::new(
+                                                    "a"
+                                                ))
+                                                .into(),
+                                            )
+                                            .into(),
+                                            ssf::ir::ArrayElement::new(
+                                                array_type.clone(),
+                                                ssf::ir::Variable::new("a"),
+                                                ssf::ir::Primitive::Integer64(-1),
+                                                ssf::ir::Primitive::Integer64(100),
+                                            )
+                                            .into(),
+                                        ),
+                                    ),
+                                    ssf::types::Primitive::Integer64,
+                                )],
+                            ),
+                            &CompileConfiguration {
+                                reference_counting,
+                                ..Default::default()
+                            },
+                            r#"
+                                #include <stdint.h>
+                                #include <stdio.h>
+
+                                int64_t f_foreign(int64_t);
+
+                                int main() {
+                                  printf("%ld\n", (long)f_foreign(3));
+                                  return 0;
+                                }
+                            "#,
+                        ),
+                        "145\n"
+                    );
+                }
+            }
+
+            #[test]
+            fn run_with_long_initialization() {
+                let array_type = ssf::types::Array::new(ssf::types::Primitive::Integer64);
+
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new(
+                                    "x",
+                                    ssf::types::Primitive::Integer64
+                                )],
+                                ssf::ir::ArrayElement::new(
+                                    array_type.clone(),
+                                    ssf::ir::ArrayInitialization::new(
+                                        array_type,
+                                        ssf::ir::Variable::new("x"),
+                                        ssf::ir::Lambda::new(
+                                            vec![ssf::ir::Argument::new(
+                                                "i",
+                                                ssf::types::Primitive::Integer64,
+                                            )],
+                                            ssf::ir::Variable::new("i"),
+                                            ssf::types::Primitive::Integer64,
+                                        ),
+                                    ),
+                                    ssf::ir::ArithmeticOperation::new(
+                                        ssf::ir::ArithmeticOperator::Subtract,
+                                        ssf::ir::Variable::new("x"),
+                                        ssf::ir::Primitive::Integer64(1),
+                                    ),
+                                    ssf::ir::Primitive::Integer64(0),
+                                ),
+                                ssf::types::Primitive::Integer64,
+                            )],
+                        ),
+                        &Default::default(),
+                        r#"
+                            #include <stdint.h>
+                            #include <stdio.h>
+
+                            int64_t f_foreign(int64_t);
+
+                            int main() {
+                              printf("%ld\n", (long)f_foreign(1000000));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "999999\n"
+                );
+            }
+
+            #[test]
+            fn run_with_boxed_elements() {
+                let record_type =
+                    ssf::types::Record::boxed(vec![ssf::types::Primitive::Float64.into()]);
+                let array_type = ssf::types::Array::new(record_type.clone());
+
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::RecordElement::new(
+                                    record_type.clone(),
+                                    0,
+                                    ssf::ir::ArrayElement::new(
+                                        array_type.clone(),
+                                        ssf::ir::Array::new(
+                                            array_type,
+                                            vec![
+                                                ssf::ir::Record::new(
+                                                    record_type.clone(),
+                                                    vec![ssf::ir::Primitive::Float64(0.0).into()],
+                                                )
+                                                .into(),
+                                                ssf::ir::Record::new(
+                                                    record_type.clone(),
+                                                    vec![ssf::ir::Variable::new("x").into()],
+                                                )
+                                                .into(),
+                                            ],
+                                        ),
+                                        ssf::ir::Primitive::Integer64(1),
+                                        ssf::ir::Record::new(
+                                            record_type,
+                                            vec![ssf::ir::Primitive::Float64(0.0).into()],
+                                        ),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            )],
+                        ),
+                        &create_reference_counting_configuration(),
+                        r#"
+                            #include <stdio.h>
+
+                            double f_foreign(double);
+
+                            int main() {
+                              printf("%g\n", f_foreign(42));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "42\n"
+                );
+            }
+
+            #[test]
+            fn compile_byte_string() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::ByteString::new("foo"),
+                        ssf::types::Array::new(ssf::types::Primitive::Integer8),
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_with_byte_string() {
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new(
+                                    "x",
+                                    ssf::types::Primitive::Integer64
+                                )],
+                                ssf::ir::ArrayElement::new(
+                                    ssf::types::Array::new(ssf::types::Primitive::Integer8),
+                                    ssf::ir::ByteString::new("hello"),
+                                    ssf::ir::Variable::new("x"),
+                                    ssf::ir::Primitive::Integer8(0),
+                                ),
+                                ssf::types::Primitive::Integer8,
+                            )],
+                        ),
+                        &create_reference_counting_configuration(),
+                        r#"
+                            #include <stdint.h>
+                            #include <stdio.h>
+
+                            uint8_t f_foreign(int64_t);
+
+                            int main() {
+                              printf("%d %d\n", f_foreign(1), f_foreign(5));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "101 0\n"
+                );
+            }
+        }
+
+        mod constructor_fields {
+            use super::*;
+
+            #[test]
+            fn compile_with_singleton_type() {
+                let algebraic_type =
+                    ssf::types::Algebraic::new(vec![ssf::types::Constructor::boxed(vec![
+                        ssf::types::Primitive::Float64.into(),
+                        ssf::types::Primitive::Integer64.into(),
+                    ])]);
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
+                        ssf::ir::ConstructorField::new(
+                            ssf::ir::Constructor::new(algebraic_type, 0),
+                            1,
+                            ssf::ir::Variable::new("x"),
+                        ),
+                        ssf::types::Primitive::Integer64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_with_tagged_type() {
+                let algebraic_type = ssf::types::Algebraic::new(vec![
+                    ssf::types::Constructor::unboxed(vec![]),
+                    ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                    ssf::types::Constructor::unboxed(vec![ssf::types::Primitive::Float64.into()]),
+                ]);
+
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::ConstructorField::new(
+                                    ssf::ir::Constructor::new(algebraic_type.clone(), 1),
+                                    0,
+                                    ssf::ir::ConstructorApplication::new(
+                                        ssf::ir::Constructor::new(algebraic_type, 1),
+                                        vec![ssf::ir::Variable::new("x").into()],
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            )],
+                        ),
+                        &create_reference_counting_configuration(),
+                        r#"
+                            #include <stdio.h>
+
+                            double f_foreign(double);
+
+                            int main() {
+                              printf("%g\n", f_foreign(42));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "42\n"
+                );
+            }
+
+            #[test]
+            fn run_with_different_constructor() {
+                let algebraic_type = ssf::types::Algebraic::new(vec![
+                    ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                    ssf::types::Constructor::boxed(vec![ssf::types::Primitive::Float64.into()]),
+                ]);
+
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::ConstructorField::new(
+                                    ssf::ir::Constructor::new(algebraic_type.clone(), 1),
+                                    0,
+                                    ssf::ir::ConstructorApplication::new(
+                                        ssf::ir::Constructor::new(algebraic_type, 0),
+                                        vec![ssf::ir::Variable::new("x").into()],
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            )],
+                        ),
+                        &CompileConfiguration {
+                            constructor_field_failure: Some("fail".into()),
+                            ..Default::default()
+                        },
+                        r#"
+                            #include <stdio.h>
+                            #include <stdlib.h>
+
+                            double f_foreign(double);
+
+                            void fail() {
+                              printf("fail\n");
+                              exit(0);
+                            }
+
+                            int main() {
+                              printf("%g\n", f_foreign(42));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "fail\n"
+                );
+            }
+        }
+
+        mod records {
+            use super::*;
+
+            fn create_record_type(boxed: bool) -> ssf::types::Record {
+                ssf::types::Record::new(
+                    vec![
+                        ssf::types::Primitive::Float64.into(),
+                        ssf::types::Primitive::Integer64.into(),
+                    ],
+                    boxed,
+                )
+            }
+
+            #[test]
+            fn compile_record() {
+                for boxed in [false, true] {
+                    let record_type = create_record_type(boxed);
+
+                    compile_module(&ssf::ir::Module::new(
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::Record::new(
+                                record_type.clone(),
+                                vec![
+                                    ssf::ir::Variable::new("x").into(),
+                                    ssf::ir::Primitive::Integer64(42).into(),
+                                ],
+                            ),
+                            record_type,
+                        )],
+                    ));
+                }
+            }
+
+            #[test]
+            fn compile_record_element() {
+                for boxed in [false, true] {
+                    let record_type = create_record_type(boxed);
+
+                    compile_module(&ssf::ir::Module::new(
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", record_type.clone())],
+                            ssf::ir::RecordElement::new(
+                                record_type,
+                                1,
+                                ssf::ir::Variable::new("x"),
+                            ),
+                            ssf::types::Primitive::Integer64,
+                        )],
+                    ));
+                }
+            }
+
+            #[test]
+            fn compile_record_update() {
+                for boxed in [false, true] {
+                    let record_type = create_record_type(boxed);
+
+                    compile_module(&ssf::ir::Module::new(
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", record_type.clone())],
+                            ssf::ir::RecordUpdate::new(
+                                record_type.clone(),
+                                ssf::ir::Variable::new("x"),
+                                vec![(0, ssf::ir::Primitive::Float64(42.0).into())],
+                            ),
+                            record_type,
+                        )],
+                    ));
+                }
+            }
+
+            #[test]
+            fn compile_recursive_type_with_boxed_record() {
+                let algebraic_type = ssf::types::Algebraic::new(vec![
+                    ssf::types::Constructor::unboxed(vec![]),
+                    ssf::types::Constructor::unboxed(vec![ssf::types::Record::boxed(vec![
+                        ssf::types::Primitive::Float64.into(),
+                        ssf::types::Type::Index(0),
+                    ])
+                    .into()]),
+                ]);
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
+                        ssf::ir::Variable::new("x"),
+                        algebraic_type,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_with_reference_counting() {
+                for boxed in [false, true] {
+                    let record_type = create_record_type(boxed);
+
+                    assert_eq!(
+                        run_module(
+                            &ssf::ir::Module::new(
+                                vec![],
+                                vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                                vec![],
+                                vec![ssf::ir::Definition::new(
+                                    "f",
+                                    vec![ssf::ir::Argument::new(
+                                        "x",
+                                        ssf::types::Primitive::Float64
+                                    )],
+                                    ssf::ir::RecordElement::new(
+                                        record_type.clone(),
+                                        0,
+                                        ssf::ir::RecordUpdate::new(
+                                            record_type.clone(),
+                                            ssf::ir::Record::new(
+                                                record_type.clone(),
+                                                vec![
+                                                    ssf::ir::Primitive::Float64(0.0).into(),
+                                                    ssf::ir::Primitive::Integer64(42).into(),
+                                                ],
+                                            ),
+                                            vec![(0, ssf::ir::Variable::new("x").into())],
+                                        ),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                )],
+                            ),
+                            &create_reference_counting_configuration(),
+                            r#"
+                                #include <stdio.h>
+
+                                double f_foreign(double);
+
+                                int main() {
+                                  printf("%g\n", f_foreign(42));
+                                  return 0;
+                                }
+                            "#,
+                        ),
+                        "42\n"
+                    );
+                }
+            }
+        }
+
+        mod lazy {
+            use super::*;
+
+            #[test]
+            fn compile_delay() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::Delay::new(
+                            ssf::ir::Variable::new("x"),
+                            ssf::types::Primitive::Float64,
+                        ),
+                        ssf::types::Lazy::new(ssf::types::Primitive::Float64),
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_force() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new(
+                            "x",
+                            ssf::types::Lazy::new(ssf::types::Primitive::Float64),
+                        )],
+                        ssf::ir::Force::new(ssf::ir::Variable::new("x")),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_force() {
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::Let::new(
+                                    "y",
+                                    ssf::types::Lazy::new(ssf::types::Primitive::Float64),
+                                    ssf::ir::Delay::new(
+                                        ssf::ir::ArithmeticOperation::new(
+                                            ssf::ir::ArithmeticOperator::Add,
+                                            ssf::ir::Variable::new("x"),
+                                            ssf::ir::Primitive::Float64(1.0),
+                                        ),
+                                        ssf::types::Primitive::Float64,
+                                    ),
+                                    ssf::ir::ArithmeticOperation::new(
+                                        ssf::ir::ArithmeticOperator::Add,
+                                        ssf::ir::Force::new(ssf::ir::Variable::new("y")),
+                                        ssf::ir::Force::new(ssf::ir::Variable::new("y")),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            )],
+                        ),
+                        &Default::default(),
+                        r#"
+                            #include <stdio.h>
+
+                            double f_foreign(double);
+
+                            int main() {
+                              printf("%g\n", f_foreign(20));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "42\n"
+                );
+            }
+        }
+
+        mod algebraic_cases {
+            use super::*;
+
+            fn run_list_sum(
+                list_type: ssf::types::Algebraic,
+                configuration: &CompileConfiguration,
+            ) -> String {
+                let cons = |head: ssf::ir::Expression, tail: ssf::ir::Expression| {
+                    ssf::ir::ConstructorApplication::new(
+                        ssf::ir::Constructor::new(list_type.clone(), 1),
+                        vec![head, tail],
+                    )
+                };
+
+                run_module(
+                    &ssf::ir::Module::new(
+                        vec![],
+                        vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                        vec![],
+                        vec![
+                            ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                ssf::ir::FunctionApplication::new(
+                                    ssf::ir::Variable::new("sum"),
+                                    cons(
+                                        ssf::ir::Variable::new("x").into(),
+                                        cons(
+                                            ssf::ir::Primitive::Float64(1.0).into(),
+                                            ssf::ir::ConstructorApplication::new(
+                                                ssf::ir::Constructor::new(list_type.clone(), 0),
+                                                vec![],
+                                            )
+                                            .into(),
+                                        )
+                                        .into(),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            ),
+                            ssf::ir::Definition::new(
+                                "sum",
+                                vec![ssf::ir::Argument::new("x", list_type.clone())],
+                                ssf::ir::AlgebraicCase::new(
+                                    ssf::ir::Variable::new("x"),
+                                    vec![ssf::ir::AlgebraicAlternative::new(
+                                        ssf::ir::Constructor::new(list_type.clone(), 1),
+                                        vec!["y".into(), "z".into()],
+                                        ssf::ir::ArithmeticOperation::new(
+                                            ssf::ir::ArithmeticOperator::Add,
+                                            ssf::ir::Variable::new("y"),
+                                            ssf::ir::FunctionApplication::new(
+                                                ssf::ir::Variable::new("sum"),
+                                                ssf::ir::Variable::new("z"),
+                                            ),
+                                        ),
+                                    )],
+                                    Some(ssf::ir::Primitive::Float64(0.0).into()),
+                                ),
+                                ssf::types::Primitive::Float64,
+                            ),
+                        ],
+                    ),
+                    configuration,
+                    r#"
+                            #include <stdio.h>
+
+                            double f_foreign(double);
+
+                            int main() {
+                              printf("%g\n", f_foreign(41));
+                              return 0;
+                            }
+                        "#,
+                )
+            }
+
+            fn create_list_type(
+                representation: ssf::types::Representation,
+            ) -> ssf::types::Algebraic {
+                ssf::types::Algebraic::with_representation(
+                    vec![
+                        (0, ssf::types::Constructor::unboxed(vec![])),
+                        (
+                            1,
+                            ssf::types::Constructor::boxed(vec![
+                                ssf::types::Primitive::Float64.into(),
+                                ssf::types::Type::Index(0),
+                            ]),
+                        ),
+                    ]
+                    .into_iter()
+                    .collect(),
+                    representation,
+                )
+            }
+
+            #[test]
+            fn run_with_null_pointer_niche() {
+                assert_eq!(
+                    run_list_sum(
+                        create_list_type(ssf::types::Representation::Compact),
+                        &Default::default()
+                    ),
+                    "42\n"
+                );
+            }
+
+            #[test]
+            fn run_with_pointer_tags() {
+                assert_eq!(
+                    run_list_sum(
+                        create_list_type(ssf::types::Representation::PointerTagged),
+                        &Default::default()
+                    ),
+                    "42\n"
+                );
+            }
+
+            #[test]
+            fn fail_to_compile_pointer_tags_on_32_bit_target() {
+                let list_type = create_list_type(ssf::types::Representation::PointerTagged);
+
+                assert!(matches!(
+                    compile(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", list_type.clone())],
+                                ssf::ir::Variable::new("x"),
+                                list_type,
+                            )],
+                        ),
+                        &CompileConfiguration {
+                            word_size: 4,
+                            ..Default::default()
+                        },
+                    ),
+                    Err(CompileError::InvalidConfiguration(_))
+                ));
+            }
+
+            #[test]
+            fn run_with_pointer_tags_and_reference_counting() {
+                assert_eq!(
+                    run_list_sum(
+                        create_list_type(ssf::types::Representation::PointerTagged),
+                        &create_reference_counting_configuration()
+                    ),
+                    "42\n"
+                );
+            }
+
+            #[test]
+            fn compile_with_singleton_enum() {
+                let algebraic_type =
+                    ssf::types::Algebraic::new(vec![ssf::types::Constructor::unboxed(vec![])]);
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
+                        ssf::ir::AlgebraicCase::new(
+                            ssf::ir::Variable::new("x"),
+                            vec![ssf::ir::AlgebraicAlternative::new(
+                                ssf::ir::Constructor::new(algebraic_type, 0),
+                                vec![],
+                                ssf::ir::Primitive::Float64(42.0),
+                            )],
+                            None,
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_with_1_element_singleton() {
+                let algebraic_type = ssf::types::Algebraic::new(vec![
                     ssf::types::Constructor::unboxed(vec![ssf::types::Primitive::Float64.into()]),
                 ]);
 
@@ -615,54 +3387,389 @@ mod tests {
                     vec![],
                     vec![ssf::ir::Definition::new(
                         "f",
-                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
-                        ssf::ir::AlgebraicCase::new(
+                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
+                        ssf::ir::AlgebraicCase::new(
+                            ssf::ir::Variable::new("x"),
+                            vec![
+                                ssf::ir::AlgebraicAlternative::new(
+                                    ssf::ir::Constructor::new(algebraic_type.clone(), 42),
+                                    vec!["y".into()],
+                                    ssf::ir::Variable::new("y"),
+                                ),
+                                ssf::ir::AlgebraicAlternative::new(
+                                    ssf::ir::Constructor::new(algebraic_type, 2045),
+                                    vec![],
+                                    ssf::ir::Primitive::Float64(42.0),
+                                ),
+                            ],
+                            None,
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_with_same_tags_of_different_enums() {
+                let create_enum_type = |tags: &[u64]| {
+                    ssf::types::Algebraic::with_tags(
+                        tags.iter()
+                            .map(|&tag| (tag, ssf::types::Constructor::unboxed(vec![])))
+                            .collect(),
+                    )
+                };
+                let module = ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![
+                            ssf::ir::Argument::new("x", create_enum_type(&[0, 1])),
+                            ssf::ir::Argument::new("y", create_enum_type(&[1, 2])),
+                        ],
+                        ssf::ir::Primitive::Float64(42.0),
+                        ssf::types::Primitive::Float64,
+                    )],
+                );
+
+                compile_module_with_configuration(
+                    &module,
+                    &create_reference_counting_configuration(),
+                );
+                compile_module_with_configuration(
+                    &module,
+                    &CompileConfiguration {
+                        allocation_strategy: AllocationStrategy::Described("allocate".into()),
+                        shadow_stack: Some(ShadowStackFunctions {
+                            push: "push_root".into(),
+                            save: "save_roots".into(),
+                            restore: "restore_roots".into(),
+                        }),
+                        ..Default::default()
+                    },
+                );
+            }
+
+            #[test]
+            fn compile_with_binary_search() {
+                let algebraic_type = ssf::types::Algebraic::new(
+                    (0..10)
+                        .map(|_| ssf::types::Constructor::unboxed(vec![]))
+                        .collect(),
+                );
+
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", algebraic_type.clone())],
+                        ssf::ir::AlgebraicCase::new(
+                            ssf::ir::Variable::new("x"),
+                            (0..9)
+                                .rev()
+                                .map(|tag| {
+                                    ssf::ir::AlgebraicAlternative::new(
+                                        ssf::ir::Constructor::new(algebraic_type.clone(), tag),
+                                        vec![],
+                                        ssf::ir::Primitive::Float64(tag as f64),
+                                    )
+                                })
+                                .collect(),
+                            Some(ssf::ir::Primitive::Float64(42.0).into()),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_with_binary_search() {
+                let algebraic_type = ssf::types::Algebraic::new(
+                    (0..10)
+                        .map(|_| ssf::types::Constructor::unboxed(vec![]))
+                        .collect(),
+                );
+                let compile_constructor = |tag| {
+                    ssf::ir::ConstructorApplication::new(
+                        ssf::ir::Constructor::new(algebraic_type.clone(), tag),
+                        vec![],
+                    )
+                };
+
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new(
+                                    "x",
+                                    ssf::types::Primitive::Integer64
+                                )],
+                                ssf::ir::Let::new(
+                                    "y",
+                                    algebraic_type.clone(),
+                                    ssf::ir::PrimitiveCase::new(
+                                        ssf::ir::Variable::new("x"),
+                                        (0..9)
+                                            .map(|tag| {
+                                                ssf::ir::PrimitiveAlternative::new(
+                                                    ssf::ir::Primitive::Integer64(tag),
+                                                    compile_constructor(tag),
+                                                )
+                                            })
+                                            .collect(),
+                                        Some(compile_constructor(9).into()),
+                                    ),
+                                    ssf::ir::AlgebraicCase::new(
+                                        ssf::ir::Variable::new("y"),
+                                        // Default alternatives are reached in
+                                        // both halves.
+                                        [0, 1, 3, 4, 5, 6, 8, 9]
+                                            .iter()
+                                            .rev()
+                                            .map(|&tag| {
+                                                ssf::ir::AlgebraicAlternative::new(
+                                                    ssf::ir::Constructor::new(
+                                                        algebraic_type.clone(),
+                                                        tag,
+                                                    ),
+                                                    vec![],
+                                                    ssf::ir::Primitive::Integer64(tag + 100),
+                                                )
+                                            })
+                                            .collect(),
+                                        Some(ssf::ir::Primitive::Integer64(0).into()),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Integer64,
+                            )],
+                        ),
+                        &Default::default(),
+                        r#"
+                            #include <stdint.h>
+                            #include <stdio.h>
+
+                            uint64_t f_foreign(uint64_t);
+
+                            int main() {
+                              for (uint64_t x = 0; x < 10; x++) {
+                                printf("%d\n", (int)f_foreign(x));
+                              }
+
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "100\n101\n0\n103\n104\n105\n106\n0\n108\n109\n"
+                );
+            }
+        }
+
+        mod primitive_cases {
+            use super::*;
+
+            #[test]
+            fn compile() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::PrimitiveCase::new(
+                            ssf::ir::Variable::new("x"),
+                            vec![
+                                ssf::ir::PrimitiveAlternative::new(
+                                    ssf::ir::Primitive::Float64(0.0),
+                                    ssf::ir::Primitive::Float64(1.0),
+                                ),
+                                ssf::ir::PrimitiveAlternative::new(
+                                    ssf::ir::Primitive::Float64(2.0),
+                                    ssf::ir::Primitive::Float64(3.0),
+                                ),
+                            ],
+                            None,
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_with_default_alternative() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::PrimitiveCase::new(
+                            ssf::ir::Variable::new("x"),
+                            vec![
+                                ssf::ir::PrimitiveAlternative::new(
+                                    ssf::ir::Primitive::Float64(0.0),
+                                    ssf::ir::Primitive::Float64(1.0),
+                                ),
+                                ssf::ir::PrimitiveAlternative::new(
+                                    ssf::ir::Primitive::Float64(2.0),
+                                    ssf::ir::Primitive::Float64(3.0),
+                                ),
+                            ],
+                            Some(ssf::ir::Primitive::Float64(4.0).into()),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn run_with_binary_search() {
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new(
+                                    "x",
+                                    ssf::types::Primitive::Integer64
+                                )],
+                                ssf::ir::PrimitiveCase::new(
+                                    ssf::ir::Variable::new("x"),
+                                    (0..10)
+                                        .rev()
+                                        .map(|number| {
+                                            ssf::ir::PrimitiveAlternative::new(
+                                                ssf::ir::Primitive::Integer64(number),
+                                                ssf::ir::Primitive::Integer64(number + 100),
+                                            )
+                                        })
+                                        .collect(),
+                                    Some(ssf::ir::Primitive::Integer64(0).into()),
+                                ),
+                                ssf::types::Primitive::Integer64,
+                            )],
+                        ),
+                        &Default::default(),
+                        r#"
+                            #include <stdint.h>
+                            #include <stdio.h>
+
+                            uint64_t f_foreign(uint64_t);
+
+                            int main() {
+                              for (uint64_t x = 0; x < 12; x++) {
+                                printf("%d\n", (int)f_foreign(x));
+                              }
+
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "100\n101\n102\n103\n104\n105\n106\n107\n108\n109\n0\n0\n"
+                );
+            }
+
+            #[test]
+            fn compile_with_null_pointer() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Pointer)],
+                        ssf::ir::PrimitiveCase::new(
+                            ssf::ir::Variable::new("x"),
+                            vec![ssf::ir::PrimitiveAlternative::new(
+                                ssf::ir::Primitive::NullPointer,
+                                ssf::ir::Primitive::Float64(42.0),
+                            )],
+                            Some(ssf::ir::Primitive::Float64(13.0).into()),
+                        ),
+                        ssf::types::Primitive::Float64,
+                    )],
+                ));
+            }
+        }
+
+        mod pointers {
+            use super::*;
+
+            #[test]
+            fn compile_null_pointer() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::Primitive::NullPointer,
+                        ssf::types::Primitive::Pointer,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_comparison_operation() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Pointer)],
+                        ssf::ir::ComparisonOperation::new(
+                            ssf::ir::ComparisonOperator::LessThan,
+                            ssf::ir::Variable::new("x"),
+                            ssf::ir::Primitive::NullPointer,
+                        ),
+                        ssf::types::Primitive::Boolean,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_pointer_offset() {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Pointer)],
+                        ssf::ir::PointerOffset::new(
                             ssf::ir::Variable::new("x"),
-                            vec![
-                                ssf::ir::AlgebraicAlternative::new(
-                                    ssf::ir::Constructor::new(algebraic_type.clone(), 42),
-                                    vec!["y".into()],
-                                    ssf::ir::Variable::new("y"),
-                                ),
-                                ssf::ir::AlgebraicAlternative::new(
-                                    ssf::ir::Constructor::new(algebraic_type, 2045),
-                                    vec![],
-                                    ssf::ir::Primitive::Float64(42.0),
-                                ),
-                            ],
-                            None,
+                            ssf::ir::Primitive::Integer64(8),
                         ),
-                        ssf::types::Primitive::Float64,
+                        ssf::types::Primitive::Pointer,
                     )],
                 ));
             }
-        }
-
-        mod primitive_cases {
-            use super::*;
 
             #[test]
-            fn compile() {
+            fn compile_load() {
                 compile_module(&ssf::ir::Module::new(
                     vec![],
                     vec![],
                     vec![],
                     vec![ssf::ir::Definition::new(
                         "f",
-                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
-                        ssf::ir::PrimitiveCase::new(
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Pointer)],
+                        ssf::ir::Load::new(
+                            ssf::types::Primitive::Float64,
                             ssf::ir::Variable::new("x"),
-                            vec![
-                                ssf::ir::PrimitiveAlternative::new(
-                                    ssf::ir::Primitive::Float64(0.0),
-                                    ssf::ir::Primitive::Float64(1.0),
-                                ),
-                                ssf::ir::PrimitiveAlternative::new(
-                                    ssf::ir::Primitive::Float64(2.0),
-                                    ssf::ir::Primitive::Float64(3.0),
-                                ),
-                            ],
-                            None,
                         ),
                         ssf::types::Primitive::Float64,
                     )],
@@ -670,37 +3777,226 @@ mod tests {
             }
 
             #[test]
-            fn compile_with_default_alternative() {
+            fn compile_store() {
                 compile_module(&ssf::ir::Module::new(
                     vec![],
                     vec![],
                     vec![],
                     vec![ssf::ir::Definition::new(
                         "f",
-                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
-                        ssf::ir::PrimitiveCase::new(
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Pointer)],
+                        ssf::ir::Store::new(
+                            ssf::types::Primitive::Float64,
+                            ssf::ir::Primitive::Float64(42.0),
+                            ssf::ir::Variable::new("x"),
                             ssf::ir::Variable::new("x"),
-                            vec![
-                                ssf::ir::PrimitiveAlternative::new(
-                                    ssf::ir::Primitive::Float64(0.0),
-                                    ssf::ir::Primitive::Float64(1.0),
-                                ),
-                                ssf::ir::PrimitiveAlternative::new(
-                                    ssf::ir::Primitive::Float64(2.0),
-                                    ssf::ir::Primitive::Float64(3.0),
-                                ),
-                            ],
-                            Some(ssf::ir::Primitive::Float64(4.0).into()),
                         ),
-                        ssf::types::Primitive::Float64,
+                        ssf::types::Primitive::Pointer,
                     )],
                 ));
             }
+
+            #[test]
+            fn run_with_load_and_store() {
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![ssf::ir::Definition::new(
+                                "f",
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Pointer)],
+                                ssf::ir::Store::new(
+                                    ssf::types::Primitive::Integer64,
+                                    ssf::ir::Primitive::Integer64(42),
+                                    ssf::ir::PointerOffset::new(
+                                        ssf::ir::Variable::new("x"),
+                                        ssf::ir::Primitive::Integer64(8),
+                                    ),
+                                    ssf::ir::ArithmeticOperation::new(
+                                        ssf::ir::ArithmeticOperator::Add,
+                                        ssf::ir::Load::new(
+                                            ssf::types::Primitive::Integer64,
+                                            ssf::ir::Variable::new("x"),
+                                        ),
+                                        ssf::ir::Load::new(
+                                            ssf::types::Primitive::Integer64,
+                                            ssf::ir::PointerOffset::new(
+                                                ssf::ir::Variable::new("x"),
+                                                ssf::ir::Primitive::Integer64(8),
+                                            ),
+                                        ),
+                                    ),
+                                ),
+                                ssf::types::Primitive::Integer64,
+                            )],
+                        ),
+                        &Default::default(),
+                        r#"
+                            #include <stdint.h>
+                            #include <stdio.h>
+
+                            int64_t f_foreign(int64_t *);
+
+                            int main() {
+                              int64_t xs[] = {1, 0};
+                              int64_t y = f_foreign(xs);
+
+                              printf("%d %d\n", (int)y, (int)xs[1]);
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "43 42\n"
+                );
+            }
         }
 
         mod constructor_applications {
             use super::*;
 
+            fn compile_constant_list(configuration: &CompileConfiguration) {
+                let list_type = ssf::types::Algebraic::new(vec![
+                    ssf::types::Constructor::boxed(vec![
+                        ssf::types::Primitive::Float64.into(),
+                        ssf::types::Type::Index(0),
+                    ]),
+                    ssf::types::Constructor::boxed(vec![]),
+                ]);
+
+                compile_module_with_configuration(
+                    &ssf::ir::Module::new(
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![ssf::ir::Definition::new(
+                            "f",
+                            vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                            ssf::ir::ConstructorApplication::new(
+                                ssf::ir::Constructor::new(list_type.clone(), 0),
+                                vec![
+                                    ssf::ir::Primitive::Float64(42.0).into(),
+                                    ssf::ir::ConstructorApplication::new(
+                                        ssf::ir::Constructor::new(list_type.clone(), 1),
+                                        vec![],
+                                    )
+                                    .into(),
+                                ],
+                            ),
+                            list_type,
+                        )],
+                    ),
+                    configuration,
+                );
+            }
+
+            fn compile_algebraic(algebraic_type: ssf::types::Algebraic) {
+                compile_module(&ssf::ir::Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![ssf::ir::Definition::new(
+                        "f",
+                        vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                        ssf::ir::ConstructorApplication::new(
+                            ssf::ir::Constructor::new(
+                                algebraic_type.clone(),
+                                *algebraic_type.constructors().keys().last().unwrap(),
+                            ),
+                            vec![ssf::ir::Variable::new("x").into()],
+                        ),
+                        algebraic_type,
+                    )],
+                ));
+            }
+
+            #[test]
+            fn compile_with_32_bit_tag() {
+                compile_algebraic(ssf::types::Algebraic::with_tags(
+                    vec![
+                        (0, ssf::types::Constructor::unboxed(vec![])),
+                        (
+                            1000,
+                            ssf::types::Constructor::unboxed(vec![
+                                ssf::types::Primitive::Float64.into()
+                            ]),
+                        ),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ));
+            }
+
+            #[test]
+            fn compile_with_64_bit_tag() {
+                compile_algebraic(ssf::types::Algebraic::with_tags(
+                    vec![
+                        (0, ssf::types::Constructor::unboxed(vec![])),
+                        (
+                            u64::MAX,
+                            ssf::types::Constructor::unboxed(vec![
+                                ssf::types::Primitive::Float64.into()
+                            ]),
+                        ),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ));
+            }
+
+            #[test]
+            fn compile_ffi_compatible() {
+                compile_algebraic(ssf::types::Algebraic::with_representation(
+                    vec![
+                        (0, ssf::types::Constructor::unboxed(vec![])),
+                        (
+                            1,
+                            ssf::types::Constructor::boxed(vec![
+                                ssf::types::Primitive::Float64.into()
+                            ]),
+                        ),
+                    ]
+                    .into_iter()
+                    .collect(),
+                    ssf::types::Representation::FfiCompatible,
+                ));
+            }
+
+            #[test]
+            fn compile_pointer_tagged() {
+                compile_algebraic(ssf::types::Algebraic::with_representation(
+                    vec![
+                        (0, ssf::types::Constructor::unboxed(vec![])),
+                        (
+                            1,
+                            ssf::types::Constructor::boxed(vec![
+                                ssf::types::Primitive::Float64.into()
+                            ]),
+                        ),
+                        (
+                            2,
+                            ssf::types::Constructor::boxed(vec![
+                                ssf::types::Primitive::Float64.into()
+                            ]),
+                        ),
+                    ]
+                    .into_iter()
+                    .collect(),
+                    ssf::types::Representation::PointerTagged,
+                ));
+            }
+
+            #[test]
+            fn compile_constant() {
+                compile_constant_list(&Default::default());
+            }
+
+            #[test]
+            fn compile_constant_with_reference_counting() {
+                compile_constant_list(&create_reference_counting_configuration());
+            }
+
             #[test]
             fn compile_singleton_enum() {
                 let algebraic_type =
@@ -820,6 +4116,111 @@ mod tests {
         mod function_applications {
             use super::*;
 
+            #[test]
+            fn run_with_apply_functions() {
+                let function_type = ssf::types::Function::new(
+                    ssf::types::Primitive::Float64,
+                    ssf::types::Function::new(
+                        ssf::types::Primitive::Float64,
+                        ssf::types::Primitive::Float64,
+                    ),
+                );
+                let add = |lhs: ssf::ir::Expression, rhs: ssf::ir::Expression| {
+                    ssf::ir::ArithmeticOperation::new(ssf::ir::ArithmeticOperator::Add, lhs, rhs)
+                };
+                let apply = |function: ssf::ir::Expression, x: f64, y: f64| {
+                    ssf::ir::FunctionApplication::new(
+                        ssf::ir::FunctionApplication::new(function, ssf::ir::Primitive::Float64(x)),
+                        ssf::ir::Primitive::Float64(y),
+                    )
+                };
+
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("f", "f_foreign")],
+                            vec![],
+                            vec![
+                                ssf::ir::Definition::new(
+                                    "add2",
+                                    vec![
+                                        ssf::ir::Argument::new("x", ssf::types::Primitive::Float64),
+                                        ssf::ir::Argument::new("y", ssf::types::Primitive::Float64),
+                                    ],
+                                    add(
+                                        ssf::ir::Variable::new("x").into(),
+                                        ssf::ir::Variable::new("y").into(),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                                ssf::ir::Definition::new(
+                                    "add3",
+                                    vec![
+                                        ssf::ir::Argument::new("x", ssf::types::Primitive::Float64),
+                                        ssf::ir::Argument::new("y", ssf::types::Primitive::Float64),
+                                        ssf::ir::Argument::new("z", ssf::types::Primitive::Float64),
+                                    ],
+                                    add(
+                                        ssf::ir::Variable::new("x").into(),
+                                        add(
+                                            ssf::ir::Variable::new("y").into(),
+                                            ssf::ir::Variable::new("z").into(),
+                                        )
+                                        .into(),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                                ssf::ir::Definition::new(
+                                    "apply",
+                                    vec![ssf::ir::Argument::new("h", function_type)],
+                                    add(
+                                        apply(ssf::ir::Variable::new("h").into(), 1.0, 2.0).into(),
+                                        apply(ssf::ir::Variable::new("h").into(), 3.0, 4.0).into(),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                                ssf::ir::Definition::new(
+                                    "f",
+                                    vec![ssf::ir::Argument::new(
+                                        "x",
+                                        ssf::types::Primitive::Float64
+                                    )],
+                                    add(
+                                        ssf::ir::FunctionApplication::new(
+                                            ssf::ir::Variable::new("apply"),
+                                            ssf::ir::FunctionApplication::new(
+                                                ssf::ir::Variable::new("add3"),
+                                                ssf::ir::Variable::new("x"),
+                                            ),
+                                        )
+                                        .into(),
+                                        ssf::ir::FunctionApplication::new(
+                                            ssf::ir::Variable::new("apply"),
+                                            ssf::ir::Variable::new("add2"),
+                                        )
+                                        .into(),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                            ],
+                        ),
+                        &Default::default(),
+                        r#"
+                            #include <stdio.h>
+
+                            double f_foreign(double);
+
+                            int main() {
+                              printf("%g\n", f_foreign(41));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "102\n"
+                );
+            }
+
             #[test]
             fn compile_1_argument() {
                 compile_module(&ssf::ir::Module::new(
@@ -946,6 +4347,70 @@ mod tests {
                 ));
             }
 
+            #[test]
+            fn share_partially_applied_entry_functions() {
+                let create_module = |count, partially_applied: bool| {
+                    ssf::ir::Module::new(
+                        vec![],
+                        vec![],
+                        vec![],
+                        vec![ssf::ir::Definition::new(
+                            "f",
+                            vec![
+                                ssf::ir::Argument::new("x", ssf::types::Primitive::Float64),
+                                ssf::ir::Argument::new("y", ssf::types::Primitive::Float64),
+                            ],
+                            ssf::ir::Variable::new("x"),
+                            ssf::types::Primitive::Float64,
+                        )]
+                        .into_iter()
+                        .chain((0..count).map(|index| {
+                            ssf::ir::Definition::new(
+                                format!("g{index}"),
+                                vec![ssf::ir::Argument::new("x", ssf::types::Primitive::Float64)],
+                                if partially_applied {
+                                    ssf::ir::FunctionApplication::new(
+                                        ssf::ir::Variable::new("f"),
+                                        ssf::ir::Variable::new("x"),
+                                    )
+                                    .into()
+                                } else {
+                                    ssf::ir::Variable::new("f").into()
+                                },
+                                if partially_applied {
+                                    ssf::types::Function::new(
+                                        ssf::types::Primitive::Float64,
+                                        ssf::types::Primitive::Float64,
+                                    )
+                                } else {
+                                    ssf::types::Function::new(
+                                        ssf::types::Primitive::Float64,
+                                        ssf::types::Function::new(
+                                            ssf::types::Primitive::Float64,
+                                            ssf::types::Primitive::Float64,
+                                        ),
+                                    )
+                                },
+                            )
+                        }))
+                        .collect(),
+                    )
+                };
+                let count_functions = |count, partially_applied| {
+                    compile(
+                        &create_module(count, partially_applied),
+                        &Default::default(),
+                    )
+                    .unwrap()
+                    .function_definitions()
+                    .len()
+                };
+
+                assert_eq!(count_functions(1, true), count_functions(1, false) + 1);
+                assert_eq!(count_functions(3, true), count_functions(3, false) + 1);
+                compile_module(&create_module(3, true));
+            }
+
             #[test]
             fn compile_1_argument_with_arity_of_3() {
                 compile_module(&ssf::ir::Module::new(
@@ -1064,6 +4529,81 @@ mod tests {
                     ],
                 ));
             }
+
+            #[test]
+            fn run_with_shadowed_global_function() {
+                let function_type = ssf::types::Function::new(
+                    ssf::types::Primitive::Float64,
+                    ssf::types::Primitive::Float64,
+                );
+
+                assert_eq!(
+                    run_module(
+                        &ssf::ir::Module::new(
+                            vec![],
+                            vec![ssf::ir::ForeignDefinition::new("h", "h_foreign")],
+                            vec![],
+                            vec![
+                                ssf::ir::Definition::new(
+                                    "f",
+                                    vec![ssf::ir::Argument::new(
+                                        "x",
+                                        ssf::types::Primitive::Float64
+                                    )],
+                                    ssf::ir::Variable::new("x"),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                                ssf::ir::Definition::new(
+                                    "double",
+                                    vec![ssf::ir::Argument::new(
+                                        "x",
+                                        ssf::types::Primitive::Float64
+                                    )],
+                                    ssf::ir::ArithmeticOperation::new(
+                                        ssf::ir::ArithmeticOperator::Multiply,
+                                        ssf::ir::Variable::new("x"),
+                                        ssf::ir::Primitive::Float64(2.0),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                                ssf::ir::Definition::new(
+                                    "g",
+                                    vec![ssf::ir::Argument::new("f", function_type)],
+                                    ssf::ir::FunctionApplication::new(
+                                        ssf::ir::Variable::new("f"),
+                                        ssf::ir::Primitive::Float64(42.0),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                                ssf::ir::Definition::new(
+                                    "h",
+                                    vec![ssf::ir::Argument::new(
+                                        "x",
+                                        ssf::types::Primitive::Float64
+                                    )],
+                                    ssf::ir::FunctionApplication::new(
+                                        ssf::ir::Variable::new("g"),
+                                        ssf::ir::Variable::new("double"),
+                                    ),
+                                    ssf::types::Primitive::Float64,
+                                ),
+                            ],
+                        ),
+                        &Default::default(),
+                        r#"
+                            #include <stdio.h>
+
+                            double h_foreign(double);
+
+                            int main() {
+                              printf("%g\n", h_foreign(0));
+                              return 0;
+                            }
+                        "#,
+                    ),
+                    "84\n"
+                );
+            }
         }
     }
 }
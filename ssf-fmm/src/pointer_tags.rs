@@ -0,0 +1,65 @@
+use crate::types;
+
+const POINTER_ALIGNMENT: i64 = 1 << types::POINTER_TAG_BITS;
+
+pub fn compile_tagged_pointer(
+    pointer: impl Into<fmm::build::TypedExpression>,
+    index: usize,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::bit_cast(
+        types::compile_tagged_pointer(),
+        fmm::build::arithmetic_operation(
+            fmm::ir::ArithmeticOperator::Add,
+            fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, pointer),
+            fmm::ir::Primitive::PointerInteger(index as i64),
+        )?,
+    )
+    .into())
+}
+
+pub fn compile_enum(index: usize) -> fmm::build::TypedExpression {
+    fmm::build::bit_cast(
+        types::compile_tagged_pointer(),
+        fmm::ir::Primitive::PointerInteger(index as i64),
+    )
+    .into()
+}
+
+pub fn compile_tag(
+    value: &fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::arithmetic_operation(
+        fmm::ir::ArithmeticOperator::Subtract,
+        fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, value.clone()),
+        compile_untagged_address(value)?,
+    )?
+    .into())
+}
+
+// Untagged pointers of enum constructors are null.
+pub fn compile_untagged_pointer(
+    value: &fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::bit_cast(
+        types::compile_generic_pointer(),
+        compile_untagged_address(value)?,
+    )
+    .into())
+}
+
+// Low bits are cleared by division and multiplication as pointers in user
+// space are positive as signed integers.
+fn compile_untagged_address(
+    value: &fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::arithmetic_operation(
+        fmm::ir::ArithmeticOperator::Multiply,
+        fmm::build::arithmetic_operation(
+            fmm::ir::ArithmeticOperator::Divide,
+            fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, value.clone()),
+            fmm::ir::Primitive::PointerInteger(POINTER_ALIGNMENT),
+        )?,
+        fmm::ir::Primitive::PointerInteger(POINTER_ALIGNMENT),
+    )?
+    .into())
+}
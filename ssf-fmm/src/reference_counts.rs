@@ -0,0 +1,553 @@
+use crate::{arrays, context::Context, pointer_tags, types};
+
+const DROP_FUNCTION_ARGUMENT_NAME: &str = "_object";
+
+type ObjectOperation = fn(
+    &fmm::build::InstructionBuilder,
+    fmm::build::TypedExpression,
+) -> Result<(), fmm::build::BuildError>;
+
+pub fn compile_header_type() -> fmm::types::Record {
+    fmm::types::Record::new(vec![
+        fmm::types::Primitive::PointerInteger.into(),
+        compile_drop_function_type().into(),
+    ])
+}
+
+fn compile_drop_function_type() -> fmm::types::Function {
+    fmm::types::Function::new(
+        vec![types::compile_generic_pointer().into()],
+        fmm::types::Record::new(vec![]),
+        fmm::types::CallingConvention::Target,
+    )
+}
+
+// Objects are placed right after their headers. Their offsets are equal to
+// sizes of headers because no object has an alignment larger than a pointer.
+pub fn compile_object_type(type_: impl Into<fmm::types::Type>) -> fmm::types::Record {
+    fmm::types::Record::new(vec![compile_header_type().into(), type_.into()])
+}
+
+pub fn compile_header(
+    count: i64,
+    drop_function: impl Into<fmm::build::TypedExpression>,
+) -> fmm::build::TypedExpression {
+    fmm::build::record(vec![
+        fmm::ir::Primitive::PointerInteger(count).into(),
+        drop_function.into(),
+    ])
+    .into()
+}
+
+// Static objects have reference counts of zero and are never freed.
+pub fn compile_static_object(
+    context: &Context,
+    content: impl Into<fmm::build::TypedExpression>,
+) -> fmm::build::TypedExpression {
+    let content = content.into();
+
+    if context.configuration().reference_counting {
+        fmm::build::record(vec![
+            compile_header(0, fmm::ir::Undefined::new(compile_drop_function_type())),
+            content,
+        ])
+        .into()
+    } else {
+        content
+    }
+}
+
+pub fn compile_static_object_type(
+    context: &Context,
+    type_: impl Into<fmm::types::Type>,
+) -> fmm::types::Type {
+    if context.configuration().reference_counting {
+        compile_object_type(type_).into()
+    } else {
+        type_.into()
+    }
+}
+
+pub fn compile_static_object_pointer(
+    context: &Context,
+    name: &str,
+    type_: impl Into<fmm::types::Type>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let type_ = type_.into();
+
+    Ok(if context.configuration().reference_counting {
+        fmm::build::record_address(
+            fmm::build::variable(name, fmm::types::Pointer::new(compile_object_type(type_))),
+            1,
+        )?
+    } else {
+        fmm::build::variable(name, fmm::types::Pointer::new(type_))
+    })
+}
+
+pub fn compile_retain(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+) -> Result<(), fmm::build::BuildError> {
+    if context.configuration().reference_counting {
+        compile_value_operation(context, instruction_builder, value, compile_retain_object)?;
+    }
+
+    Ok(())
+}
+
+pub fn compile_release(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+) -> Result<(), fmm::build::BuildError> {
+    if context.configuration().reference_counting {
+        compile_value_operation(context, instruction_builder, value, compile_release_object)?;
+    }
+
+    Ok(())
+}
+
+fn compile_value_operation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+    operation: ObjectOperation,
+) -> Result<(), fmm::build::BuildError> {
+    if types::is_pointer_free(value.type_()) {
+        return Ok(());
+    }
+
+    match value.type_() {
+        fmm::types::Type::Pointer(pointer) => {
+            if pointer == &types::compile_tagged_pointer() {
+                compile_tagged_pointer_operation(instruction_builder, value, operation)?;
+            } else if pointer != &types::compile_generic_pointer() {
+                // Generic pointers come from foreign code and are not managed.
+                operation(instruction_builder, value.clone())?;
+            }
+        }
+        fmm::types::Type::Record(record) => {
+            if let Some(fmm::types::Type::Union(union)) = record.fields().last() {
+                compile_algebraic_operation(
+                    context,
+                    instruction_builder,
+                    value,
+                    record,
+                    union,
+                    operation,
+                )?;
+            } else {
+                for index in 0..record.fields().len() {
+                    compile_value_operation(
+                        context,
+                        instruction_builder,
+                        &instruction_builder.deconstruct_record(value.clone(), index)?,
+                        operation,
+                    )?;
+                }
+            }
+        }
+        fmm::types::Type::Function(_)
+        | fmm::types::Type::Primitive(_)
+        | fmm::types::Type::Union(_) => {}
+    }
+
+    Ok(())
+}
+
+// Records ending with unions are algebraic data types because unions are used
+// only for them.
+fn compile_algebraic_operation(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+    record: &fmm::types::Record,
+    union: &fmm::types::Union,
+    operation: ObjectOperation,
+) -> Result<(), fmm::build::BuildError> {
+    let payload =
+        instruction_builder.deconstruct_record(value.clone(), record.fields().len() - 1)?;
+
+    // Untagged unions have single members except the ones of null pointer
+    // niches.
+    if record.fields().len() == 1 {
+        for (index, member) in union.members().iter().enumerate() {
+            if types::is_pointer_free(member) {
+                continue;
+            }
+
+            let member = instruction_builder.deconstruct_union(payload.clone(), index)?;
+
+            if union.members().len() == 1 {
+                compile_value_operation(context, instruction_builder, &member, operation)?;
+            } else {
+                instruction_builder.if_(
+                    fmm::build::comparison_operation(
+                        fmm::ir::ComparisonOperator::Equal,
+                        fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, member.clone()),
+                        fmm::ir::Primitive::PointerInteger(0),
+                    )?,
+                    |instruction_builder| {
+                        Ok(instruction_builder.branch(fmm::build::record(vec![])))
+                    },
+                    |instruction_builder| {
+                        compile_value_operation(
+                            context,
+                            &instruction_builder,
+                            &member,
+                            operation,
+                        )?;
+
+                        Ok(instruction_builder.branch(fmm::build::record(vec![])))
+                    },
+                )?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    let tag = instruction_builder.deconstruct_record(value.clone(), 0)?;
+    let tag_type = match &record.fields()[0] {
+        fmm::types::Type::Primitive(primitive) => *primitive,
+        _ => unreachable!(),
+    };
+
+    for (&tag_value, &index) in context.find_algebraic_tags(record).unwrap() {
+        if types::is_pointer_free(&union.members()[index]) {
+            continue;
+        }
+
+        instruction_builder.if_(
+            fmm::build::comparison_operation(
+                fmm::ir::ComparisonOperator::Equal,
+                tag.clone(),
+                types::compile_tag_value(tag_type, tag_value),
+            )?,
+            |instruction_builder| {
+                compile_value_operation(
+                    context,
+                    &instruction_builder,
+                    &instruction_builder.deconstruct_union(payload.clone(), index)?,
+                    operation,
+                )?;
+
+                Ok(instruction_builder.branch(fmm::build::record(vec![])))
+            },
+            |instruction_builder| Ok(instruction_builder.branch(fmm::build::record(vec![]))),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn compile_tagged_pointer_operation(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+    operation: ObjectOperation,
+) -> Result<(), fmm::build::BuildError> {
+    let pointer = pointer_tags::compile_untagged_pointer(value)?;
+
+    instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::Equal,
+            fmm::build::bit_cast(fmm::types::Primitive::PointerInteger, pointer.clone()),
+            fmm::ir::Primitive::PointerInteger(0),
+        )?,
+        |instruction_builder| Ok(instruction_builder.branch(fmm::build::record(vec![]))),
+        |instruction_builder| {
+            operation(&instruction_builder, pointer.clone())?;
+
+            Ok(instruction_builder.branch(fmm::build::record(vec![])))
+        },
+    )?;
+
+    Ok(())
+}
+
+fn compile_header_pointer(
+    pointer: fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(fmm::build::bit_cast(
+        fmm::types::Pointer::new(compile_header_type()),
+        fmm::build::pointer_address(
+            fmm::build::bit_cast(types::compile_generic_pointer(), pointer),
+            fmm::build::arithmetic_operation(
+                fmm::ir::ArithmeticOperator::Subtract,
+                fmm::ir::Primitive::PointerInteger(0),
+                fmm::build::size_of(compile_header_type()),
+            )?,
+        )?,
+    )
+    .into())
+}
+
+fn compile_retain_object(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    pointer: fmm::build::TypedExpression,
+) -> Result<(), fmm::build::BuildError> {
+    let count_pointer = fmm::build::record_address(compile_header_pointer(pointer)?, 0)?;
+    let count = instruction_builder.load(count_pointer.clone())?;
+
+    instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::Equal,
+            count.clone(),
+            fmm::ir::Primitive::PointerInteger(0),
+        )?,
+        |instruction_builder| Ok(instruction_builder.branch(fmm::build::record(vec![]))),
+        |instruction_builder| {
+            instruction_builder.store(
+                fmm::build::arithmetic_operation(
+                    fmm::ir::ArithmeticOperator::Add,
+                    count.clone(),
+                    fmm::ir::Primitive::PointerInteger(1),
+                )?,
+                count_pointer.clone(),
+            );
+
+            Ok(instruction_builder.branch(fmm::build::record(vec![])))
+        },
+    )?;
+
+    Ok(())
+}
+
+fn compile_release_object(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    pointer: fmm::build::TypedExpression,
+) -> Result<(), fmm::build::BuildError> {
+    let header_pointer = compile_header_pointer(pointer.clone())?;
+    let count_pointer = fmm::build::record_address(header_pointer.clone(), 0)?;
+    let count = instruction_builder.load(count_pointer.clone())?;
+
+    instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::Equal,
+            count.clone(),
+            fmm::ir::Primitive::PointerInteger(1),
+        )?,
+        |instruction_builder| {
+            instruction_builder.call(
+                instruction_builder.load(fmm::build::record_address(header_pointer.clone(), 1)?)?,
+                vec![
+                    fmm::build::bit_cast(types::compile_generic_pointer(), pointer.clone()).into(),
+                ],
+            )?;
+            instruction_builder.free_heap(fmm::build::bit_cast(
+                types::compile_generic_pointer(),
+                header_pointer.clone(),
+            ));
+
+            Ok(instruction_builder.branch(fmm::build::record(vec![])))
+        },
+        |instruction_builder| {
+            instruction_builder.if_(
+                fmm::build::comparison_operation(
+                    fmm::ir::ComparisonOperator::Equal,
+                    count.clone(),
+                    fmm::ir::Primitive::PointerInteger(0),
+                )?,
+                |instruction_builder| Ok(instruction_builder.branch(fmm::build::record(vec![]))),
+                |instruction_builder| {
+                    instruction_builder.store(
+                        fmm::build::arithmetic_operation(
+                            fmm::ir::ArithmeticOperator::Subtract,
+                            count.clone(),
+                            fmm::ir::Primitive::PointerInteger(1),
+                        )?,
+                        count_pointer.clone(),
+                    );
+
+                    Ok(instruction_builder.branch(fmm::build::record(vec![])))
+                },
+            )?;
+
+            Ok(instruction_builder.branch(fmm::build::record(vec![])))
+        },
+    )?;
+
+    Ok(())
+}
+
+// Unique objects are reused without being dropped while the others are
+// released. Null pointers are returned if objects are not reusable.
+pub fn compile_reuse_pointer(
+    instruction_builder: &fmm::build::InstructionBuilder,
+    pointer: fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let count = instruction_builder.load(fmm::build::record_address(
+        compile_header_pointer(pointer.clone())?,
+        0,
+    )?)?;
+
+    instruction_builder.if_(
+        fmm::build::comparison_operation(
+            fmm::ir::ComparisonOperator::Equal,
+            count,
+            fmm::ir::Primitive::PointerInteger(1),
+        )?,
+        |instruction_builder| {
+            Ok(instruction_builder.branch(fmm::build::bit_cast(
+                types::compile_generic_pointer(),
+                pointer.clone(),
+            )))
+        },
+        |instruction_builder| {
+            compile_release_object(&instruction_builder, pointer.clone())?;
+
+            Ok(instruction_builder.branch(fmm::build::bit_cast(
+                types::compile_generic_pointer(),
+                fmm::ir::Primitive::PointerInteger(0),
+            )))
+        },
+    )
+}
+
+pub fn compile_drop_function(
+    context: &Context,
+    type_: &fmm::types::Type,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    if let Some(drop_function) = context.find_drop_function(type_) {
+        return Ok(drop_function);
+    }
+
+    let drop_function = compile_raw_drop_function(context, |instruction_builder, pointer| {
+        compile_release(
+            context,
+            instruction_builder,
+            &instruction_builder.load(fmm::build::bit_cast(
+                fmm::types::Pointer::new(type_.clone()),
+                pointer,
+            ))?,
+        )
+    })?;
+
+    context.add_drop_function(type_.clone(), drop_function.clone());
+
+    Ok(drop_function)
+}
+
+// Array drop functions release all elements of arrays. They are shared by
+// arrays of the same element types.
+pub fn compile_array_drop_function(
+    context: &Context,
+    element_type: &fmm::types::Type,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    if let Some(drop_function) = context.find_array_drop_function(element_type) {
+        return Ok(drop_function);
+    }
+
+    let drop_function = compile_raw_drop_function(context, |instruction_builder, pointer| {
+        if types::is_pointer_free(element_type) {
+            return Ok(());
+        }
+
+        arrays::compile_loop(
+            context,
+            instruction_builder,
+            arrays::compile_elements_pointer(pointer.clone(), element_type)?,
+            instruction_builder.load(arrays::compile_length_pointer(pointer)?)?,
+            |instruction_builder, index, elements_pointer| {
+                compile_release(
+                    context,
+                    instruction_builder,
+                    &instruction_builder
+                        .load(fmm::build::pointer_address(elements_pointer, index)?)?,
+                )
+            },
+        )
+    })?;
+
+    context.add_array_drop_function(element_type.clone(), drop_function.clone());
+
+    Ok(drop_function)
+}
+
+// Thunks contain their environments before evaluation and their values after
+// it.
+pub fn compile_thunk_drop_function(
+    context: &Context,
+    definition: &ssf::ir::Definition,
+    entry_function: fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    compile_raw_drop_function(context, |instruction_builder, pointer| {
+        let closure_pointer = fmm::build::bit_cast(
+            fmm::types::Pointer::new(types::compile_sized_closure(definition)),
+            pointer,
+        );
+        let payload_pointer = fmm::build::record_address(closure_pointer.clone(), 2)?;
+        let compile_release_payload =
+            |instruction_builder: fmm::build::InstructionBuilder, type_: fmm::types::Type| {
+                compile_release(
+                    context,
+                    &instruction_builder,
+                    &instruction_builder.load(fmm::build::bit_cast(
+                        fmm::types::Pointer::new(type_),
+                        payload_pointer.clone(),
+                    ))?,
+                )?;
+
+                Ok(instruction_builder.branch(fmm::build::record(vec![])))
+            };
+
+        instruction_builder.if_(
+            fmm::build::comparison_operation(
+                fmm::ir::ComparisonOperator::Equal,
+                fmm::build::bit_cast(
+                    fmm::types::Primitive::PointerInteger,
+                    instruction_builder.load(fmm::build::record_address(closure_pointer, 0)?)?,
+                ),
+                fmm::build::bit_cast(
+                    fmm::types::Primitive::PointerInteger,
+                    entry_function.clone(),
+                ),
+            )?,
+            |instruction_builder| {
+                compile_release_payload(
+                    instruction_builder,
+                    types::compile_environment(definition).into(),
+                )
+            },
+            |instruction_builder| {
+                compile_release_payload(
+                    instruction_builder,
+                    types::compile(definition.result_type()),
+                )
+            },
+        )?;
+
+        Ok(())
+    })
+}
+
+fn compile_raw_drop_function(
+    context: &Context,
+    compile_body: impl Fn(
+        &fmm::build::InstructionBuilder,
+        fmm::build::TypedExpression,
+    ) -> Result<(), fmm::build::BuildError>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    context.module_builder().define_anonymous_function(
+        vec![fmm::ir::Argument::new(
+            DROP_FUNCTION_ARGUMENT_NAME,
+            types::compile_generic_pointer(),
+        )],
+        fmm::types::Record::new(vec![]),
+        |instruction_builder| {
+            compile_body(
+                &instruction_builder,
+                fmm::build::variable(
+                    DROP_FUNCTION_ARGUMENT_NAME,
+                    types::compile_generic_pointer(),
+                ),
+            )?;
+
+            Ok(instruction_builder.return_(fmm::build::record(vec![])))
+        },
+        fmm::ir::FunctionDefinitionOptions::new()
+            .set_calling_convention(fmm::types::CallingConvention::Target),
+    )
+}
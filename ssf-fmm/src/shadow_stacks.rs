@@ -0,0 +1,135 @@
+use crate::{context::Context, layout_descriptors, types};
+
+pub fn compile_save(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+) -> Result<Option<fmm::build::TypedExpression>, fmm::build::BuildError> {
+    Ok(
+        if let Some(functions) = &context.configuration().shadow_stack {
+            Some(instruction_builder.call(
+                context.module_builder().declare_function(
+                    &functions.save,
+                    fmm::types::Function::new(
+                        vec![],
+                        fmm::types::Primitive::PointerInteger,
+                        fmm::types::CallingConvention::Target,
+                    ),
+                ),
+                vec![],
+            )?)
+        } else {
+            None
+        },
+    )
+}
+
+pub fn compile_restore(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    height: Option<fmm::build::TypedExpression>,
+) -> Result<(), fmm::build::BuildError> {
+    if let (Some(functions), Some(height)) = (&context.configuration().shadow_stack, height) {
+        instruction_builder.call(
+            context.module_builder().declare_function(
+                &functions.restore,
+                fmm::types::Function::new(
+                    vec![fmm::types::Primitive::PointerInteger.into()],
+                    fmm::types::Record::new(vec![]),
+                    fmm::types::CallingConvention::Target,
+                ),
+            ),
+            vec![height],
+        )?;
+    }
+
+    Ok(())
+}
+
+// Values pushed in blocks are popped at their ends as their slots are not
+// alive anymore.
+pub fn compile_block(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    compile: impl FnOnce() -> Result<fmm::build::TypedExpression, fmm::build::BuildError>,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    let height = compile_save(context, instruction_builder)?;
+    let value = compile()?;
+
+    compile_restore(context, instruction_builder, height)?;
+
+    Ok(value)
+}
+
+// Values pushed to shadow stacks are replaced with the ones loaded from their
+// slots. Their uses after allocations must reload them with `compile_load`.
+pub fn compile_push(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(match &context.configuration().shadow_stack {
+        Some(functions) if !types::is_pointer_free(value.type_()) => {
+            let slot = instruction_builder.allocate_stack(value.type_().clone());
+            instruction_builder.store(value.clone(), slot.clone());
+
+            instruction_builder.call(
+                context.module_builder().declare_function(
+                    &functions.push,
+                    fmm::types::Function::new(
+                        vec![
+                            types::compile_generic_pointer().into(),
+                            types::compile_generic_pointer().into(),
+                        ],
+                        fmm::types::Record::new(vec![]),
+                        fmm::types::CallingConvention::Target,
+                    ),
+                ),
+                vec![
+                    fmm::build::bit_cast(types::compile_generic_pointer(), slot.clone()).into(),
+                    layout_descriptors::compile_layout_descriptor(context, value.type_())?,
+                ],
+            )?;
+
+            let value = instruction_builder.load(slot.clone())?;
+
+            context.add_shadow_stack_slot(value.clone(), slot);
+
+            value
+        }
+        _ => value.clone(),
+    })
+}
+
+pub fn compile_pushes(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    values: &[fmm::build::TypedExpression],
+) -> Result<Vec<fmm::build::TypedExpression>, fmm::build::BuildError> {
+    values
+        .iter()
+        .map(|value| compile_push(context, instruction_builder, value))
+        .collect()
+}
+
+pub fn compile_load(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    value: &fmm::build::TypedExpression,
+) -> Result<fmm::build::TypedExpression, fmm::build::BuildError> {
+    Ok(if let Some(slot) = context.find_shadow_stack_slot(value) {
+        instruction_builder.load(slot)?
+    } else {
+        value.clone()
+    })
+}
+
+pub fn compile_loads(
+    context: &Context,
+    instruction_builder: &fmm::build::InstructionBuilder,
+    values: &[fmm::build::TypedExpression],
+) -> Result<Vec<fmm::build::TypedExpression>, fmm::build::BuildError> {
+    values
+        .iter()
+        .map(|value| compile_load(context, instruction_builder, value))
+        .collect()
+}
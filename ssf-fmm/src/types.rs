@@ -1,21 +1,52 @@
 pub const FUNCTION_ARGUMENT_OFFSET: usize = 1;
 
+// Pointer tags are stored in low bits of pointers to payloads aligned to words
+// on 64-bit targets.
+pub const POINTER_TAG_BITS: usize = 3;
+pub const WORD_SIZE: usize = 8;
+
 pub fn compile_generic_pointer() -> fmm::types::Pointer {
     fmm::types::Pointer::new(fmm::types::Primitive::Integer8)
 }
 
+pub fn is_pointer_free(type_: &fmm::types::Type) -> bool {
+    match type_ {
+        fmm::types::Type::Primitive(_) => true,
+        fmm::types::Type::Record(record) => record.fields().iter().all(is_pointer_free),
+        fmm::types::Type::Union(union) => union.members().iter().all(is_pointer_free),
+        fmm::types::Type::Function(_) | fmm::types::Type::Pointer(_) => false,
+    }
+}
+
 pub fn get_arity(type_: &fmm::types::Function) -> usize {
     type_.arguments().len() - FUNCTION_ARGUMENT_OFFSET
 }
 
 pub fn compile(type_: &ssf::types::Type) -> fmm::types::Type {
     match type_ {
-        ssf::types::Type::Algebraic(algebraic) => compile_algebraic(algebraic).into(),
+        ssf::types::Type::Algebraic(algebraic) => {
+            if is_pointer_tagged(algebraic) {
+                compile_tagged_pointer().into()
+            } else {
+                compile_algebraic(algebraic).into()
+            }
+        }
+        ssf::types::Type::Array(_) => compile_array().into(),
         ssf::types::Type::Function(function) => {
             fmm::types::Pointer::new(compile_unsized_closure(function)).into()
         }
         ssf::types::Type::Index(_) => unreachable!(),
+        ssf::types::Type::Lazy(lazy) => {
+            fmm::types::Pointer::new(compile_unsized_closure(&lazy.to_function())).into()
+        }
         ssf::types::Type::Primitive(primitive) => compile_primitive(primitive),
+        ssf::types::Type::Record(record) => {
+            if record.is_boxed() {
+                compile_shallow_boxed_record().into()
+            } else {
+                compile_unboxed_record(record).into()
+            }
+        }
     }
 }
 
@@ -33,14 +64,134 @@ pub fn compile_primitive(primitive: &ssf::types::Primitive) -> fmm::types::Type
 
 pub fn compile_algebraic(algebraic: &ssf::types::Algebraic) -> fmm::types::Record {
     fmm::types::Record::new(
-        (!algebraic.is_singleton())
-            .then(|| compile_tag().into())
+        is_tagged(algebraic)
+            .then(|| compile_tag(algebraic).into())
             .into_iter()
             .chain((!algebraic.is_enum()).then(|| compile_constructor_union(algebraic).into()))
             .collect(),
     )
 }
 
+pub fn is_tagged(algebraic: &ssf::types::Algebraic) -> bool {
+    !algebraic.is_singleton()
+        && !is_pointer_tagged(algebraic)
+        && get_null_pointer_niche(algebraic).is_none()
+}
+
+// Values of pointer-tagged types are pointers to payloads of boxed
+// constructors plus indices of the constructors, or the indices alone for
+// enum constructors. The indices fit in low bits of the pointers because
+// all payloads are aligned to words.
+pub fn is_pointer_tagged(algebraic: &ssf::types::Algebraic) -> bool {
+    algebraic.representation() == ssf::types::Representation::PointerTagged
+        && !algebraic.is_singleton()
+        && algebraic.constructors().len() <= 1 << POINTER_TAG_BITS
+        && algebraic
+            .constructors()
+            .values()
+            .any(|constructor| constructor.is_boxed())
+        && algebraic.constructors().values().all(|constructor| {
+            constructor.is_enum()
+                || constructor.is_boxed()
+                    && constructor
+                        .elements()
+                        .iter()
+                        .map(get_alignment)
+                        .max()
+                        .unwrap_or(1)
+                        >= WORD_SIZE
+        })
+}
+
+// Pointer-tagged values have their own pointer type so that they are not
+// confused with untagged pointers.
+pub fn compile_tagged_pointer() -> fmm::types::Pointer {
+    fmm::types::Pointer::new(fmm::types::Primitive::PointerInteger)
+}
+
+// Alignments are computed from ssf types rather than fmm ones to avoid
+// infinite recursion on recursive types. Algebraic data types containing
+// themselves are always aligned to words because they have boxed
+// constructors somewhere inside.
+fn get_alignment(type_: &ssf::types::Type) -> usize {
+    match type_ {
+        ssf::types::Type::Algebraic(algebraic) => {
+            if algebraic
+                .constructors()
+                .values()
+                .any(|constructor| constructor.is_boxed())
+            {
+                WORD_SIZE
+            } else {
+                algebraic
+                    .constructors()
+                    .values()
+                    .flat_map(|constructor| constructor.elements())
+                    .map(get_alignment)
+                    .chain(
+                        (!algebraic.is_singleton())
+                            .then(|| get_primitive_size(compile_tag(algebraic))),
+                    )
+                    .max()
+                    .unwrap_or(1)
+            }
+        }
+        ssf::types::Type::Array(_)
+        | ssf::types::Type::Function(_)
+        | ssf::types::Type::Index(_)
+        | ssf::types::Type::Lazy(_) => WORD_SIZE,
+        ssf::types::Type::Primitive(primitive) => match compile_primitive(primitive) {
+            fmm::types::Type::Primitive(primitive) => get_primitive_size(primitive),
+            _ => WORD_SIZE,
+        },
+        ssf::types::Type::Record(record) => {
+            if record.is_boxed() {
+                WORD_SIZE
+            } else {
+                record
+                    .elements()
+                    .iter()
+                    .map(get_alignment)
+                    .max()
+                    .unwrap_or(1)
+            }
+        }
+    }
+}
+
+// Primitives are aligned to their sizes on 64-bit targets.
+pub fn get_primitive_size(primitive: fmm::types::Primitive) -> usize {
+    match primitive {
+        fmm::types::Primitive::Boolean | fmm::types::Primitive::Integer8 => 1,
+        fmm::types::Primitive::Float32 | fmm::types::Primitive::Integer32 => 4,
+        fmm::types::Primitive::Float64
+        | fmm::types::Primitive::Integer64
+        | fmm::types::Primitive::PointerInteger => WORD_SIZE,
+    }
+}
+
+// Algebraic types of an enum constructor and a boxed one represent the enum
+// constructor as a null pointer of the boxed one instead of tags. This
+// returns tags of the enum and boxed constructors.
+pub fn get_null_pointer_niche(algebraic: &ssf::types::Algebraic) -> Option<(u64, u64)> {
+    if algebraic.is_ffi_compatible() || is_pointer_tagged(algebraic) {
+        return None;
+    }
+
+    match algebraic.constructors().iter().collect::<Vec<_>>()[..] {
+        [(tag, constructor), (other_tag, other_constructor)] => {
+            if constructor.is_enum() && other_constructor.is_boxed() {
+                Some((*tag, *other_tag))
+            } else if constructor.is_boxed() && other_constructor.is_enum() {
+                Some((*other_tag, *tag))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
 pub fn compile_constructor_union(algebraic_type: &ssf::types::Algebraic) -> fmm::types::Union {
     fmm::types::Union::new(
         algebraic_type
@@ -67,6 +218,31 @@ pub fn compile_unboxed_constructor(constructor: &ssf::types::Constructor) -> fmm
     fmm::types::Record::new(constructor.elements().iter().map(compile).collect())
 }
 
+// Arrays are pointers to their lengths followed by their elements. Their
+// element types are not included so that recursive types containing them are
+// finite.
+pub fn compile_array() -> fmm::types::Pointer {
+    fmm::types::Pointer::new(compile_array_header())
+}
+
+pub fn compile_array_header() -> fmm::types::Record {
+    fmm::types::Record::new(vec![fmm::types::Primitive::PointerInteger.into()])
+}
+
+// Boxed records are compiled into pointers to empty records like boxed
+// constructors so that recursive types containing them are finite.
+fn compile_shallow_boxed_record() -> fmm::types::Pointer {
+    fmm::types::Pointer::new(fmm::types::Record::new(vec![]))
+}
+
+pub fn compile_boxed_record(record: &ssf::types::Record) -> fmm::types::Pointer {
+    fmm::types::Pointer::new(compile_unboxed_record(record))
+}
+
+pub fn compile_unboxed_record(record: &ssf::types::Record) -> fmm::types::Record {
+    fmm::types::Record::new(record.elements().iter().map(compile).collect())
+}
+
 pub fn get_constructor_union_index(algebraic_type: &ssf::types::Algebraic, tag: u64) -> usize {
     algebraic_type
         .constructors()
@@ -206,8 +382,44 @@ fn compile_calling_convention(
     }
 }
 
-pub fn compile_tag() -> fmm::types::Primitive {
-    fmm::types::Primitive::PointerInteger
+// Tags are the smallest integers which can hold the maximum tags unless types
+// are FFI-compatible or pointer-tagged.
+pub fn compile_tag(algebraic: &ssf::types::Algebraic) -> fmm::types::Primitive {
+    let max_tag = algebraic.constructors().keys().max().copied().unwrap_or(0);
+
+    if algebraic.is_ffi_compatible() || is_pointer_tagged(algebraic) {
+        fmm::types::Primitive::PointerInteger
+    } else if max_tag <= u8::MAX.into() {
+        fmm::types::Primitive::Integer8
+    } else if max_tag <= u32::MAX.into() {
+        fmm::types::Primitive::Integer32
+    } else {
+        fmm::types::Primitive::Integer64
+    }
+}
+
+// Pointer tags are indices of constructors instead of their tags.
+pub fn compile_constructor_tag(algebraic: &ssf::types::Algebraic, tag: u64) -> fmm::ir::Primitive {
+    compile_tag_value(
+        compile_tag(algebraic),
+        if is_pointer_tagged(algebraic) {
+            get_constructor_union_index(algebraic, tag) as u64
+        } else {
+            tag
+        },
+    )
+}
+
+pub fn compile_tag_value(tag_type: fmm::types::Primitive, tag: u64) -> fmm::ir::Primitive {
+    match tag_type {
+        fmm::types::Primitive::Integer8 => fmm::ir::Primitive::Integer8(tag as u8),
+        fmm::types::Primitive::Integer32 => fmm::ir::Primitive::Integer32(tag as u32),
+        fmm::types::Primitive::Integer64 => fmm::ir::Primitive::Integer64(tag),
+        fmm::types::Primitive::PointerInteger => fmm::ir::Primitive::PointerInteger(tag as i64),
+        fmm::types::Primitive::Boolean
+        | fmm::types::Primitive::Float32
+        | fmm::types::Primitive::Float64 => unreachable!(),
+    }
 }
 
 pub fn compile_arity() -> fmm::types::Primitive {
@@ -1,3 +1,7 @@
+mod escape_analysis;
+mod thunk_elimination;
 mod type_check;
 
+pub use escape_analysis::*;
+pub use thunk_elimination::*;
 pub use type_check::*;
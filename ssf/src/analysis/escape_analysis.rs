@@ -0,0 +1,360 @@
+use crate::{ir::*, types::Type};
+use std::collections::HashMap;
+
+/// Checks if a value bound by a let expression escapes from its scope.
+///
+/// Values escape if they are returned, passed to functions or constructors,
+/// or captured by closures.
+pub fn is_let_escaping(let_: &Let) -> bool {
+    let arity = match let_.type_() {
+        Type::Function(function) => Some(function.arguments().into_iter().count()),
+        _ => None,
+    };
+
+    is_escaping(
+        &vec![(let_.name().into(), arity)].into_iter().collect(),
+        let_.expression(),
+    )
+}
+
+/// Checks if any closure defined by a recursive let expression escapes from
+/// its scope.
+///
+/// Closures can be referenced by each other in their bodies as long as they
+/// are only called with enough arguments there.
+pub fn is_let_recursive_escaping(let_: &LetRecursive) -> bool {
+    let variables = let_
+        .definitions()
+        .iter()
+        .map(|definition| (definition.name().into(), Some(definition.arguments().len())))
+        .collect::<HashMap<_, _>>();
+
+    let_.definitions()
+        .iter()
+        .any(|definition| is_escaping_in_definition(&variables, definition))
+        || is_escaping(&variables, let_.expression())
+}
+
+// Variables are mapped to arities of their closures if they are known.
+// Otherwise, any application of them can create partially-applied closures.
+fn is_escaping(variables: &HashMap<String, Option<usize>>, expression: &Expression) -> bool {
+    let is_escaping = |expression| is_escaping(variables, expression);
+
+    match expression {
+        Expression::ArithmeticOperation(operation) => {
+            is_escaping(operation.lhs()) || is_escaping(operation.rhs())
+        }
+        Expression::Array(array) => array.elements().iter().any(is_escaping),
+        Expression::ArrayElement(element) => {
+            (!is_variable(variables, element.array()) && is_escaping(element.array()))
+                || is_escaping(element.index())
+                || is_escaping(element.default_element())
+        }
+        Expression::ArrayInitialization(initialization) => {
+            is_escaping(initialization.length()) || is_escaping(initialization.function())
+        }
+        Expression::ArrayLength(length) => {
+            !is_variable(variables, length.array()) && is_escaping(length.array())
+        }
+        Expression::BitCast(bit_cast) => is_escaping(bit_cast.expression()),
+        Expression::Case(Case::Algebraic(case)) => {
+            (!is_variable(variables, case.argument()) && is_escaping(case.argument()))
+                || case.alternatives().iter().any(|alternative| {
+                    let mut variables = variables.clone();
+
+                    for name in alternative.element_names() {
+                        variables.remove(name);
+                    }
+
+                    self::is_escaping(&variables, alternative.expression())
+                })
+                || case.default_alternative().map(is_escaping).unwrap_or(false)
+        }
+        Expression::Case(Case::Primitive(case)) => {
+            is_escaping(case.argument())
+                || case
+                    .alternatives()
+                    .iter()
+                    .any(|alternative| is_escaping(alternative.expression()))
+                || case.default_alternative().map(is_escaping).unwrap_or(false)
+        }
+        Expression::ComparisonOperation(operation) => {
+            is_escaping(operation.lhs()) || is_escaping(operation.rhs())
+        }
+        Expression::ConstructorApplication(application) => {
+            application.arguments().iter().any(is_escaping)
+        }
+        Expression::ConstructorField(field) => {
+            !is_variable(variables, field.argument()) && is_escaping(field.argument())
+        }
+        Expression::Delay(delay) => is_captured(variables, delay.definition()),
+        Expression::Force(force) => {
+            !is_variable(variables, force.expression()) && is_escaping(force.expression())
+        }
+        Expression::FunctionApplication(application) => {
+            let arguments = application.arguments().into_iter().collect::<Vec<_>>();
+
+            (!is_called(variables, application.first_function(), arguments.len())
+                && is_escaping(application.first_function()))
+                || arguments.into_iter().any(is_escaping)
+        }
+        Expression::Lambda(lambda) => is_captured(variables, lambda.definition()),
+        Expression::Let(let_) => {
+            is_escaping(let_.bound_expression()) || {
+                let mut variables = variables.clone();
+
+                variables.remove(let_.name());
+
+                self::is_escaping(&variables, let_.expression())
+            }
+        }
+        Expression::LetRecursive(let_) => {
+            let mut variables = variables.clone();
+
+            for definition in let_.definitions() {
+                variables.remove(definition.name());
+            }
+
+            let_.definitions()
+                .iter()
+                .any(|definition| is_captured(&variables, definition))
+                || self::is_escaping(&variables, let_.expression())
+        }
+        Expression::Load(load) => is_escaping(load.pointer()),
+        Expression::PointerOffset(offset) => {
+            is_escaping(offset.pointer()) || is_escaping(offset.offset())
+        }
+        Expression::ByteString(_) | Expression::Primitive(_) => false,
+        Expression::Record(record) => record.elements().iter().any(is_escaping),
+        Expression::RecordElement(element) => {
+            !is_variable(variables, element.record()) && is_escaping(element.record())
+        }
+        Expression::RecordUpdate(update) => {
+            (!is_variable(variables, update.record()) && is_escaping(update.record()))
+                || update
+                    .elements()
+                    .iter()
+                    .any(|(_, element)| is_escaping(element))
+        }
+        Expression::Store(store) => {
+            is_escaping(store.value())
+                || is_escaping(store.pointer())
+                || is_escaping(store.expression())
+        }
+        Expression::Variable(variable) => variables.contains_key(variable.name()),
+    }
+}
+
+fn is_escaping_in_definition(
+    variables: &HashMap<String, Option<usize>>,
+    definition: &Definition,
+) -> bool {
+    let mut variables = variables.clone();
+
+    for argument in definition.arguments() {
+        variables.remove(argument.name());
+    }
+
+    is_escaping(&variables, definition.body())
+}
+
+fn is_captured(variables: &HashMap<String, Option<usize>>, definition: &Definition) -> bool {
+    definition
+        .find_variables()
+        .iter()
+        .any(|name| variables.contains_key(name))
+}
+
+fn is_variable(variables: &HashMap<String, Option<usize>>, expression: &Expression) -> bool {
+    matches!(expression, Expression::Variable(variable) if variables.contains_key(variable.name()))
+}
+
+fn is_called(
+    variables: &HashMap<String, Option<usize>>,
+    expression: &Expression,
+    argument_count: usize,
+) -> bool {
+    matches!(
+        expression,
+        Expression::Variable(variable)
+            if matches!(variables.get(variable.name()), Some(Some(arity)) if *arity <= argument_count)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types;
+
+    fn create_let(expression: impl Into<Expression>) -> Let {
+        Let::new(
+            "x",
+            types::Primitive::Float64,
+            Primitive::Float64(42.0),
+            expression,
+        )
+    }
+
+    fn create_let_recursive(
+        body: impl Into<Expression>,
+        expression: impl Into<Expression>,
+    ) -> LetRecursive {
+        LetRecursive::new(
+            vec![Definition::new(
+                "f",
+                vec![Argument::new("x", types::Primitive::Float64)],
+                body,
+                types::Primitive::Float64,
+            )],
+            expression,
+        )
+    }
+
+    #[test]
+    fn check_returned_variable() {
+        assert!(is_let_escaping(&create_let(Variable::new("x"))));
+    }
+
+    #[test]
+    fn check_unused_variable() {
+        assert!(!is_let_escaping(&create_let(Primitive::Float64(42.0))));
+    }
+
+    #[test]
+    fn check_variable_in_case_argument() {
+        let algebraic_type = types::Algebraic::new(vec![types::Constructor::boxed(vec![
+            types::Primitive::Float64.into(),
+        ])]);
+
+        assert!(!is_let_escaping(&create_let(AlgebraicCase::new(
+            Variable::new("x"),
+            vec![AlgebraicAlternative::new(
+                Constructor::new(algebraic_type, 0),
+                vec!["y".into()],
+                Variable::new("y"),
+            )],
+            None,
+        ))));
+    }
+
+    #[test]
+    fn check_variable_in_constructor_application() {
+        let algebraic_type = types::Algebraic::new(vec![types::Constructor::boxed(vec![
+            types::Primitive::Float64.into(),
+        ])]);
+
+        assert!(is_let_escaping(&create_let(ConstructorApplication::new(
+            Constructor::new(algebraic_type, 0),
+            vec![Variable::new("x").into()],
+        ))));
+    }
+
+    #[test]
+    fn check_variable_in_record() {
+        assert!(is_let_escaping(&create_let(Record::new(
+            types::Record::boxed(vec![types::Primitive::Float64.into()]),
+            vec![Variable::new("x").into()],
+        ))));
+    }
+
+    #[test]
+    fn check_variable_in_record_element() {
+        let record_type = types::Record::boxed(vec![types::Primitive::Float64.into()]);
+
+        assert!(!is_let_escaping(&Let::new(
+            "x",
+            record_type.clone(),
+            Record::new(record_type.clone(), vec![Primitive::Float64(42.0).into()]),
+            RecordElement::new(record_type, 0, Variable::new("x")),
+        )));
+    }
+
+    #[test]
+    fn check_applied_function() {
+        assert!(!is_let_escaping(&Let::new(
+            "f",
+            types::Function::new(types::Primitive::Float64, types::Primitive::Float64),
+            Variable::new("g"),
+            FunctionApplication::new(Variable::new("f"), Primitive::Float64(42.0)),
+        )));
+    }
+
+    #[test]
+    fn check_variable_in_function_argument() {
+        assert!(is_let_escaping(&create_let(FunctionApplication::new(
+            Variable::new("f"),
+            Variable::new("x"),
+        ))));
+    }
+
+    #[test]
+    fn check_captured_variable() {
+        assert!(is_let_escaping(&create_let(LetRecursive::new(
+            vec![Definition::new(
+                "f",
+                vec![Argument::new("y", types::Primitive::Float64)],
+                Variable::new("x"),
+                types::Primitive::Float64,
+            )],
+            Primitive::Float64(42.0),
+        ))));
+    }
+
+    #[test]
+    fn check_shadowed_variable() {
+        assert!(!is_let_escaping(&create_let(Let::new(
+            "x",
+            types::Primitive::Float64,
+            Primitive::Float64(42.0),
+            Variable::new("x"),
+        ))));
+    }
+
+    #[test]
+    fn check_called_closure() {
+        assert!(!is_let_recursive_escaping(&create_let_recursive(
+            Variable::new("x"),
+            FunctionApplication::new(Variable::new("f"), Primitive::Float64(42.0)),
+        )));
+    }
+
+    #[test]
+    fn check_recursively_called_closure() {
+        assert!(!is_let_recursive_escaping(&create_let_recursive(
+            FunctionApplication::new(Variable::new("f"), Variable::new("x")),
+            FunctionApplication::new(Variable::new("f"), Primitive::Float64(42.0)),
+        )));
+    }
+
+    #[test]
+    fn check_returned_closure() {
+        assert!(is_let_recursive_escaping(&create_let_recursive(
+            Variable::new("x"),
+            Variable::new("f"),
+        )));
+    }
+
+    #[test]
+    fn check_closure_returned_by_itself() {
+        assert!(is_let_recursive_escaping(&create_let_recursive(
+            Variable::new("f"),
+            FunctionApplication::new(Variable::new("f"), Primitive::Float64(42.0)),
+        )));
+    }
+
+    #[test]
+    fn check_partially_applied_closure() {
+        assert!(is_let_recursive_escaping(&LetRecursive::new(
+            vec![Definition::new(
+                "f",
+                vec![
+                    Argument::new("x", types::Primitive::Float64),
+                    Argument::new("y", types::Primitive::Float64),
+                ],
+                Variable::new("x"),
+                types::Primitive::Float64,
+            )],
+            FunctionApplication::new(Variable::new("f"), Primitive::Float64(42.0)),
+        )));
+    }
+}
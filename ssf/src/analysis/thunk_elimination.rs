@@ -0,0 +1,807 @@
+use crate::{ir::*, types::Type};
+
+/// Eliminates thunks which are unnecessary under strict evaluation.
+///
+/// - Delay expressions bound by let expressions are evaluated strictly if
+///   their values are forced immediately or they are cheap and pure.
+/// - Thunk definitions are converted into normal ones if their bodies are
+///   cheap and pure and do not depend on their arguments.
+pub fn eliminate_thunks(module: &Module) -> Module {
+    Module::new(
+        module.foreign_declarations().to_vec(),
+        module.foreign_definitions().to_vec(),
+        module.declarations().to_vec(),
+        module
+            .definitions()
+            .iter()
+            .map(eliminate_in_definition)
+            .collect(),
+    )
+}
+
+fn eliminate_in_definition(definition: &Definition) -> Definition {
+    let body = eliminate_in_expression(definition.body());
+    let variables = body.find_variables();
+
+    Definition::with_options(
+        definition.name(),
+        definition.environment().to_vec(),
+        definition.arguments().to_vec(),
+        body.clone(),
+        definition.result_type().clone(),
+        definition.is_thunk()
+            && !(is_cheap(&body)
+                && definition
+                    .arguments()
+                    .iter()
+                    .all(|argument| !variables.contains(argument.name()))),
+    )
+}
+
+fn eliminate_in_expression(expression: &Expression) -> Expression {
+    match expression {
+        Expression::ArithmeticOperation(operation) => ArithmeticOperation::new(
+            operation.operator(),
+            eliminate_in_expression(operation.lhs()),
+            eliminate_in_expression(operation.rhs()),
+        )
+        .into(),
+        Expression::Array(array) => Array::new(
+            array.type_().clone(),
+            array
+                .elements()
+                .iter()
+                .map(eliminate_in_expression)
+                .collect(),
+        )
+        .into(),
+        Expression::ArrayElement(element) => ArrayElement::new(
+            element.type_().clone(),
+            eliminate_in_expression(element.array()),
+            eliminate_in_expression(element.index()),
+            eliminate_in_expression(element.default_element()),
+        )
+        .into(),
+        Expression::ArrayInitialization(initialization) => ArrayInitialization::new(
+            initialization.type_().clone(),
+            eliminate_in_expression(initialization.length()),
+            eliminate_in_expression(initialization.function()),
+        )
+        .into(),
+        Expression::ArrayLength(length) => {
+            ArrayLength::new(eliminate_in_expression(length.array())).into()
+        }
+        Expression::BitCast(bit_cast) => BitCast::new(
+            eliminate_in_expression(bit_cast.expression()),
+            bit_cast.type_().clone(),
+        )
+        .into(),
+        Expression::Case(Case::Algebraic(case)) => AlgebraicCase::new(
+            eliminate_in_expression(case.argument()),
+            case.alternatives()
+                .iter()
+                .map(|alternative| {
+                    AlgebraicAlternative::new(
+                        alternative.constructor().clone(),
+                        alternative.element_names().to_vec(),
+                        eliminate_in_expression(alternative.expression()),
+                    )
+                })
+                .collect(),
+            case.default_alternative().map(eliminate_in_expression),
+        )
+        .into(),
+        Expression::Case(Case::Primitive(case)) => PrimitiveCase::new(
+            eliminate_in_expression(case.argument()),
+            case.alternatives()
+                .iter()
+                .map(|alternative| {
+                    PrimitiveAlternative::new(
+                        alternative.primitive().clone(),
+                        eliminate_in_expression(alternative.expression()),
+                    )
+                })
+                .collect(),
+            case.default_alternative().map(eliminate_in_expression),
+        )
+        .into(),
+        Expression::ComparisonOperation(operation) => ComparisonOperation::new(
+            operation.operator(),
+            eliminate_in_expression(operation.lhs()),
+            eliminate_in_expression(operation.rhs()),
+        )
+        .into(),
+        Expression::ConstructorApplication(application) => ConstructorApplication::new(
+            application.constructor().clone(),
+            application
+                .arguments()
+                .iter()
+                .map(eliminate_in_expression)
+                .collect(),
+        )
+        .into(),
+        Expression::ConstructorField(field) => ConstructorField::new(
+            field.constructor().clone(),
+            field.index(),
+            eliminate_in_expression(field.argument()),
+        )
+        .into(),
+        Expression::Delay(delay) => Delay::new(
+            eliminate_in_expression(delay.expression()),
+            delay.type_().clone(),
+        )
+        .into(),
+        Expression::Force(force) => match eliminate_in_expression(force.expression()) {
+            Expression::Delay(delay) => delay.expression().clone(),
+            expression => Force::new(expression).into(),
+        },
+        Expression::FunctionApplication(application) => FunctionApplication::new(
+            eliminate_in_expression(application.function()),
+            eliminate_in_expression(application.argument()),
+        )
+        .into(),
+        Expression::Lambda(lambda) => Lambda::new(
+            lambda.arguments().to_vec(),
+            eliminate_in_expression(lambda.body()),
+            lambda.result_type().clone(),
+        )
+        .into(),
+        Expression::Let(let_) => eliminate_in_let(let_),
+        Expression::LetRecursive(let_recursive) => LetRecursive::new(
+            let_recursive
+                .definitions()
+                .iter()
+                .map(eliminate_in_definition)
+                .collect(),
+            eliminate_in_expression(let_recursive.expression()),
+        )
+        .into(),
+        Expression::Load(load) => {
+            Load::new(load.type_(), eliminate_in_expression(load.pointer())).into()
+        }
+        Expression::PointerOffset(offset) => PointerOffset::new(
+            eliminate_in_expression(offset.pointer()),
+            eliminate_in_expression(offset.offset()),
+        )
+        .into(),
+        Expression::Record(record) => Record::new(
+            record.type_().clone(),
+            record
+                .elements()
+                .iter()
+                .map(eliminate_in_expression)
+                .collect(),
+        )
+        .into(),
+        Expression::RecordElement(element) => RecordElement::new(
+            element.type_().clone(),
+            element.index(),
+            eliminate_in_expression(element.record()),
+        )
+        .into(),
+        Expression::RecordUpdate(update) => RecordUpdate::new(
+            update.type_().clone(),
+            eliminate_in_expression(update.record()),
+            update
+                .elements()
+                .iter()
+                .map(|(index, element)| (*index, eliminate_in_expression(element)))
+                .collect(),
+        )
+        .into(),
+        Expression::Store(store) => Store::new(
+            store.type_(),
+            eliminate_in_expression(store.value()),
+            eliminate_in_expression(store.pointer()),
+            eliminate_in_expression(store.expression()),
+        )
+        .into(),
+        Expression::ByteString(_) | Expression::Primitive(_) | Expression::Variable(_) => {
+            expression.clone()
+        }
+    }
+}
+
+fn eliminate_in_let(let_: &Let) -> Expression {
+    let bound_expression = eliminate_in_expression(let_.bound_expression());
+    let expression = eliminate_in_expression(let_.expression());
+
+    if let (Expression::Delay(delay), Type::Lazy(_)) = (&bound_expression, let_.type_()) {
+        if is_forced_first(&expression, let_.name()) || is_cheap(delay.expression()) {
+            if let Some(expression) = unforce(&expression, let_.name()) {
+                return Let::new(
+                    let_.name(),
+                    delay.type_().clone(),
+                    delay.expression().clone(),
+                    expression,
+                )
+                .into();
+            }
+        }
+    }
+
+    Let::new(
+        let_.name(),
+        let_.type_().clone(),
+        bound_expression,
+        expression,
+    )
+    .into()
+}
+
+// Returns true if evaluation of an expression starts with forcing a variable.
+fn is_forced_first(expression: &Expression, name: &str) -> bool {
+    let is_forced_first_in_order = |expressions: &[&Expression]| {
+        for expression in expressions {
+            if is_forced_first(expression, name) {
+                return true;
+            } else if !is_trivial(expression) {
+                return false;
+            }
+        }
+
+        false
+    };
+
+    match expression {
+        Expression::ArithmeticOperation(operation) => {
+            is_forced_first_in_order(&[operation.lhs(), operation.rhs()])
+        }
+        Expression::Array(array) => {
+            is_forced_first_in_order(&array.elements().iter().collect::<Vec<_>>())
+        }
+        Expression::ArrayElement(element) => {
+            is_forced_first_in_order(&[element.array(), element.index()])
+        }
+        Expression::ArrayInitialization(initialization) => {
+            is_forced_first_in_order(&[initialization.length(), initialization.function()])
+        }
+        Expression::ArrayLength(length) => is_forced_first(length.array(), name),
+        Expression::BitCast(bit_cast) => is_forced_first(bit_cast.expression(), name),
+        Expression::Case(Case::Algebraic(case)) => is_forced_first(case.argument(), name),
+        Expression::Case(Case::Primitive(case)) => is_forced_first(case.argument(), name),
+        Expression::ComparisonOperation(operation) => {
+            is_forced_first_in_order(&[operation.lhs(), operation.rhs()])
+        }
+        Expression::ConstructorApplication(application) => {
+            is_forced_first_in_order(&application.arguments().iter().collect::<Vec<_>>())
+        }
+        Expression::ConstructorField(field) => is_forced_first(field.argument(), name),
+        Expression::Force(force) => match force.expression() {
+            Expression::Variable(variable) => variable.name() == name,
+            expression => is_forced_first(expression, name),
+        },
+        Expression::FunctionApplication(application) => {
+            let mut expressions = vec![application.first_function()];
+            expressions.extend(application.arguments());
+
+            is_forced_first_in_order(&expressions)
+        }
+        Expression::Let(let_) => {
+            is_forced_first(let_.bound_expression(), name)
+                || is_trivial(let_.bound_expression())
+                    && let_.name() != name
+                    && is_forced_first(let_.expression(), name)
+        }
+        Expression::Load(load) => is_forced_first(load.pointer(), name),
+        Expression::PointerOffset(offset) => {
+            is_forced_first_in_order(&[offset.pointer(), offset.offset()])
+        }
+        Expression::Record(record) => {
+            is_forced_first_in_order(&record.elements().iter().collect::<Vec<_>>())
+        }
+        Expression::RecordElement(element) => is_forced_first(element.record(), name),
+        Expression::RecordUpdate(update) => is_forced_first_in_order(
+            &std::iter::once(update.record())
+                .chain(update.elements().iter().map(|(_, element)| element))
+                .collect::<Vec<_>>(),
+        ),
+        // Values are stored before the expressions are evaluated.
+        Expression::Store(store) => is_forced_first_in_order(&[store.value(), store.pointer()]),
+        Expression::ByteString(_)
+        | Expression::Delay(_)
+        | Expression::Lambda(_)
+        | Expression::LetRecursive(_)
+        | Expression::Primitive(_)
+        | Expression::Variable(_) => false,
+    }
+}
+
+fn is_trivial(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Primitive(_) | Expression::Variable(_)
+    )
+}
+
+// Cheap and pure expressions can be evaluated eagerly or repeatedly without
+// changing semantics.
+fn is_cheap(expression: &Expression) -> bool {
+    match expression {
+        Expression::ArithmeticOperation(operation) => {
+            operation.operator() != ArithmeticOperator::Divide
+                && is_cheap(operation.lhs())
+                && is_cheap(operation.rhs())
+        }
+        Expression::ArrayElement(element) => {
+            is_cheap(element.array())
+                && is_cheap(element.index())
+                && is_cheap(element.default_element())
+        }
+        Expression::ArrayLength(length) => is_cheap(length.array()),
+        Expression::BitCast(bit_cast) => is_cheap(bit_cast.expression()),
+        Expression::ComparisonOperation(operation) => {
+            is_cheap(operation.lhs()) && is_cheap(operation.rhs())
+        }
+        Expression::ConstructorApplication(application) => {
+            !application.constructor().constructor_type().is_boxed()
+                && application.arguments().iter().all(is_cheap)
+        }
+        // Fields of multi-constructor types are unreachable on tag mismatch.
+        Expression::ConstructorField(field) => {
+            field.constructor().algebraic_type().is_singleton() && is_cheap(field.argument())
+        }
+        Expression::Record(record) => {
+            !record.type_().is_boxed() && record.elements().iter().all(is_cheap)
+        }
+        Expression::PointerOffset(offset) => {
+            is_cheap(offset.pointer()) && is_cheap(offset.offset())
+        }
+        Expression::RecordElement(element) => is_cheap(element.record()),
+        Expression::RecordUpdate(update) => {
+            !update.type_().is_boxed()
+                && is_cheap(update.record())
+                && update
+                    .elements()
+                    .iter()
+                    .all(|(_, element)| is_cheap(element))
+        }
+        Expression::ByteString(_) | Expression::Primitive(_) | Expression::Variable(_) => true,
+        Expression::Array(_)
+        | Expression::ArrayInitialization(_)
+        | Expression::Case(_)
+        | Expression::Delay(_)
+        | Expression::Force(_)
+        | Expression::FunctionApplication(_)
+        | Expression::Lambda(_)
+        | Expression::Let(_)
+        | Expression::LetRecursive(_)
+        | Expression::Load(_)
+        | Expression::Store(_) => false,
+    }
+}
+
+// Replaces forced variables with the variables themselves. It returns `None`
+// if the variables are used without being forced.
+fn unforce(expression: &Expression, name: &str) -> Option<Expression> {
+    let unforce = |expression| unforce(expression, name);
+
+    Some(match expression {
+        Expression::ArithmeticOperation(operation) => ArithmeticOperation::new(
+            operation.operator(),
+            unforce(operation.lhs())?,
+            unforce(operation.rhs())?,
+        )
+        .into(),
+        Expression::Array(array) => Array::new(
+            array.type_().clone(),
+            array
+                .elements()
+                .iter()
+                .map(unforce)
+                .collect::<Option<_>>()?,
+        )
+        .into(),
+        Expression::ArrayElement(element) => ArrayElement::new(
+            element.type_().clone(),
+            unforce(element.array())?,
+            unforce(element.index())?,
+            unforce(element.default_element())?,
+        )
+        .into(),
+        Expression::ArrayInitialization(initialization) => ArrayInitialization::new(
+            initialization.type_().clone(),
+            unforce(initialization.length())?,
+            unforce(initialization.function())?,
+        )
+        .into(),
+        Expression::ArrayLength(length) => ArrayLength::new(unforce(length.array())?).into(),
+        Expression::BitCast(bit_cast) => {
+            BitCast::new(unforce(bit_cast.expression())?, bit_cast.type_().clone()).into()
+        }
+        Expression::Case(Case::Algebraic(case)) => AlgebraicCase::new(
+            unforce(case.argument())?,
+            case.alternatives()
+                .iter()
+                .map(|alternative| {
+                    Some(AlgebraicAlternative::new(
+                        alternative.constructor().clone(),
+                        alternative.element_names().to_vec(),
+                        if alternative
+                            .element_names()
+                            .iter()
+                            .any(|element_name| element_name == name)
+                        {
+                            alternative.expression().clone()
+                        } else {
+                            unforce(alternative.expression())?
+                        },
+                    ))
+                })
+                .collect::<Option<_>>()?,
+            match case.default_alternative() {
+                Some(expression) => Some(unforce(expression)?),
+                None => None,
+            },
+        )
+        .into(),
+        Expression::Case(Case::Primitive(case)) => PrimitiveCase::new(
+            unforce(case.argument())?,
+            case.alternatives()
+                .iter()
+                .map(|alternative| {
+                    Some(PrimitiveAlternative::new(
+                        alternative.primitive().clone(),
+                        unforce(alternative.expression())?,
+                    ))
+                })
+                .collect::<Option<_>>()?,
+            match case.default_alternative() {
+                Some(expression) => Some(unforce(expression)?),
+                None => None,
+            },
+        )
+        .into(),
+        Expression::ComparisonOperation(operation) => ComparisonOperation::new(
+            operation.operator(),
+            unforce(operation.lhs())?,
+            unforce(operation.rhs())?,
+        )
+        .into(),
+        Expression::ConstructorApplication(application) => ConstructorApplication::new(
+            application.constructor().clone(),
+            application
+                .arguments()
+                .iter()
+                .map(unforce)
+                .collect::<Option<_>>()?,
+        )
+        .into(),
+        Expression::ConstructorField(field) => ConstructorField::new(
+            field.constructor().clone(),
+            field.index(),
+            unforce(field.argument())?,
+        )
+        .into(),
+        Expression::Delay(delay) => Delay::new(
+            unforce_in_definition(delay.definition(), name)?
+                .body()
+                .clone(),
+            delay.type_().clone(),
+        )
+        .into(),
+        Expression::Force(force) => match force.expression() {
+            Expression::Variable(variable) if variable.name() == name => variable.clone().into(),
+            expression => Force::new(unforce(expression)?).into(),
+        },
+        Expression::FunctionApplication(application) => FunctionApplication::new(
+            unforce(application.function())?,
+            unforce(application.argument())?,
+        )
+        .into(),
+        Expression::Lambda(lambda) => Lambda::new(
+            lambda.arguments().to_vec(),
+            unforce_in_definition(lambda.definition(), name)?
+                .body()
+                .clone(),
+            lambda.result_type().clone(),
+        )
+        .into(),
+        Expression::Let(let_) => Let::new(
+            let_.name(),
+            let_.type_().clone(),
+            unforce(let_.bound_expression())?,
+            if let_.name() == name {
+                let_.expression().clone()
+            } else {
+                unforce(let_.expression())?
+            },
+        )
+        .into(),
+        Expression::LetRecursive(let_recursive) => {
+            if let_recursive
+                .definitions()
+                .iter()
+                .any(|definition| definition.name() == name)
+            {
+                let_recursive.clone().into()
+            } else {
+                LetRecursive::new(
+                    let_recursive
+                        .definitions()
+                        .iter()
+                        .map(|definition| unforce_in_definition(definition, name))
+                        .collect::<Option<_>>()?,
+                    unforce(let_recursive.expression())?,
+                )
+                .into()
+            }
+        }
+        Expression::Load(load) => Load::new(load.type_(), unforce(load.pointer())?).into(),
+        Expression::PointerOffset(offset) => {
+            PointerOffset::new(unforce(offset.pointer())?, unforce(offset.offset())?).into()
+        }
+        Expression::ByteString(_) | Expression::Primitive(_) => expression.clone(),
+        Expression::Record(record) => Record::new(
+            record.type_().clone(),
+            record
+                .elements()
+                .iter()
+                .map(unforce)
+                .collect::<Option<_>>()?,
+        )
+        .into(),
+        Expression::RecordElement(element) => RecordElement::new(
+            element.type_().clone(),
+            element.index(),
+            unforce(element.record())?,
+        )
+        .into(),
+        Expression::RecordUpdate(update) => RecordUpdate::new(
+            update.type_().clone(),
+            unforce(update.record())?,
+            update
+                .elements()
+                .iter()
+                .map(|(index, element)| Some((*index, unforce(element)?)))
+                .collect::<Option<_>>()?,
+        )
+        .into(),
+        Expression::Store(store) => Store::new(
+            store.type_(),
+            unforce(store.value())?,
+            unforce(store.pointer())?,
+            unforce(store.expression())?,
+        )
+        .into(),
+        Expression::Variable(variable) => {
+            if variable.name() == name {
+                return None;
+            }
+
+            expression.clone()
+        }
+    })
+}
+
+fn unforce_in_definition(definition: &Definition, name: &str) -> Option<Definition> {
+    Some(
+        if definition
+            .arguments()
+            .iter()
+            .any(|argument| argument.name() == name)
+        {
+            definition.clone()
+        } else {
+            Definition::with_options(
+                definition.name(),
+                definition.environment().to_vec(),
+                definition.arguments().to_vec(),
+                unforce(definition.body(), name)?,
+                definition.result_type().clone(),
+                definition.is_thunk(),
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types;
+
+    fn create_module(expression: impl Into<Expression>) -> Module {
+        Module::new(
+            vec![],
+            vec![],
+            vec![],
+            vec![Definition::new(
+                "f",
+                vec![Argument::new("x", types::Primitive::Float64)],
+                expression,
+                types::Primitive::Float64,
+            )],
+        )
+    }
+
+    fn eliminate_in_body(expression: impl Into<Expression>) -> Expression {
+        eliminate_thunks(&create_module(expression)).definitions()[0]
+            .body()
+            .clone()
+    }
+
+    #[test]
+    fn eliminate_delay_forced_immediately() {
+        assert_eq!(
+            eliminate_in_body(Let::new(
+                "y",
+                types::Lazy::new(types::Primitive::Float64),
+                Delay::new(
+                    FunctionApplication::new(Variable::new("f"), Variable::new("x")),
+                    types::Primitive::Float64
+                ),
+                ArithmeticOperation::new(
+                    ArithmeticOperator::Add,
+                    Force::new(Variable::new("y")),
+                    Force::new(Variable::new("y"))
+                )
+            )),
+            Let::new(
+                "y",
+                types::Primitive::Float64,
+                FunctionApplication::new(Variable::new("f"), Variable::new("x")),
+                ArithmeticOperation::new(
+                    ArithmeticOperator::Add,
+                    Variable::new("y"),
+                    Variable::new("y")
+                )
+            )
+            .into()
+        );
+    }
+
+    #[test]
+    fn eliminate_cheap_delay() {
+        assert_eq!(
+            eliminate_in_body(Let::new(
+                "y",
+                types::Lazy::new(types::Primitive::Float64),
+                Delay::new(
+                    ArithmeticOperation::new(
+                        ArithmeticOperator::Add,
+                        Variable::new("x"),
+                        Primitive::Float64(1.0)
+                    ),
+                    types::Primitive::Float64
+                ),
+                PrimitiveCase::new(
+                    Variable::new("x"),
+                    vec![PrimitiveAlternative::new(
+                        Primitive::Float64(0.0),
+                        Force::new(Variable::new("y"))
+                    )],
+                    Some(Primitive::Float64(0.0).into())
+                )
+            )),
+            Let::new(
+                "y",
+                types::Primitive::Float64,
+                ArithmeticOperation::new(
+                    ArithmeticOperator::Add,
+                    Variable::new("x"),
+                    Primitive::Float64(1.0)
+                ),
+                PrimitiveCase::new(
+                    Variable::new("x"),
+                    vec![PrimitiveAlternative::new(
+                        Primitive::Float64(0.0),
+                        Variable::new("y")
+                    )],
+                    Some(Primitive::Float64(0.0).into())
+                )
+            )
+            .into()
+        );
+    }
+
+    #[test]
+    fn keep_expensive_delay_forced_conditionally() {
+        let expression = Let::new(
+            "y",
+            types::Lazy::new(types::Primitive::Float64),
+            Delay::new(
+                FunctionApplication::new(Variable::new("f"), Variable::new("x")),
+                types::Primitive::Float64,
+            ),
+            PrimitiveCase::new(
+                Variable::new("x"),
+                vec![PrimitiveAlternative::new(
+                    Primitive::Float64(0.0),
+                    Force::new(Variable::new("y")),
+                )],
+                Some(Primitive::Float64(0.0).into()),
+            ),
+        );
+
+        assert_eq!(
+            eliminate_in_body(expression.clone()),
+            create_module(expression).definitions()[0].body().clone()
+        );
+    }
+
+    #[test]
+    fn keep_delay_used_without_force() {
+        let expression = Let::new(
+            "y",
+            types::Lazy::new(types::Primitive::Float64),
+            Delay::new(Primitive::Float64(42.0), types::Primitive::Float64),
+            Force::new(FunctionApplication::new(
+                Variable::new("g"),
+                Variable::new("y"),
+            )),
+        );
+
+        assert_eq!(
+            eliminate_in_body(expression.clone()),
+            create_module(expression).definitions()[0].body().clone()
+        );
+    }
+
+    #[test]
+    fn keep_delay_of_load_forced_after_store() {
+        let expression = Let::new(
+            "y",
+            types::Lazy::new(types::Primitive::Float64),
+            Delay::new(
+                Load::new(types::Primitive::Float64, Variable::new("p")),
+                types::Primitive::Float64,
+            ),
+            Store::new(
+                types::Primitive::Float64,
+                Primitive::Float64(42.0),
+                Variable::new("p"),
+                Force::new(Variable::new("y")),
+            ),
+        );
+
+        assert_eq!(
+            eliminate_in_body(expression.clone()),
+            create_module(expression).definitions()[0].body().clone()
+        );
+    }
+
+    #[test]
+    fn eliminate_force_of_delay() {
+        assert_eq!(
+            eliminate_in_body(Force::new(Delay::new(
+                Variable::new("x"),
+                types::Primitive::Float64
+            ))),
+            Variable::new("x").into()
+        );
+    }
+
+    #[test]
+    fn convert_cheap_thunk_into_normal_definition() {
+        let module = eliminate_thunks(&Module::new(
+            vec![],
+            vec![],
+            vec![],
+            vec![Definition::thunk(
+                "x",
+                vec![Argument::new("y", types::Primitive::Float64)],
+                Primitive::Float64(42.0),
+                types::Primitive::Float64,
+            )],
+        ));
+
+        assert!(!module.definitions()[0].is_thunk());
+    }
+
+    #[test]
+    fn keep_expensive_thunk() {
+        let module = eliminate_thunks(&Module::new(
+            vec![],
+            vec![],
+            vec![],
+            vec![Definition::thunk(
+                "x",
+                vec![Argument::new("y", types::Primitive::Float64)],
+                FunctionApplication::new(Variable::new("x"), Primitive::Float64(42.0)),
+                types::Primitive::Float64,
+            )],
+        ));
+
+        assert!(module.definitions()[0].is_thunk());
+    }
+}
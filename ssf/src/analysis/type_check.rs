@@ -68,14 +68,66 @@ fn check_expression(
 
             if !lhs_type.is_primitive() || !rhs_type.is_primitive() || lhs_type != rhs_type {
                 return Err(TypeCheckError::TypesNotMatched(lhs_type, rhs_type));
+            } else if lhs_type == types::Primitive::Pointer.into() {
+                return Err(TypeCheckError::NumberExpected(expression.clone()));
             }
 
             lhs_type
         }
+        Expression::Array(array) => {
+            for element in array.elements() {
+                check_equality(
+                    &check_expression(element, variables)?,
+                    array.type_().element(),
+                )?;
+            }
+
+            array.type_().clone().into()
+        }
+        Expression::ArrayElement(element) => {
+            check_equality(
+                &check_expression(element.array(), variables)?,
+                &element.type_().clone().into(),
+            )?;
+            check_equality(
+                &check_expression(element.index(), variables)?,
+                &types::Primitive::Integer64.into(),
+            )?;
+            check_equality(
+                &check_expression(element.default_element(), variables)?,
+                element.type_().element(),
+            )?;
+
+            element.type_().element().clone()
+        }
+        Expression::ArrayInitialization(initialization) => {
+            check_equality(
+                &check_expression(initialization.length(), variables)?,
+                &types::Primitive::Integer64.into(),
+            )?;
+            check_equality(
+                &check_expression(initialization.function(), variables)?,
+                &types::Function::new(
+                    types::Primitive::Integer64,
+                    initialization.type_().element().clone(),
+                )
+                .into(),
+            )?;
+
+            initialization.type_().clone().into()
+        }
+        Expression::ArrayLength(length) => {
+            check_expression(length.array(), variables)?
+                .into_array()
+                .ok_or_else(|| TypeCheckError::ArrayExpected(length.array().clone()))?;
+
+            types::Primitive::Integer64.into()
+        }
         Expression::BitCast(bit_cast) => {
             check_expression(bit_cast.expression(), variables)?;
             bit_cast.type_().clone()
         }
+        Expression::ByteString(_) => types::Array::new(types::Primitive::Integer8).into(),
         Expression::Case(case) => check_case(case, variables)?,
         Expression::ComparisonOperation(operation) => {
             let lhs_type = check_expression(operation.lhs(), variables)?;
@@ -110,6 +162,31 @@ fn check_expression(
                 .clone()
                 .into()
         }
+        Expression::ConstructorField(field) => {
+            let constructor = field.constructor();
+
+            check_equality(
+                &check_expression(field.argument(), variables)?,
+                &constructor.algebraic_type().clone().into(),
+            )?;
+
+            constructor
+                .constructor_type()
+                .elements()
+                .get(field.index())
+                .ok_or_else(|| TypeCheckError::IndexOutOfBounds(expression.clone()))?
+                .clone()
+        }
+        Expression::Delay(delay) => {
+            check_definition(delay.definition(), variables)?;
+
+            types::Lazy::new(delay.type_().clone()).into()
+        }
+        Expression::Force(force) => check_expression(force.expression(), variables)?
+            .into_lazy()
+            .ok_or_else(|| TypeCheckError::LazyExpected(force.expression().clone()))?
+            .content()
+            .clone(),
         Expression::FunctionApplication(function_application) => {
             let function_type = check_expression(function_application.function(), variables)?
                 .into_function()
@@ -124,6 +201,11 @@ fn check_expression(
 
             function_type.result().clone()
         }
+        Expression::Lambda(lambda) => {
+            check_definition(lambda.definition(), variables)?;
+
+            lambda.type_().clone().into()
+        }
         Expression::LetRecursive(let_recursive) => {
             let mut variables = variables.clone();
 
@@ -148,7 +230,82 @@ fn check_expression(
 
             check_expression(let_.expression(), &variables)?
         }
+        Expression::Load(load) => {
+            check_equality(
+                &check_expression(load.pointer(), variables)?,
+                &types::Primitive::Pointer.into(),
+            )?;
+
+            load.type_().into()
+        }
+        Expression::PointerOffset(offset) => {
+            check_equality(
+                &check_expression(offset.pointer(), variables)?,
+                &types::Primitive::Pointer.into(),
+            )?;
+            check_equality(
+                &check_expression(offset.offset(), variables)?,
+                &types::Primitive::Integer64.into(),
+            )?;
+
+            types::Primitive::Pointer.into()
+        }
         Expression::Primitive(primitive) => Ok(check_primitive(primitive).into())?,
+        Expression::Record(record) => {
+            if record.elements().len() != record.type_().elements().len() {
+                return Err(TypeCheckError::WrongArgumentsLength(expression.clone()));
+            }
+
+            for (element, element_type) in record.elements().iter().zip(record.type_().elements()) {
+                check_equality(&check_expression(element, variables)?, element_type)?;
+            }
+
+            record.type_().clone().into()
+        }
+        Expression::RecordElement(element) => {
+            check_equality(
+                &check_expression(element.record(), variables)?,
+                &element.type_().clone().into(),
+            )?;
+
+            element
+                .type_()
+                .elements()
+                .get(element.index())
+                .ok_or_else(|| TypeCheckError::IndexOutOfBounds(expression.clone()))?
+                .clone()
+        }
+        Expression::RecordUpdate(update) => {
+            check_equality(
+                &check_expression(update.record(), variables)?,
+                &update.type_().clone().into(),
+            )?;
+
+            for (index, element) in update.elements() {
+                check_equality(
+                    &check_expression(element, variables)?,
+                    update
+                        .type_()
+                        .elements()
+                        .get(*index)
+                        .ok_or_else(|| TypeCheckError::IndexOutOfBounds(expression.clone()))?,
+                )?;
+            }
+
+            update.type_().clone().into()
+        }
+        Expression::Store(store) => {
+            check_equality(
+                &check_expression(store.value(), variables)?,
+                &store.type_().into(),
+            )?;
+            check_equality(
+                &check_expression(store.pointer(), variables)?,
+                &types::Primitive::Pointer.into(),
+            )?;
+
+            check_expression(store.expression(), variables)?
+        }
         Expression::Variable(variable) => check_variable(variable, variables)?,
     })
 }
@@ -240,6 +397,7 @@ fn check_primitive(primitive: &Primitive) -> types::Primitive {
         Primitive::Integer8(_) => types::Primitive::Integer8,
         Primitive::Integer32(_) => types::Primitive::Integer32,
         Primitive::Integer64(_) => types::Primitive::Integer64,
+        Primitive::NullPointer => types::Primitive::Pointer,
     }
 }
 
@@ -981,48 +1139,366 @@ mod tests {
         }
     }
 
-    #[test]
-    fn check_bit_cast() {
-        let module = Module::new(
-            vec![],
-            vec![],
-            vec![],
-            vec![Definition::with_environment(
-                "f",
-                vec![],
-                vec![Argument::new("x", types::Primitive::Float64)],
-                BitCast::new(42, types::Primitive::Float64),
-                types::Primitive::Float64,
-            )],
-        );
-        assert_eq!(check_types(&module), Ok(()));
-    }
+    mod arrays {
+        use super::*;
 
-    #[test]
-    fn check_add_operator() {
-        let module = Module::new(
-            vec![],
-            vec![],
-            vec![],
-            vec![Definition::with_environment(
-                "f",
-                vec![],
-                vec![Argument::new("x", types::Primitive::Float64)],
-                ArithmeticOperation::new(ArithmeticOperator::Add, 42.0, 42.0),
-                types::Primitive::Float64,
-            )],
-        );
-        assert_eq!(check_types(&module), Ok(()));
+        #[test]
+        fn check_arrays() {
+            let array_type = types::Array::new(types::Primitive::Float64);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Float64)],
+                        Array::new(
+                            array_type.clone(),
+                            vec![Variable::new("x").into(), Primitive::Float64(42.0).into()],
+                        ),
+                        array_type,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_arrays_with_wrong_element_type() {
+            let array_type = types::Array::new(types::Primitive::Float64);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Float64)],
+                        Array::new(array_type.clone(), vec![Primitive::Integer64(42).into()]),
+                        array_type,
+                    )],
+                )),
+                Err(TypeCheckError::TypesNotMatched(_, _))
+            ));
+        }
+
+        #[test]
+        fn check_byte_strings() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Float64)],
+                        ByteString::new("foo"),
+                        types::Array::new(types::Primitive::Integer8),
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn check_array_lengths() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new(
+                            "x",
+                            types::Array::new(types::Primitive::Float64)
+                        )],
+                        ArrayLength::new(Variable::new("x")),
+                        types::Primitive::Integer64,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_array_lengths_of_non_array_values() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Float64)],
+                        ArrayLength::new(Variable::new("x")),
+                        types::Primitive::Integer64,
+                    )],
+                )),
+                Err(TypeCheckError::ArrayExpected(Variable::new("x").into()))
+            );
+        }
+
+        #[test]
+        fn check_array_elements() {
+            let array_type = types::Array::new(types::Primitive::Float64);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", array_type.clone())],
+                        ArrayElement::new(
+                            array_type,
+                            Variable::new("x"),
+                            Primitive::Integer64(0),
+                            Primitive::Float64(42.0),
+                        ),
+                        types::Primitive::Float64,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_array_elements_with_wrong_index_type() {
+            let array_type = types::Array::new(types::Primitive::Float64);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", array_type.clone())],
+                        ArrayElement::new(
+                            array_type,
+                            Variable::new("x"),
+                            Primitive::Float64(0.0),
+                            Primitive::Float64(42.0),
+                        ),
+                        types::Primitive::Float64,
+                    )],
+                )),
+                Err(TypeCheckError::TypesNotMatched(_, _))
+            ));
+        }
+
+        #[test]
+        fn check_array_initializations() {
+            let array_type = types::Array::new(types::Primitive::Integer64);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Integer64)],
+                        ArrayInitialization::new(
+                            array_type.clone(),
+                            Variable::new("x"),
+                            Lambda::new(
+                                vec![Argument::new("i", types::Primitive::Integer64)],
+                                Variable::new("i"),
+                                types::Primitive::Integer64,
+                            ),
+                        ),
+                        array_type,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_array_initializations_with_wrong_function_type() {
+            let array_type = types::Array::new(types::Primitive::Float64);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Integer64)],
+                        ArrayInitialization::new(
+                            array_type.clone(),
+                            Variable::new("x"),
+                            Lambda::new(
+                                vec![Argument::new("i", types::Primitive::Integer64)],
+                                Variable::new("i"),
+                                types::Primitive::Integer64,
+                            ),
+                        ),
+                        array_type,
+                    )],
+                )),
+                Err(TypeCheckError::TypesNotMatched(_, _))
+            ));
+        }
     }
 
-    #[test]
-    fn check_equality_operator() {
-        let module = Module::new(
-            vec![],
-            vec![],
-            vec![],
-            vec![Definition::with_environment(
-                "f",
+    mod constructor_fields {
+        use super::*;
+
+        #[test]
+        fn check_constructor_fields() {
+            let algebraic_type = types::Algebraic::new(vec![types::Constructor::boxed(vec![
+                types::Primitive::Float64.into(),
+                types::Primitive::Integer64.into(),
+            ])]);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", algebraic_type.clone())],
+                        ConstructorField::new(
+                            Constructor::new(algebraic_type, 0),
+                            1,
+                            Variable::new("x"),
+                        ),
+                        types::Primitive::Integer64,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn check_constructor_fields_of_recursive_algebraic_types() {
+            let algebraic_type =
+                types::Algebraic::new(vec![types::Constructor::boxed(vec![Type::Index(0)])]);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", algebraic_type.clone())],
+                        ConstructorField::new(
+                            Constructor::new(algebraic_type.clone(), 0),
+                            0,
+                            Variable::new("x"),
+                        ),
+                        algebraic_type,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_constructor_fields_with_index_out_of_bounds() {
+            let algebraic_type = types::Algebraic::new(vec![types::Constructor::boxed(vec![
+                types::Primitive::Float64.into(),
+            ])]);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", algebraic_type.clone())],
+                        ConstructorField::new(
+                            Constructor::new(algebraic_type, 0),
+                            1,
+                            Variable::new("x"),
+                        ),
+                        types::Primitive::Float64,
+                    )],
+                )),
+                Err(TypeCheckError::IndexOutOfBounds(_))
+            ));
+        }
+
+        #[test]
+        fn fail_to_check_constructor_fields_with_wrong_argument_type() {
+            let algebraic_type = types::Algebraic::new(vec![types::Constructor::boxed(vec![
+                types::Primitive::Float64.into(),
+            ])]);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Float64)],
+                        ConstructorField::new(
+                            Constructor::new(algebraic_type, 0),
+                            0,
+                            Variable::new("x"),
+                        ),
+                        types::Primitive::Float64,
+                    )],
+                )),
+                Err(TypeCheckError::TypesNotMatched(_, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn check_bit_cast() {
+        let module = Module::new(
+            vec![],
+            vec![],
+            vec![],
+            vec![Definition::with_environment(
+                "f",
+                vec![],
+                vec![Argument::new("x", types::Primitive::Float64)],
+                BitCast::new(42, types::Primitive::Float64),
+                types::Primitive::Float64,
+            )],
+        );
+        assert_eq!(check_types(&module), Ok(()));
+    }
+
+    #[test]
+    fn check_add_operator() {
+        let module = Module::new(
+            vec![],
+            vec![],
+            vec![],
+            vec![Definition::with_environment(
+                "f",
+                vec![],
+                vec![Argument::new("x", types::Primitive::Float64)],
+                ArithmeticOperation::new(ArithmeticOperator::Add, 42.0, 42.0),
+                types::Primitive::Float64,
+            )],
+        );
+        assert_eq!(check_types(&module), Ok(()));
+    }
+
+    #[test]
+    fn check_equality_operator() {
+        let module = Module::new(
+            vec![],
+            vec![],
+            vec![],
+            vec![Definition::with_environment(
+                "f",
                 vec![],
                 vec![Argument::new("x", types::Primitive::Float64)],
                 ComparisonOperation::new(ComparisonOperator::Equal, 42.0, 42.0),
@@ -1032,6 +1508,444 @@ mod tests {
         assert_eq!(check_types(&module), Ok(()));
     }
 
+    mod pointers {
+        use super::*;
+
+        #[test]
+        fn check_comparison_operation() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Pointer)],
+                        ComparisonOperation::new(
+                            ComparisonOperator::Equal,
+                            Variable::new("x"),
+                            Primitive::NullPointer,
+                        ),
+                        types::Primitive::Boolean,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_arithmetic_operation() {
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Pointer)],
+                        ArithmeticOperation::new(
+                            ArithmeticOperator::Add,
+                            Variable::new("x"),
+                            Primitive::NullPointer,
+                        ),
+                        types::Primitive::Pointer,
+                    )],
+                )),
+                Err(TypeCheckError::NumberExpected(_))
+            ));
+        }
+
+        #[test]
+        fn check_pointer_offset() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Pointer)],
+                        PointerOffset::new(Variable::new("x"), Primitive::Integer64(8)),
+                        types::Primitive::Pointer,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_pointer_offset_with_wrong_offset_type() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Pointer)],
+                        PointerOffset::new(Variable::new("x"), Primitive::Integer32(8)),
+                        types::Primitive::Pointer,
+                    )],
+                )),
+                Err(TypeCheckError::TypesNotMatched(
+                    types::Primitive::Integer32.into(),
+                    types::Primitive::Integer64.into()
+                ))
+            );
+        }
+
+        #[test]
+        fn check_load() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Pointer)],
+                        Load::new(types::Primitive::Float64, Variable::new("x")),
+                        types::Primitive::Float64,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn check_store() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Pointer)],
+                        Store::new(
+                            types::Primitive::Float64,
+                            42.0,
+                            Variable::new("x"),
+                            Variable::new("x"),
+                        ),
+                        types::Primitive::Pointer,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_store_with_wrong_value_type() {
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Pointer)],
+                        Store::new(
+                            types::Primitive::Float64,
+                            42,
+                            Variable::new("x"),
+                            Variable::new("x"),
+                        ),
+                        types::Primitive::Pointer,
+                    )],
+                )),
+                Err(TypeCheckError::TypesNotMatched(
+                    types::Primitive::Integer64.into(),
+                    types::Primitive::Float64.into()
+                ))
+            );
+        }
+    }
+
+    mod lazy {
+        use super::*;
+
+        #[test]
+        fn check_delay() {
+            let module = Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![Definition::new(
+                    "f",
+                    vec![Argument::new("x", types::Primitive::Float64)],
+                    Delay::new(Variable::new("x"), types::Primitive::Float64),
+                    types::Lazy::new(types::Primitive::Float64),
+                )],
+            );
+
+            assert_eq!(check_types(&module), Ok(()));
+        }
+
+        #[test]
+        fn fail_to_check_delay_with_wrong_type() {
+            let module = Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![Definition::new(
+                    "f",
+                    vec![Argument::new("x", types::Primitive::Float64)],
+                    Delay::new(Variable::new("x"), types::Primitive::Float32),
+                    types::Lazy::new(types::Primitive::Float32),
+                )],
+            );
+
+            assert!(matches!(
+                check_types(&module),
+                Err(TypeCheckError::TypesNotMatched(_, _))
+            ));
+        }
+
+        #[test]
+        fn check_force() {
+            let module = Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![Definition::new(
+                    "f",
+                    vec![Argument::new(
+                        "x",
+                        types::Lazy::new(types::Primitive::Float64),
+                    )],
+                    Force::new(Variable::new("x")),
+                    types::Primitive::Float64,
+                )],
+            );
+
+            assert_eq!(check_types(&module), Ok(()));
+        }
+
+        #[test]
+        fn fail_to_check_force_of_non_lazy_value() {
+            let module = Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![Definition::new(
+                    "f",
+                    vec![Argument::new("x", types::Primitive::Float64)],
+                    Force::new(Variable::new("x")),
+                    types::Primitive::Float64,
+                )],
+            );
+
+            assert_eq!(
+                check_types(&module),
+                Err(TypeCheckError::LazyExpected(Variable::new("x").into()))
+            );
+        }
+    }
+
+    mod lambda {
+        use super::*;
+
+        #[test]
+        fn check_lambda() {
+            let module = Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![Definition::new(
+                    "f",
+                    vec![Argument::new("x", types::Primitive::Float64)],
+                    FunctionApplication::new(
+                        Lambda::new(
+                            vec![Argument::new("y", types::Primitive::Float64)],
+                            ArithmeticOperation::new(
+                                ArithmeticOperator::Add,
+                                Variable::new("x"),
+                                Variable::new("y"),
+                            ),
+                            types::Primitive::Float64,
+                        ),
+                        Primitive::Float64(42.0),
+                    ),
+                    types::Primitive::Float64,
+                )],
+            );
+
+            assert_eq!(check_types(&module), Ok(()));
+        }
+
+        #[test]
+        fn fail_to_check_lambda_with_wrong_result_type() {
+            let module = Module::new(
+                vec![],
+                vec![],
+                vec![],
+                vec![Definition::new(
+                    "f",
+                    vec![Argument::new("x", types::Primitive::Float64)],
+                    Lambda::new(
+                        vec![Argument::new("y", types::Primitive::Float64)],
+                        Variable::new("x"),
+                        types::Primitive::Float32,
+                    ),
+                    types::Function::new(types::Primitive::Float64, types::Primitive::Float32),
+                )],
+            );
+
+            assert!(matches!(
+                check_types(&module),
+                Err(TypeCheckError::TypesNotMatched(_, _))
+            ));
+        }
+    }
+
+    mod records {
+        use super::*;
+
+        #[test]
+        fn check_records() {
+            let record_type = types::Record::boxed(vec![
+                types::Primitive::Float64.into(),
+                types::Primitive::Integer64.into(),
+            ]);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Float64)],
+                        Record::new(
+                            record_type.clone(),
+                            vec![Variable::new("x").into(), Primitive::Integer64(42).into()],
+                        ),
+                        record_type,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_records_with_wrong_number_of_elements() {
+            let record_type = types::Record::boxed(vec![types::Primitive::Float64.into()]);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", types::Primitive::Float64)],
+                        Record::new(
+                            record_type.clone(),
+                            vec![Variable::new("x").into(), Variable::new("x").into()],
+                        ),
+                        record_type,
+                    )],
+                )),
+                Err(TypeCheckError::WrongArgumentsLength(_))
+            ));
+        }
+
+        #[test]
+        fn check_record_elements() {
+            let record_type = types::Record::unboxed(vec![
+                types::Primitive::Float64.into(),
+                types::Primitive::Integer64.into(),
+            ]);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", record_type.clone())],
+                        RecordElement::new(record_type, 1, Variable::new("x")),
+                        types::Primitive::Integer64,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_record_elements_with_index_out_of_bounds() {
+            let record_type = types::Record::unboxed(vec![types::Primitive::Float64.into()]);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", record_type.clone())],
+                        RecordElement::new(record_type, 1, Variable::new("x")),
+                        types::Primitive::Float64,
+                    )],
+                )),
+                Err(TypeCheckError::IndexOutOfBounds(_))
+            ));
+        }
+
+        #[test]
+        fn check_record_updates() {
+            let record_type = types::Record::boxed(vec![
+                types::Primitive::Float64.into(),
+                types::Primitive::Integer64.into(),
+            ]);
+
+            assert_eq!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", record_type.clone())],
+                        RecordUpdate::new(
+                            record_type.clone(),
+                            Variable::new("x"),
+                            vec![(1, Primitive::Integer64(42).into())],
+                        ),
+                        record_type,
+                    )],
+                )),
+                Ok(())
+            );
+        }
+
+        #[test]
+        fn fail_to_check_record_updates_with_wrong_element_type() {
+            let record_type = types::Record::boxed(vec![types::Primitive::Float64.into()]);
+
+            assert!(matches!(
+                check_types(&Module::new(
+                    vec![],
+                    vec![],
+                    vec![],
+                    vec![Definition::new(
+                        "f",
+                        vec![Argument::new("x", record_type.clone())],
+                        RecordUpdate::new(
+                            record_type.clone(),
+                            Variable::new("x"),
+                            vec![(0, Primitive::Integer64(42).into())],
+                        ),
+                        record_type,
+                    )],
+                )),
+                Err(TypeCheckError::TypesNotMatched(_, _))
+            ));
+        }
+    }
+
     mod foreign_declarations {
         use super::*;
 
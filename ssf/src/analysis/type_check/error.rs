@@ -3,9 +3,13 @@ use std::{error::Error, fmt::Display};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum TypeCheckError {
+    ArrayExpected(Expression),
     ForeignDefinitionNotFound(ForeignDefinition),
     FunctionExpected(Expression),
+    IndexOutOfBounds(Expression),
+    LazyExpected(Expression),
     NoAlternativeFound(Case),
+    NumberExpected(Expression),
     TypesNotMatched(Type, Type),
     VariableNotFound(Variable),
     WrongArgumentsLength(Expression),
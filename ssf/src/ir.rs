@@ -3,25 +3,40 @@ mod algebraic_case;
 mod argument;
 mod arithmetic_operation;
 mod arithmetic_operator;
+mod array;
+mod array_element;
+mod array_initialization;
+mod array_length;
 mod bit_cast;
+mod byte_string;
 mod calling_convention;
 mod case;
 mod comparison_operation;
 mod comparison_operator;
 mod constructor;
 mod constructor_application;
+mod constructor_field;
 mod declaration;
 mod definition;
+mod delay;
 mod expression;
+mod force;
 mod foreign_declaration;
 mod foreign_definition;
 mod function_application;
+mod lambda;
 mod let_;
 mod let_recursive;
+mod load;
 mod module;
+mod pointer_offset;
 mod primitive;
 mod primitive_alternative;
 mod primitive_case;
+mod record;
+mod record_element;
+mod record_update;
+mod store;
 mod variable;
 
 pub use algebraic_alternative::*;
@@ -29,23 +44,38 @@ pub use algebraic_case::*;
 pub use argument::*;
 pub use arithmetic_operation::*;
 pub use arithmetic_operator::*;
+pub use array::*;
+pub use array_element::*;
+pub use array_initialization::*;
+pub use array_length::*;
 pub use bit_cast::*;
+pub use byte_string::*;
 pub use calling_convention::*;
 pub use case::*;
 pub use comparison_operation::*;
 pub use comparison_operator::*;
 pub use constructor::*;
 pub use constructor_application::*;
+pub use constructor_field::*;
 pub use declaration::*;
 pub use definition::*;
+pub use delay::*;
 pub use expression::*;
+pub use force::*;
 pub use foreign_declaration::*;
 pub use foreign_definition::*;
 pub use function_application::*;
+pub use lambda::*;
 pub use let_::*;
 pub use let_recursive::*;
+pub use load::*;
 pub use module::*;
+pub use pointer_offset::*;
 pub use primitive::*;
 pub use primitive_alternative::*;
 pub use primitive_case::*;
+pub use record::*;
+pub use record_element::*;
+pub use record_update::*;
+pub use store::*;
 pub use variable::*;
@@ -0,0 +1,76 @@
+use super::expression::Expression;
+use crate::types::{self, Type};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// An element of an array at an index.
+///
+/// Default elements are evaluated if indices are out of bounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArrayElement {
+    type_: types::Array,
+    array: Arc<Expression>,
+    index: Arc<Expression>,
+    default_element: Arc<Expression>,
+}
+
+impl ArrayElement {
+    pub fn new(
+        type_: types::Array,
+        array: impl Into<Expression>,
+        index: impl Into<Expression>,
+        default_element: impl Into<Expression>,
+    ) -> Self {
+        Self {
+            type_,
+            array: array.into().into(),
+            index: index.into().into(),
+            default_element: default_element.into().into(),
+        }
+    }
+
+    pub fn type_(&self) -> &types::Array {
+        &self.type_
+    }
+
+    pub fn array(&self) -> &Expression {
+        &self.array
+    }
+
+    pub fn index(&self) -> &Expression {
+        &self.index
+    }
+
+    pub fn default_element(&self) -> &Expression {
+        &self.default_element
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        let mut variables = self.array.find_variables();
+
+        variables.extend(self.index.find_variables());
+        variables.extend(self.default_element.find_variables());
+
+        variables
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(
+            self.type_.clone(),
+            self.array.infer_environment(variables),
+            self.index.infer_environment(variables),
+            self.default_element.infer_environment(variables),
+        )
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(
+            convert(&self.type_.clone().into()).into_array().unwrap(),
+            self.array.convert_types(convert),
+            self.index.convert_types(convert),
+            self.default_element.convert_types(convert),
+        )
+    }
+}
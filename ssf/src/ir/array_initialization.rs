@@ -0,0 +1,65 @@
+use super::expression::Expression;
+use crate::types::{self, Type};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// An array of a length whose elements are computed by applying a function
+/// to their indices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArrayInitialization {
+    type_: types::Array,
+    length: Arc<Expression>,
+    function: Arc<Expression>,
+}
+
+impl ArrayInitialization {
+    pub fn new(
+        type_: types::Array,
+        length: impl Into<Expression>,
+        function: impl Into<Expression>,
+    ) -> Self {
+        Self {
+            type_,
+            length: length.into().into(),
+            function: function.into().into(),
+        }
+    }
+
+    pub fn type_(&self) -> &types::Array {
+        &self.type_
+    }
+
+    pub fn length(&self) -> &Expression {
+        &self.length
+    }
+
+    pub fn function(&self) -> &Expression {
+        &self.function
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        let mut variables = self.length.find_variables();
+
+        variables.extend(self.function.find_variables());
+
+        variables
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(
+            self.type_.clone(),
+            self.length.infer_environment(variables),
+            self.function.infer_environment(variables),
+        )
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(
+            convert(&self.type_.clone().into()).into_array().unwrap(),
+            self.length.convert_types(convert),
+            self.function.convert_types(convert),
+        )
+    }
+}
@@ -0,0 +1,35 @@
+use super::expression::Expression;
+use crate::types::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArrayLength {
+    array: Arc<Expression>,
+}
+
+impl ArrayLength {
+    pub fn new(array: impl Into<Expression>) -> Self {
+        Self {
+            array: array.into().into(),
+        }
+    }
+
+    pub fn array(&self) -> &Expression {
+        &self.array
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.array.find_variables()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(self.array.infer_environment(variables))
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(self.array.convert_types(convert))
+    }
+}
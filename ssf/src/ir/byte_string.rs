@@ -0,0 +1,17 @@
+/// A byte string literal. Its value is an array of 8-bit integers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ByteString {
+    bytes: Vec<u8>,
+}
+
+impl ByteString {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            bytes: bytes.into(),
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
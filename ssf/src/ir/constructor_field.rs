@@ -0,0 +1,55 @@
+use super::{constructor::Constructor, expression::Expression};
+use crate::types::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstructorField {
+    constructor: Constructor,
+    index: usize,
+    argument: Arc<Expression>,
+}
+
+impl ConstructorField {
+    pub fn new(constructor: Constructor, index: usize, argument: impl Into<Expression>) -> Self {
+        Self {
+            constructor,
+            index,
+            argument: argument.into().into(),
+        }
+    }
+
+    pub fn constructor(&self) -> &Constructor {
+        &self.constructor
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn argument(&self) -> &Expression {
+        &self.argument
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.argument.find_variables()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(
+            self.constructor.clone(),
+            self.index,
+            self.argument.infer_environment(variables),
+        )
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(
+            self.constructor.convert_types(convert),
+            self.index,
+            self.argument.convert_types(convert),
+        )
+    }
+}
@@ -0,0 +1,104 @@
+use super::{argument::Argument, definition::Definition, expression::Expression};
+use crate::types::{self, Type};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+const DEFINITION_NAME: &str = "_delay";
+
+/// Delay expressions create thunks of lazy values.
+///
+/// They are represented by thunk definitions with unit arguments so that
+/// compilers can reuse thunk implementation for them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delay {
+    definition: Arc<Definition>,
+}
+
+impl Delay {
+    pub fn new(expression: impl Into<Expression>, type_: impl Into<Type>) -> Self {
+        let expression = expression.into();
+        let type_ = type_.into();
+        let name = expression.generate_fresh_name(DEFINITION_NAME);
+
+        Self {
+            definition: Definition::thunk(
+                &name,
+                vec![Argument::new(
+                    &name,
+                    types::Lazy::new(type_.clone())
+                        .to_function()
+                        .argument()
+                        .clone(),
+                )],
+                expression,
+                type_,
+            )
+            .into(),
+        }
+    }
+
+    pub fn expression(&self) -> &Expression {
+        self.definition.body()
+    }
+
+    pub fn type_(&self) -> &Type {
+        self.definition.result_type()
+    }
+
+    pub fn definition(&self) -> &Definition {
+        &self.definition
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.definition.find_variables()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self {
+            definition: self.definition.infer_environment(variables).into(),
+        }
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self {
+            definition: self.definition.convert_types(convert).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::variable::Variable, *};
+
+    #[test]
+    fn infer_environment() {
+        assert_eq!(
+            Delay::new(Variable::new("x"), types::Primitive::Float64)
+                .infer_environment(
+                    &vec![("x".into(), types::Primitive::Float64.into())]
+                        .into_iter()
+                        .collect()
+                )
+                .definition()
+                .environment(),
+            &[Argument::new("x", types::Primitive::Float64)]
+        );
+    }
+
+    #[test]
+    fn infer_environment_with_variable_of_definition_name() {
+        assert_eq!(
+            Delay::new(Variable::new(DEFINITION_NAME), types::Primitive::Float64)
+                .infer_environment(
+                    &vec![(DEFINITION_NAME.into(), types::Primitive::Float64.into())]
+                        .into_iter()
+                        .collect()
+                )
+                .definition()
+                .environment(),
+            &[Argument::new(DEFINITION_NAME, types::Primitive::Float64)]
+        );
+    }
+}
@@ -1,9 +1,13 @@
 use super::{
-    algebraic_case::AlgebraicCase, arithmetic_operation::ArithmeticOperation, bit_cast::BitCast,
-    case::Case, comparison_operation::ComparisonOperation,
-    constructor_application::ConstructorApplication, function_application::FunctionApplication,
-    let_::Let, let_recursive::LetRecursive, primitive::Primitive, primitive_case::PrimitiveCase,
-    variable::Variable,
+    algebraic_case::AlgebraicCase, arithmetic_operation::ArithmeticOperation, array::Array,
+    array_element::ArrayElement, array_initialization::ArrayInitialization,
+    array_length::ArrayLength, bit_cast::BitCast, byte_string::ByteString, case::Case,
+    comparison_operation::ComparisonOperation, constructor_application::ConstructorApplication,
+    constructor_field::ConstructorField, delay::Delay, force::Force,
+    function_application::FunctionApplication, lambda::Lambda, let_::Let,
+    let_recursive::LetRecursive, load::Load, pointer_offset::PointerOffset, primitive::Primitive,
+    primitive_case::PrimitiveCase, record::Record, record_element::RecordElement,
+    record_update::RecordUpdate, store::Store, variable::Variable,
 };
 use crate::types::Type;
 use std::collections::{HashMap, HashSet};
@@ -11,14 +15,29 @@ use std::collections::{HashMap, HashSet};
 #[derive(Clone, Debug, PartialEq)]
 pub enum Expression {
     ArithmeticOperation(ArithmeticOperation),
+    Array(Array),
+    ArrayElement(ArrayElement),
+    ArrayInitialization(ArrayInitialization),
+    ArrayLength(ArrayLength),
     BitCast(BitCast),
+    ByteString(ByteString),
     Case(Case),
     ComparisonOperation(ComparisonOperation),
     ConstructorApplication(ConstructorApplication),
+    ConstructorField(ConstructorField),
+    Delay(Delay),
+    Force(Force),
     FunctionApplication(FunctionApplication),
+    Lambda(Lambda),
     Let(Let),
     LetRecursive(LetRecursive),
+    Load(Load),
+    PointerOffset(PointerOffset),
     Primitive(Primitive),
+    Record(Record),
+    RecordElement(RecordElement),
+    RecordUpdate(RecordUpdate),
+    Store(Store),
     Variable(Variable),
 }
 
@@ -33,55 +52,113 @@ impl Expression {
     pub(crate) fn find_variables(&self) -> HashSet<String> {
         match self {
             Self::ArithmeticOperation(operation) => operation.find_variables(),
+            Self::Array(array) => array.find_variables(),
+            Self::ArrayElement(element) => element.find_variables(),
+            Self::ArrayInitialization(initialization) => initialization.find_variables(),
+            Self::ArrayLength(length) => length.find_variables(),
             Self::BitCast(bit_cast) => bit_cast.find_variables(),
             Self::Case(case) => case.find_variables(),
             Self::ComparisonOperation(operation) => operation.find_variables(),
             Self::ConstructorApplication(constructor_application) => {
                 constructor_application.find_variables()
             }
+            Self::ConstructorField(field) => field.find_variables(),
+            Self::Delay(delay) => delay.find_variables(),
+            Self::Force(force) => force.find_variables(),
             Self::FunctionApplication(function_application) => {
                 function_application.find_variables()
             }
+            Self::Lambda(lambda) => lambda.find_variables(),
             Self::LetRecursive(let_recursive) => let_recursive.find_variables(),
             Self::Let(let_) => let_.find_variables(),
+            Self::Load(load) => load.find_variables(),
+            Self::PointerOffset(offset) => offset.find_variables(),
+            Self::Record(record) => record.find_variables(),
+            Self::RecordElement(element) => element.find_variables(),
+            Self::RecordUpdate(update) => update.find_variables(),
+            Self::Store(store) => store.find_variables(),
             Self::Variable(variable) => variable.find_variables(),
-            Self::Primitive(_) => HashSet::new(),
+            Self::ByteString(_) | Self::Primitive(_) => HashSet::new(),
         }
     }
 
+    // Generate a name which does not shadow any free variables.
+    pub(crate) fn generate_fresh_name(&self, prefix: &str) -> String {
+        let variables = self.find_variables();
+        let mut name = prefix.to_string();
+
+        while variables.contains(&name) {
+            name.push('_');
+        }
+
+        name
+    }
+
     pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
         match self {
             Self::ArithmeticOperation(operation) => operation.infer_environment(variables).into(),
+            Self::Array(array) => array.infer_environment(variables).into(),
+            Self::ArrayElement(element) => element.infer_environment(variables).into(),
+            Self::ArrayInitialization(initialization) => {
+                initialization.infer_environment(variables).into()
+            }
+            Self::ArrayLength(length) => length.infer_environment(variables).into(),
             Self::BitCast(bit_cast) => bit_cast.infer_environment(variables).into(),
             Self::Case(case) => case.infer_environment(variables).into(),
             Self::ComparisonOperation(operation) => operation.infer_environment(variables).into(),
             Self::ConstructorApplication(constructor_application) => {
                 constructor_application.infer_environment(variables).into()
             }
+            Self::ConstructorField(field) => field.infer_environment(variables).into(),
+            Self::Delay(delay) => delay.infer_environment(variables).into(),
+            Self::Force(force) => force.infer_environment(variables).into(),
             Self::FunctionApplication(function_application) => {
                 function_application.infer_environment(variables).into()
             }
+            Self::Lambda(lambda) => lambda.infer_environment(variables).into(),
             Self::LetRecursive(let_recursive) => let_recursive.infer_environment(variables).into(),
             Self::Let(let_) => let_.infer_environment(variables).into(),
-            Self::Primitive(_) | Self::Variable(_) => self.clone(),
+            Self::Load(load) => load.infer_environment(variables).into(),
+            Self::PointerOffset(offset) => offset.infer_environment(variables).into(),
+            Self::Record(record) => record.infer_environment(variables).into(),
+            Self::RecordElement(element) => element.infer_environment(variables).into(),
+            Self::RecordUpdate(update) => update.infer_environment(variables).into(),
+            Self::Store(store) => store.infer_environment(variables).into(),
+            Self::ByteString(_) | Self::Primitive(_) | Self::Variable(_) => self.clone(),
         }
     }
 
     pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
         match self {
             Self::ArithmeticOperation(operation) => operation.convert_types(convert).into(),
+            Self::Array(array) => array.convert_types(convert).into(),
+            Self::ArrayElement(element) => element.convert_types(convert).into(),
+            Self::ArrayInitialization(initialization) => {
+                initialization.convert_types(convert).into()
+            }
+            Self::ArrayLength(length) => length.convert_types(convert).into(),
             Self::BitCast(bit_cast) => bit_cast.convert_types(convert).into(),
             Self::Case(case) => case.convert_types(convert).into(),
             Self::ComparisonOperation(operation) => operation.convert_types(convert).into(),
             Self::ConstructorApplication(constructor_application) => {
                 constructor_application.convert_types(convert).into()
             }
+            Self::ConstructorField(field) => field.convert_types(convert).into(),
+            Self::Delay(delay) => delay.convert_types(convert).into(),
+            Self::Force(force) => force.convert_types(convert).into(),
             Self::FunctionApplication(function_application) => {
                 function_application.convert_types(convert).into()
             }
+            Self::Lambda(lambda) => lambda.convert_types(convert).into(),
             Self::LetRecursive(let_recursive) => let_recursive.convert_types(convert).into(),
             Self::Let(let_) => let_.convert_types(convert).into(),
-            Self::Primitive(_) | Self::Variable(_) => self.clone(),
+            Self::Load(load) => load.convert_types(convert).into(),
+            Self::PointerOffset(offset) => offset.convert_types(convert).into(),
+            Self::Record(record) => record.convert_types(convert).into(),
+            Self::RecordElement(element) => element.convert_types(convert).into(),
+            Self::RecordUpdate(update) => update.convert_types(convert).into(),
+            Self::Store(store) => store.convert_types(convert).into(),
+            Self::ByteString(_) | Self::Primitive(_) | Self::Variable(_) => self.clone(),
         }
     }
 }
@@ -98,12 +175,42 @@ impl From<ArithmeticOperation> for Expression {
     }
 }
 
+impl From<Array> for Expression {
+    fn from(array: Array) -> Self {
+        Self::Array(array)
+    }
+}
+
+impl From<ArrayElement> for Expression {
+    fn from(element: ArrayElement) -> Self {
+        Self::ArrayElement(element)
+    }
+}
+
+impl From<ArrayInitialization> for Expression {
+    fn from(initialization: ArrayInitialization) -> Self {
+        Self::ArrayInitialization(initialization)
+    }
+}
+
+impl From<ArrayLength> for Expression {
+    fn from(length: ArrayLength) -> Self {
+        Self::ArrayLength(length)
+    }
+}
+
 impl From<BitCast> for Expression {
     fn from(bit_cast: BitCast) -> Self {
         Self::BitCast(bit_cast)
     }
 }
 
+impl From<ByteString> for Expression {
+    fn from(string: ByteString) -> Self {
+        Self::ByteString(string)
+    }
+}
+
 impl From<Case> for Expression {
     fn from(case: Case) -> Self {
         Self::Case(case)
@@ -122,12 +229,36 @@ impl From<ConstructorApplication> for Expression {
     }
 }
 
+impl From<ConstructorField> for Expression {
+    fn from(field: ConstructorField) -> Self {
+        Self::ConstructorField(field)
+    }
+}
+
+impl From<Delay> for Expression {
+    fn from(delay: Delay) -> Self {
+        Self::Delay(delay)
+    }
+}
+
+impl From<Force> for Expression {
+    fn from(force: Force) -> Self {
+        Self::Force(force)
+    }
+}
+
 impl From<FunctionApplication> for Expression {
     fn from(function_application: FunctionApplication) -> Self {
         Self::FunctionApplication(function_application)
     }
 }
 
+impl From<Lambda> for Expression {
+    fn from(lambda: Lambda) -> Self {
+        Self::Lambda(lambda)
+    }
+}
+
 impl From<LetRecursive> for Expression {
     fn from(let_recursive: LetRecursive) -> Self {
         Self::LetRecursive(let_recursive)
@@ -140,6 +271,18 @@ impl From<Let> for Expression {
     }
 }
 
+impl From<Load> for Expression {
+    fn from(load: Load) -> Self {
+        Self::Load(load)
+    }
+}
+
+impl From<PointerOffset> for Expression {
+    fn from(offset: PointerOffset) -> Self {
+        Self::PointerOffset(offset)
+    }
+}
+
 impl<T: Into<Primitive>> From<T> for Expression {
     fn from(primitive: T) -> Self {
         Self::Primitive(primitive.into())
@@ -152,6 +295,30 @@ impl From<PrimitiveCase> for Expression {
     }
 }
 
+impl From<Record> for Expression {
+    fn from(record: Record) -> Self {
+        Self::Record(record)
+    }
+}
+
+impl From<RecordElement> for Expression {
+    fn from(element: RecordElement) -> Self {
+        Self::RecordElement(element)
+    }
+}
+
+impl From<RecordUpdate> for Expression {
+    fn from(update: RecordUpdate) -> Self {
+        Self::RecordUpdate(update)
+    }
+}
+
+impl From<Store> for Expression {
+    fn from(store: Store) -> Self {
+        Self::Store(store)
+    }
+}
+
 impl From<Variable> for Expression {
     fn from(variable: Variable) -> Self {
         Self::Variable(variable)
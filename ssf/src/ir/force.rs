@@ -0,0 +1,35 @@
+use super::expression::Expression;
+use crate::types::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Force {
+    expression: Arc<Expression>,
+}
+
+impl Force {
+    pub fn new(expression: impl Into<Expression>) -> Self {
+        Self {
+            expression: expression.into().into(),
+        }
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.expression.find_variables()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(self.expression.infer_environment(variables))
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(self.expression.convert_types(convert))
+    }
+}
@@ -0,0 +1,111 @@
+use super::{argument::Argument, definition::Definition, expression::Expression};
+use crate::types::{self, Type};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+const DEFINITION_NAME: &str = "_lambda";
+
+/// Lambda expressions create anonymous closures.
+///
+/// They are represented by non-recursive definitions so that compilers can
+/// reuse closure implementation for them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lambda {
+    definition: Arc<Definition>,
+}
+
+impl Lambda {
+    pub fn new(
+        arguments: Vec<Argument>,
+        body: impl Into<Expression>,
+        result_type: impl Into<Type>,
+    ) -> Self {
+        let body = body.into();
+        let name = body.generate_fresh_name(DEFINITION_NAME);
+
+        Self {
+            definition: Definition::new(&name, arguments, body, result_type.into()).into(),
+        }
+    }
+
+    pub fn arguments(&self) -> &[Argument] {
+        self.definition.arguments()
+    }
+
+    pub fn body(&self) -> &Expression {
+        self.definition.body()
+    }
+
+    pub fn result_type(&self) -> &Type {
+        self.definition.result_type()
+    }
+
+    pub fn type_(&self) -> &types::Function {
+        self.definition.type_()
+    }
+
+    pub fn definition(&self) -> &Definition {
+        &self.definition
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.definition.find_variables()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self {
+            definition: self.definition.infer_environment(variables).into(),
+        }
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self {
+            definition: self.definition.convert_types(convert).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::variable::Variable, *};
+
+    #[test]
+    fn infer_environment() {
+        assert_eq!(
+            Lambda::new(
+                vec![Argument::new("x", types::Primitive::Float64)],
+                Variable::new("y"),
+                types::Primitive::Float64
+            )
+            .infer_environment(
+                &vec![("y".into(), types::Primitive::Float64.into())]
+                    .into_iter()
+                    .collect()
+            )
+            .definition()
+            .environment(),
+            &[Argument::new("y", types::Primitive::Float64)]
+        );
+    }
+
+    #[test]
+    fn infer_environment_with_free_variable_named_as_definition() {
+        assert_eq!(
+            Lambda::new(
+                vec![Argument::new("x", types::Primitive::Float64)],
+                Variable::new(DEFINITION_NAME),
+                types::Primitive::Float64
+            )
+            .infer_environment(
+                &vec![(DEFINITION_NAME.into(), types::Primitive::Float64.into())]
+                    .into_iter()
+                    .collect()
+            )
+            .definition()
+            .environment(),
+            &[Argument::new(DEFINITION_NAME, types::Primitive::Float64)]
+        );
+    }
+}
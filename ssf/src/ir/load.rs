@@ -0,0 +1,42 @@
+use super::expression::Expression;
+use crate::types::{self, Type};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// A primitive value loaded from memory through a pointer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Load {
+    type_: types::Primitive,
+    pointer: Arc<Expression>,
+}
+
+impl Load {
+    pub fn new(type_: types::Primitive, pointer: impl Into<Expression>) -> Self {
+        Self {
+            type_,
+            pointer: pointer.into().into(),
+        }
+    }
+
+    pub fn type_(&self) -> types::Primitive {
+        self.type_
+    }
+
+    pub fn pointer(&self) -> &Expression {
+        &self.pointer
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.pointer.find_variables()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(self.type_, self.pointer.infer_environment(variables))
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(self.type_, self.pointer.convert_types(convert))
+    }
+}
@@ -0,0 +1,52 @@
+use super::expression::Expression;
+use crate::types::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// A pointer offset by a number of bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointerOffset {
+    pointer: Arc<Expression>,
+    offset: Arc<Expression>,
+}
+
+impl PointerOffset {
+    pub fn new(pointer: impl Into<Expression>, offset: impl Into<Expression>) -> Self {
+        Self {
+            pointer: pointer.into().into(),
+            offset: offset.into().into(),
+        }
+    }
+
+    pub fn pointer(&self) -> &Expression {
+        &self.pointer
+    }
+
+    pub fn offset(&self) -> &Expression {
+        &self.offset
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.pointer
+            .find_variables()
+            .into_iter()
+            .chain(self.offset.find_variables())
+            .collect()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(
+            self.pointer.infer_environment(variables),
+            self.offset.infer_environment(variables),
+        )
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(
+            self.pointer.convert_types(convert),
+            self.offset.convert_types(convert),
+        )
+    }
+}
@@ -6,6 +6,7 @@ pub enum Primitive {
     Integer8(u8),
     Integer32(u32),
     Integer64(u64),
+    NullPointer,
 }
 
 impl From<bool> for Primitive {
@@ -0,0 +1,71 @@
+use super::expression::Expression;
+use crate::types::{self, Type};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordUpdate {
+    type_: types::Record,
+    record: Arc<Expression>,
+    elements: Vec<(usize, Expression)>,
+}
+
+impl RecordUpdate {
+    pub fn new(
+        type_: types::Record,
+        record: impl Into<Expression>,
+        elements: Vec<(usize, Expression)>,
+    ) -> Self {
+        Self {
+            type_,
+            record: record.into().into(),
+            elements,
+        }
+    }
+
+    pub fn type_(&self) -> &types::Record {
+        &self.type_
+    }
+
+    pub fn record(&self) -> &Expression {
+        &self.record
+    }
+
+    pub fn elements(&self) -> &[(usize, Expression)] {
+        &self.elements
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        let mut variables = self.record.find_variables();
+
+        for (_, element) in &self.elements {
+            variables.extend(element.find_variables());
+        }
+
+        variables
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(
+            self.type_.clone(),
+            self.record.infer_environment(variables),
+            self.elements
+                .iter()
+                .map(|(index, element)| (*index, element.infer_environment(variables)))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(
+            convert(&self.type_.clone().into()).into_record().unwrap(),
+            self.record.convert_types(convert),
+            self.elements
+                .iter()
+                .map(|(index, element)| (*index, element.convert_types(convert)))
+                .collect(),
+        )
+    }
+}
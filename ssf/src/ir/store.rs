@@ -0,0 +1,76 @@
+use super::expression::Expression;
+use crate::types::{self, Type};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// A primitive value stored into memory through a pointer.
+///
+/// The expression is evaluated after the value is stored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Store {
+    type_: types::Primitive,
+    value: Arc<Expression>,
+    pointer: Arc<Expression>,
+    expression: Arc<Expression>,
+}
+
+impl Store {
+    pub fn new(
+        type_: types::Primitive,
+        value: impl Into<Expression>,
+        pointer: impl Into<Expression>,
+        expression: impl Into<Expression>,
+    ) -> Self {
+        Self {
+            type_,
+            value: value.into().into(),
+            pointer: pointer.into().into(),
+            expression: expression.into().into(),
+        }
+    }
+
+    pub fn type_(&self) -> types::Primitive {
+        self.type_
+    }
+
+    pub fn value(&self) -> &Expression {
+        &self.value
+    }
+
+    pub fn pointer(&self) -> &Expression {
+        &self.pointer
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    pub(crate) fn find_variables(&self) -> HashSet<String> {
+        self.value
+            .find_variables()
+            .into_iter()
+            .chain(self.pointer.find_variables())
+            .chain(self.expression.find_variables())
+            .collect()
+    }
+
+    pub(crate) fn infer_environment(&self, variables: &HashMap<String, Type>) -> Self {
+        Self::new(
+            self.type_,
+            self.value.infer_environment(variables),
+            self.pointer.infer_environment(variables),
+            self.expression.infer_environment(variables),
+        )
+    }
+
+    pub(crate) fn convert_types(&self, convert: &impl Fn(&Type) -> Type) -> Self {
+        Self::new(
+            self.type_,
+            self.value.convert_types(convert),
+            self.pointer.convert_types(convert),
+            self.expression.convert_types(convert),
+        )
+    }
+}
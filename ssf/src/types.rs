@@ -1,14 +1,22 @@
 mod algebraic;
+mod array;
 mod canonicalize;
 mod constructor;
 mod function;
+mod lazy;
 mod primitive;
+mod record;
+mod representation;
 mod type_;
 mod unfold;
 
 pub use algebraic::*;
+pub use array::*;
 pub(crate) use canonicalize::canonicalize;
 pub use constructor::*;
 pub use function::*;
+pub use lazy::*;
 pub use primitive::*;
+pub use record::*;
+pub use representation::*;
 pub use type_::*;
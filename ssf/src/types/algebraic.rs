@@ -1,9 +1,10 @@
-use super::{constructor::Constructor, unfold::unfold};
+use super::{constructor::Constructor, representation::Representation, unfold::unfold};
 use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Algebraic {
     constructors: BTreeMap<u64, Constructor>,
+    representation: Representation,
 }
 
 impl Algebraic {
@@ -18,17 +19,38 @@ impl Algebraic {
     }
 
     pub fn with_tags(constructors: BTreeMap<u64, Constructor>) -> Self {
+        Self::with_representation(constructors, Representation::Compact)
+    }
+
+    /// Creates an algebraic data type with a binary representation. For
+    /// example, FFI-compatible types always have pointer-sized tags if they
+    /// have multiple constructors.
+    pub fn with_representation(
+        constructors: BTreeMap<u64, Constructor>,
+        representation: Representation,
+    ) -> Self {
         if constructors.is_empty() {
             panic!("no constructors in algebraic data type");
         }
 
-        Self { constructors }
+        Self {
+            constructors,
+            representation,
+        }
     }
 
     pub fn constructors(&self) -> &BTreeMap<u64, Constructor> {
         &self.constructors
     }
 
+    pub fn representation(&self) -> Representation {
+        self.representation
+    }
+
+    pub fn is_ffi_compatible(&self) -> bool {
+        self.representation == Representation::FfiCompatible
+    }
+
     pub fn is_singleton(&self) -> bool {
         self.constructors.len() == 1
     }
@@ -47,10 +69,41 @@ impl Algebraic {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::Type;
 
     #[test]
     #[should_panic]
     fn new_with_no_constructor() {
         Algebraic::new(vec![]);
     }
+
+    #[test]
+    fn unfold_ffi_compatible() {
+        assert!(Algebraic::with_representation(
+            vec![(0, Constructor::boxed(vec![Type::Index(0)]))]
+                .into_iter()
+                .collect(),
+            Representation::FfiCompatible
+        )
+        .unfold()
+        .is_ffi_compatible());
+    }
+
+    #[test]
+    fn unfold_pointer_tagged() {
+        assert_eq!(
+            Algebraic::with_representation(
+                vec![
+                    (0, Constructor::unboxed(vec![])),
+                    (1, Constructor::boxed(vec![Type::Index(0)]))
+                ]
+                .into_iter()
+                .collect(),
+                Representation::PointerTagged
+            )
+            .unfold()
+            .representation(),
+            Representation::PointerTagged
+        );
+    }
 }
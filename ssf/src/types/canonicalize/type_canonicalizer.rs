@@ -23,7 +23,7 @@ impl<'a> TypeCanonicalizer<'a> {
 
                 let other = self.push_type(algebraic);
 
-                Algebraic::with_tags(
+                Algebraic::with_representation(
                     algebraic
                         .constructors()
                         .iter()
@@ -41,14 +41,26 @@ impl<'a> TypeCanonicalizer<'a> {
                             )
                         })
                         .collect(),
+                    algebraic.representation(),
                 )
                 .into()
             }
+            Type::Array(array) => Array::new(self.canonicalize(array.element())).into(),
             Type::Function(function) => Function::new(
                 self.canonicalize(function.argument()),
                 self.canonicalize(function.result()),
             )
             .into(),
+            Type::Lazy(lazy) => Lazy::new(self.canonicalize(lazy.content())).into(),
+            Type::Record(record) => Record::new(
+                record
+                    .elements()
+                    .iter()
+                    .map(|element| self.canonicalize(element))
+                    .collect(),
+                record.is_boxed(),
+            )
+            .into(),
             _ => type_.clone(),
         }
     }
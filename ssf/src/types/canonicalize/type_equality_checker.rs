@@ -12,7 +12,9 @@ impl<'a> TypeEqualityChecker<'a> {
     }
 
     pub fn equal_algebraics(&self, one: &Algebraic, other: &Algebraic) -> bool {
-        if one.constructors().len() != other.constructors().len() {
+        if one.constructors().len() != other.constructors().len()
+            || one.representation() != other.representation()
+        {
             return false;
         } else if self.pairs.contains(&(one, other)) {
             return true;
@@ -28,11 +30,22 @@ impl<'a> TypeEqualityChecker<'a> {
 
     fn equal(&self, one: &Type, other: &Type) -> bool {
         match (one, other) {
+            (Type::Array(one), Type::Array(other)) => self.equal(one.element(), other.element()),
             (Type::Function(one), Type::Function(other)) => {
                 self.equal(one.argument(), other.argument())
                     && self.equal(one.result(), other.result())
             }
+            (Type::Lazy(one), Type::Lazy(other)) => self.equal(one.content(), other.content()),
             (Type::Primitive(one), Type::Primitive(other)) => one == other,
+            (Type::Record(one), Type::Record(other)) => {
+                one.elements().len() == other.elements().len()
+                    && one.is_boxed() == other.is_boxed()
+                    && one
+                        .elements()
+                        .iter()
+                        .zip(other.elements())
+                        .all(|(one, other)| self.equal(one, other))
+            }
             (Type::Algebraic(one), Type::Algebraic(other)) => self.equal_algebraics(one, other),
             (Type::Index(index), Type::Algebraic(other)) => {
                 self.equal_algebraics(self.pairs[*index].0, other)
@@ -148,6 +161,22 @@ mod tests {
                 .into()])])
                 .into(),
             ),
+            (
+                Algebraic::new(vec![Constructor::boxed(vec![Record::unboxed(vec![
+                    Type::Index(0),
+                ])
+                .into()])])
+                .into(),
+                Algebraic::new(vec![Constructor::boxed(vec![Record::unboxed(vec![
+                    Algebraic::new(vec![Constructor::boxed(vec![Record::unboxed(vec![
+                        Type::Index(0),
+                    ])
+                    .into()])])
+                    .into(),
+                ])
+                .into()])])
+                .into(),
+            ),
         ] {
             assert!(TypeEqualityChecker::new(&[]).equal(one, other));
         }
@@ -180,6 +209,10 @@ mod tests {
                 Algebraic::new(vec![Constructor::boxed(vec![Primitive::Float64.into()])]).into(),
                 Algebraic::new(vec![Constructor::unboxed(vec![Primitive::Float64.into()])]).into(),
             ),
+            (
+                Record::boxed(vec![Primitive::Float64.into()]).into(),
+                Record::unboxed(vec![Primitive::Float64.into()]).into(),
+            ),
         ] {
             assert!(!TypeEqualityChecker::new(&[]).equal(one, other));
         }
@@ -0,0 +1,41 @@
+use super::{algebraic::Algebraic, constructor::Constructor, function::Function, type_::Type};
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Lazy {
+    content: Arc<Type>,
+}
+
+impl Lazy {
+    pub fn new(content: impl Into<Type>) -> Self {
+        Self {
+            content: content.into().into(),
+        }
+    }
+
+    pub fn content(&self) -> &Type {
+        &self.content
+    }
+
+    /// Returns a type of thunks which evaluate lazy values. They take a unit
+    /// argument of a singleton enum type.
+    pub fn to_function(&self) -> Function {
+        Function::new(
+            Algebraic::new(vec![Constructor::unboxed(vec![])]),
+            self.content.as_ref().clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::primitive::Primitive, *};
+
+    #[test]
+    fn to_function() {
+        assert_eq!(
+            Lazy::new(Primitive::Float64).to_function().result(),
+            &Primitive::Float64.into()
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use super::type_::Type;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Record {
+    elements: Vec<Type>,
+    boxed: bool,
+}
+
+impl Record {
+    pub const fn new(elements: Vec<Type>, boxed: bool) -> Self {
+        Self { elements, boxed }
+    }
+
+    pub const fn boxed(elements: Vec<Type>) -> Self {
+        Self::new(elements, true)
+    }
+
+    pub const fn unboxed(elements: Vec<Type>) -> Self {
+        Self::new(elements, false)
+    }
+
+    pub fn elements(&self) -> &[Type] {
+        &self.elements
+    }
+
+    pub fn is_boxed(&self) -> bool {
+        self.boxed
+    }
+}
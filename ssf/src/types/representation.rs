@@ -0,0 +1,12 @@
+/// Binary representations of algebraic data types requested by users.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Representation {
+    /// Compilers choose the most compact representation.
+    Compact,
+    /// Tags are always pointer-sized and no niche is used so that foreign
+    /// code can read values directly.
+    FfiCompatible,
+    /// Tags are carried in low bits of pointers to boxed payloads if
+    /// possible. Otherwise, compilers fall back to the compact one.
+    PointerTagged,
+}
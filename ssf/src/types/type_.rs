@@ -1,11 +1,17 @@
-use super::{algebraic::Algebraic, function::Function, primitive::Primitive};
+use super::{
+    algebraic::Algebraic, array::Array, function::Function, lazy::Lazy, primitive::Primitive,
+    record::Record,
+};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Type {
     Algebraic(Algebraic),
+    Array(Array),
     Function(Function),
     Index(usize),
+    Lazy(Lazy),
     Primitive(Primitive),
+    Record(Record),
 }
 
 impl Type {
@@ -20,6 +26,13 @@ impl Type {
         }
     }
 
+    pub fn into_array(self) -> Option<Array> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
     pub fn into_function(self) -> Option<Function> {
         match self {
             Self::Function(function) => Some(function),
@@ -27,12 +40,26 @@ impl Type {
         }
     }
 
+    pub fn into_lazy(self) -> Option<Lazy> {
+        match self {
+            Self::Lazy(lazy) => Some(lazy),
+            _ => None,
+        }
+    }
+
     pub fn into_primitive(self) -> Option<Primitive> {
         match self {
             Self::Primitive(primitive) => Some(primitive),
             _ => None,
         }
     }
+
+    pub fn into_record(self) -> Option<Record> {
+        match self {
+            Self::Record(record) => Some(record),
+            _ => None,
+        }
+    }
 }
 
 impl From<Algebraic> for Type {
@@ -41,14 +68,32 @@ impl From<Algebraic> for Type {
     }
 }
 
+impl From<Array> for Type {
+    fn from(array: Array) -> Self {
+        Self::Array(array)
+    }
+}
+
 impl From<Function> for Type {
     fn from(function: Function) -> Self {
         Self::Function(function)
     }
 }
 
+impl From<Lazy> for Type {
+    fn from(lazy: Lazy) -> Self {
+        Self::Lazy(lazy)
+    }
+}
+
 impl From<Primitive> for Type {
     fn from(primitive: Primitive) -> Self {
         Self::Primitive(primitive)
     }
 }
+
+impl From<Record> for Type {
+    fn from(record: Record) -> Self {
+        Self::Record(record)
+    }
+}
@@ -4,7 +4,7 @@ use crate::types::*;
 use type_unfolder::TypeUnfolder;
 
 pub(crate) fn unfold(algebraic: &Algebraic) -> Algebraic {
-    Algebraic::with_tags(
+    Algebraic::with_representation(
         algebraic
             .constructors()
             .iter()
@@ -22,6 +22,7 @@ pub(crate) fn unfold(algebraic: &Algebraic) -> Algebraic {
                 )
             })
             .collect(),
+        algebraic.representation(),
     )
 }
 
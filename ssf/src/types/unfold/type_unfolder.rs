@@ -16,6 +16,7 @@ impl TypeUnfolder {
     pub fn unfold(&self, type_: &Type) -> Type {
         match type_ {
             Type::Algebraic(algebraic) => self.unfold_algebraic(algebraic).into(),
+            Type::Array(array) => Array::new(self.unfold(array.element())).into(),
             Type::Function(function) => Function::new(
                 self.unfold(function.argument()),
                 self.unfold(function.result()),
@@ -28,14 +29,24 @@ impl TypeUnfolder {
                     Type::Index(*index)
                 }
             }
+            Type::Lazy(lazy) => Lazy::new(self.unfold(lazy.content())).into(),
             Type::Primitive(_) => type_.clone(),
+            Type::Record(record) => Record::new(
+                record
+                    .elements()
+                    .iter()
+                    .map(|element| self.unfold(element))
+                    .collect(),
+                record.is_boxed(),
+            )
+            .into(),
         }
     }
 
     fn unfold_algebraic(&self, algebraic: &Algebraic) -> Algebraic {
         let other = self.increment_index();
 
-        Algebraic::with_tags(
+        Algebraic::with_representation(
             algebraic
                 .constructors()
                 .iter()
@@ -53,6 +64,7 @@ impl TypeUnfolder {
                     )
                 })
                 .collect(),
+            algebraic.representation(),
         )
     }
 